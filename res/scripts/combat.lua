@@ -7,3 +7,8 @@ function apply_damage_with_pierce(pierce_threshold, pre_damage)
 	end
 	return damage, pierce_failed
 end
+
+-- Whether `caster`'s spells may affect `target`; allies are only hit with friendly fire enabled.
+function can_hit(caster, target)
+	return Rules.friendly_fire or caster:alliance() ~= target:alliance()
+end
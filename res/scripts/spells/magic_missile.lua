@@ -1,9 +1,9 @@
 require("combat")
 
 return coroutine.create(function()
-	local target = coroutine.yield({ type = "Cursor", x = caster.x, y = caster.y })
+	local target = coroutine.yield({ type = "Cursor", x = caster.x, y = caster.y, range = range })
 
-	if caster:alliance() == target:alliance() then
+	if not can_hit(caster, target) then
 		Console:print_unimportant("You cannot attack your allies.");
 		return
 	end
@@ -13,8 +13,11 @@ return coroutine.create(function()
 		magnitude - target:stats().resistance
 	)
 
+	if not caster:spend_sp(level) then
+		Console:print_unimportant(caster:replace_nouns("{Address} can't afford to cast that."))
+		return
+	end
 	target.hp = target.hp - damage
-	caster.sp = target.sp - level
 
 	local damage_messages = {
 		"{self_Address}'s magic missile strikes {target_address}",
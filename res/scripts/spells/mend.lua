@@ -0,0 +1,20 @@
+return coroutine.create(function()
+	local target = coroutine.yield({ type = "Cursor", x = caster.x, y = caster.y, range = range })
+
+	local max_hp = target:stats().heart
+	if target.hp >= max_hp then
+		Console:print_unimportant(target:replace_nouns("{Address} {are} not hurt."))
+		return
+	end
+
+	if not caster:spend_sp(level) then
+		Console:print_unimportant(caster:replace_nouns("{Address} can't afford to cast that."))
+		return
+	end
+	target.hp = math.min(target.hp + magnitude, max_hp)
+
+	Console:print(target:replace_prefixed_nouns(
+		"target_",
+		caster:replace_prefixed_nouns("self_", "{self_Address} mends {target_address}'s wounds.")
+	))
+end)
@@ -0,0 +1,19 @@
+return coroutine.create(function()
+	local target = coroutine.yield({ type = "Cursor", x = caster.x, y = caster.y, range = range })
+
+	if not target.downed then
+		Console:print_unimportant(target:replace_nouns("{Address} doesn't need reviving."))
+		return
+	end
+
+	if not caster:spend_sp(level) then
+		Console:print_unimportant(caster:replace_nouns("{Address} can't afford to cast that."))
+		return
+	end
+	target:revive(magnitude)
+
+	Console:print_special(target:replace_prefixed_nouns(
+		"target_",
+		caster:replace_prefixed_nouns("self_", "{self_Address} pulls {target_address} back from the brink!")
+	))
+end)
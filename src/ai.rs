@@ -0,0 +1,562 @@
+//! Decision making for pieces that aren't controlled by the player.
+//!
+//! Each candidate action is scored by a profile's weighted evaluators,
+//! and the highest-scoring action is taken.
+
+use crate::prelude::*;
+use character::{Action, OrdDir};
+use grid::Grid;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+	pub evaluators: Vec<Weighted>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Weighted {
+	pub weight: i32,
+	#[serde(flatten)]
+	pub evaluator: Evaluator,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Evaluator {
+	/// Prefer moves which shorten the path to the nearest hostile piece.
+	Approach,
+	/// Prefer attacking adjacent hostile pieces.
+	Attack,
+	/// Below the given percentage of HP, prefer moves which lengthen the path to any hostile piece.
+	Flee { hp_percent: u32 },
+	/// Stay put unless a hostile piece comes within `radius` tiles.
+	Guard { radius: u32 },
+	/// Prefer casting beneficial spells at (or otherwise moving towards) the most wounded ally below the given percentage of HP.
+	Support { hp_percent: u32 },
+	/// Avoid casting spells that would leave less than the given percentage of SP,
+	/// and avoid overcasting (paying for spells with HP) even more.
+	Conserve { sp_percent: u32 },
+	/// Add some noise to movement.
+	Wander,
+	/// Prefer staying within `distance` steps of the player's piece.
+	Follow { distance: u32 },
+	/// While the party is eating through supplies (see `world::Attrition`),
+	/// prefer picking up food and moving towards it.
+	Forage,
+}
+
+// A step towards the player costs Guard's penalty for moving and forfeits its reward for waiting,
+// so Follow has to outweigh Guard twice over or defensive allies never walk back.
+const DEFENSIVE_FOLLOW: i32 = 5;
+const DEFENSIVE_GUARD: i32 = 2;
+const _: () = assert!(DEFENSIVE_FOLLOW > 2 * DEFENSIVE_GUARD);
+
+/// Instructions the player can give to party members they aren't controlling.
+///
+/// Orders replace the piece's usual AI profile.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Order {
+	/// Go after hostile pieces.
+	Aggressive,
+	/// Stay near the player, fighting whatever comes close.
+	Defensive,
+	/// Stick to the player, only fighting when cornered.
+	Follow,
+	/// Stay put, attacking anything adjacent.
+	Hold,
+}
+
+impl Order {
+	pub const ALL: [Order; 4] = [
+		Order::Aggressive,
+		Order::Defensive,
+		Order::Follow,
+		Order::Hold,
+	];
+
+	/// Step through the orders, including having none, wrapping around at either end.
+	pub fn cycle(order: Option<Order>, step: isize) -> Option<Order> {
+		let choices = Order::ALL.len() as isize + 1;
+		let index = order.map_or(0, |order| {
+			Order::ALL.iter().position(|x| *x == order).unwrap_or(0) as isize + 1
+		});
+		let index = (index + step).rem_euclid(choices) as usize;
+		index.checked_sub(1).map(|i| Order::ALL[i])
+	}
+
+	pub fn profile(self) -> Profile {
+		let evaluators = match self {
+			Order::Aggressive => vec![(10, Evaluator::Attack), (2, Evaluator::Approach)],
+			Order::Defensive => vec![
+				(10, Evaluator::Attack),
+				(DEFENSIVE_FOLLOW, Evaluator::Follow { distance: 2 }),
+				(DEFENSIVE_GUARD, Evaluator::Guard { radius: 2 }),
+				(1, Evaluator::Forage),
+			],
+			Order::Follow => vec![
+				(10, Evaluator::Follow { distance: 1 }),
+				(2, Evaluator::Attack),
+				(3, Evaluator::Forage),
+			],
+			Order::Hold => vec![(10, Evaluator::Attack), (5, Evaluator::Guard { radius: 1 })],
+		};
+		Profile {
+			evaluators: evaluators
+				.into_iter()
+				.map(|(weight, evaluator)| Weighted { weight, evaluator })
+				.collect(),
+		}
+	}
+}
+
+/// How much each other piece has provoked this one.
+///
+/// Damage dealt to a piece adds threat towards the attacker,
+/// and healing a piece adds threat towards the healer for everything hostile to it.
+#[derive(Clone, Debug, Default)]
+pub struct ThreatTable(Vec<(Weak<RefCell<character::Piece>>, u32)>);
+
+impl ThreatTable {
+	pub fn add(&mut self, source: &world::CharacterRef, amount: u32) {
+		self.0.retain(|(x, _)| x.strong_count() > 0);
+		if let Some((_, threat)) = self
+			.0
+			.iter_mut()
+			.find(|(x, _)| std::ptr::eq(x.as_ptr(), Rc::as_ptr(source)))
+		{
+			*threat = threat.saturating_add(amount);
+		} else {
+			self.0.push((Rc::downgrade(source), amount));
+		}
+	}
+
+	pub fn get(&self, source: &world::CharacterRef) -> u32 {
+		self.0
+			.iter()
+			.find(|(x, _)| std::ptr::eq(x.as_ptr(), Rc::as_ptr(source)))
+			.map_or(0, |(_, threat)| *threat)
+	}
+
+	/// Every living source of threat, highest first.
+	pub fn iter(&self) -> impl Iterator<Item = (world::CharacterRef, u32)> {
+		let mut threats = self
+			.0
+			.iter()
+			.filter_map(|(x, threat)| Some((x.upgrade()?, *threat)))
+			.collect::<Vec<_>>();
+		threats.sort_by(|(_, a), (_, b)| b.cmp(a));
+		threats.into_iter()
+	}
+}
+
+/// Something the piece could do this turn.
+#[derive(Clone, Copy, Debug)]
+enum Candidate {
+	Move {
+		dir: OrdDir,
+		x: i32,
+		y: i32,
+	},
+	Attack {
+		dir: OrdDir,
+	},
+	/// Cast the piece's spell at the given index on the ally at `x`, `y`.
+	Cast {
+		spell: usize,
+		x: i32,
+		y: i32,
+	},
+	/// Pick up the food underfoot.
+	PickUp,
+	Wait,
+}
+
+/// Everything the evaluators need to know about the piece's surroundings.
+struct Context<'a> {
+	piece: &'a character::Piece,
+	/// Path distance to the nearest hostile piece, for each tile.
+	threat_distance: Grid<Option<u32>>,
+	/// Path distance to the piece this one is going after, for each tile.
+	target_distance: Grid<Option<u32>>,
+	target: Option<(i32, i32)>,
+	nearest_hostile: Option<u32>,
+	/// The most wounded ally below each `Support` threshold, if there is one.
+	patients: Vec<Patient>,
+	/// Path distance to the player's piece, for each tile, if any evaluator needs it.
+	leader_distance: Option<Grid<Option<u32>>>,
+	/// Path distance to the nearest food, for each tile, if the party is hungry and any evaluator cares.
+	food_distance: Option<Grid<Option<u32>>>,
+}
+
+struct Patient {
+	hp_percent: u32,
+	x: i32,
+	y: i32,
+	/// Path distance to the patient, for each tile.
+	distances: Grid<Option<u32>>,
+}
+
+/// Fill a grid with the number of steps from the nearest source,
+/// moving diagonally and only through passable tiles.
+pub fn distance_map(floor: &Floor, sources: impl Iterator<Item = (i32, i32)>) -> Grid<Option<u32>> {
+	let mut distances = Grid::init(floor.map.rows(), floor.map.cols(), None);
+	let mut queue: VecDeque<((i32, i32), u32)> = sources.map(|x| (x, 0)).collect();
+	while let Some(((x, y), distance)) = queue.pop_front() {
+		let Some(entry) = distances.get_mut(y, x) else {
+			continue;
+		};
+		if entry.is_some() {
+			continue;
+		}
+		*entry = Some(distance);
+		for dir in OrdDir::ALL {
+			let (xoff, yoff) = dir.as_offset();
+			let (x, y) = (x + xoff, y + yoff);
+			if floor.map.get(y, x).is_some_and(|tile| tile.is_passable()) {
+				queue.push_back(((x, y), distance + 1));
+			}
+		}
+	}
+	distances
+}
+
+impl Evaluator {
+	fn score(&self, context: &Context, candidate: Candidate, rng: &mut dyn rand::RngCore) -> i32 {
+		let piece = context.piece;
+		// How much closer a candidate brings this piece to something on the given distance map.
+		let change = |distances: &Grid<Option<u32>>| {
+			let here = distances.get(piece.y, piece.x).copied().flatten();
+			let there = match candidate {
+				Candidate::Move { x, y, .. } => distances.get(y, x).copied().flatten(),
+				Candidate::Attack { .. } => Some(0),
+				Candidate::Cast { .. } | Candidate::PickUp | Candidate::Wait => here,
+			};
+			match (here, there) {
+				(Some(here), Some(there)) => here as i32 - there as i32,
+				_ => 0,
+			}
+		};
+		match self {
+			Evaluator::Approach => change(&context.target_distance),
+			Evaluator::Attack => match candidate {
+				Candidate::Attack { dir } => {
+					let (xoff, yoff) = dir.as_offset();
+					// Any hostile piece is worth hitting, but the target most of all.
+					1 + (context.target == Some((piece.x + xoff, piece.y + yoff))) as i32
+				}
+				Candidate::Move { .. }
+				| Candidate::Cast { .. }
+				| Candidate::PickUp
+				| Candidate::Wait => 0,
+			},
+			Evaluator::Flee { hp_percent } => {
+				let max_hp = piece.sheet.stats().heart.max(1) as i64;
+				if (piece.hp as i64) * 100 < (*hp_percent as i64) * max_hp {
+					-change(&context.threat_distance)
+				} else {
+					0
+				}
+			}
+			Evaluator::Support { hp_percent } => {
+				let Some(patient) = context
+					.patients
+					.iter()
+					.find(|x| x.hp_percent == *hp_percent)
+				else {
+					return 0;
+				};
+				match candidate {
+					// Tending to the patient is worth more than any step towards it.
+					Candidate::Cast { x, y, .. } => 2 * ((x, y) == (patient.x, patient.y)) as i32,
+					Candidate::Move { .. }
+					| Candidate::Attack { .. }
+					| Candidate::PickUp
+					| Candidate::Wait => change(&patient.distances),
+				}
+			}
+			Evaluator::Guard { radius } => {
+				if context.nearest_hostile.is_some_and(|x| x <= *radius) {
+					0
+				} else if matches!(candidate, Candidate::Wait | Candidate::PickUp) {
+					1
+				} else {
+					-1
+				}
+			}
+			Evaluator::Conserve { sp_percent } => {
+				let Candidate::Cast { spell, .. } = candidate else {
+					return 0;
+				};
+				let cost = piece.spells.get(spell).map_or(0, |x| i32::from(x.level));
+				let remaining = piece.sp - cost;
+				let soul = piece.stats().soul as i64;
+				if remaining < 0 {
+					-2
+				} else if (remaining as i64) * 100 < (*sp_percent as i64) * soul {
+					-1
+				} else {
+					0
+				}
+			}
+			Evaluator::Wander => match candidate {
+				Candidate::Move { .. } => rng.gen_range(0..=1),
+				Candidate::Attack { .. }
+				| Candidate::Cast { .. }
+				| Candidate::PickUp
+				| Candidate::Wait => 0,
+			},
+			Evaluator::Follow { distance } => {
+				let Some(distances) = &context.leader_distance else {
+					return 0;
+				};
+				let there = match candidate {
+					Candidate::Move { x, y, .. } => distances.get(y, x).copied().flatten(),
+					Candidate::Attack { .. }
+					| Candidate::Cast { .. }
+					| Candidate::PickUp
+					| Candidate::Wait => distances.get(piece.y, piece.x).copied().flatten(),
+				};
+				// Only straying too far is penalized.
+				there.map_or(0, |there| -(there.saturating_sub(*distance) as i32))
+			}
+			Evaluator::Forage => match (&context.food_distance, candidate) {
+				(None, _) => 0,
+				// Eating is worth more than any step towards food.
+				(Some(_), Candidate::PickUp) => 2,
+				(Some(distances), _) => change(distances),
+			},
+		}
+	}
+}
+
+impl Profile {
+	/// Pick the best action for `character` to take.
+	pub fn consider(
+		&self,
+		world: &world::Manager,
+		character: &world::CharacterRef,
+		rng: &mut dyn rand::RngCore,
+	) -> Action {
+		let piece = character.borrow();
+		let alliance = piece.effective_alliance();
+		// Downed pieces are left to bleed out.
+		let hostiles = world
+			.characters
+			.iter()
+			.filter(|x| {
+				!Rc::ptr_eq(x, character) && {
+					let x = x.borrow();
+					x.effective_alliance() != alliance && x.downed.is_none()
+				}
+			})
+			.collect::<Vec<_>>();
+		let positions = hostiles
+			.iter()
+			.map(|x| {
+				let x = x.borrow();
+				(x.x, x.y)
+			})
+			.collect::<Vec<_>>();
+		let threat_distance = distance_map(&world.current_floor, positions.iter().copied());
+		let nearest_hostile = positions
+			.iter()
+			.map(|&(x, y)| x.abs_diff(piece.x).max(y.abs_diff(piece.y)))
+			.min();
+
+		// Go after whichever reachable hostile has caused the most trouble,
+		// breaking ties (including when nobody has) by distance.
+		let reachable = distance_map(&world.current_floor, [(piece.x, piece.y)].into_iter());
+		let target = hostiles
+			.iter()
+			.zip(&positions)
+			.filter_map(|(hostile, &(x, y))| {
+				// Hostile pieces stand on their own tiles, so check their neighbors instead.
+				let distance = OrdDir::ALL
+					.iter()
+					.filter_map(|dir| {
+						let (xoff, yoff) = dir.as_offset();
+						reachable.get(y + yoff, x + xoff).copied().flatten()
+					})
+					.min()?;
+				Some((piece.threat.get(hostile), distance, (x, y)))
+			})
+			.max_by(|(a_threat, a_distance, _), (b_threat, b_distance, _)| {
+				a_threat.cmp(b_threat).then(b_distance.cmp(a_distance))
+			})
+			.map(|(_, _, position)| position);
+		let target_distance = match target {
+			Some(target) => distance_map(&world.current_floor, [target].into_iter()),
+			None => threat_distance.clone(),
+		};
+
+		let patients = self
+			.evaluators
+			.iter()
+			.filter_map(|x| match x.evaluator {
+				Evaluator::Support { hp_percent } => Some(hp_percent),
+				_ => None,
+			})
+			.filter_map(|hp_percent| {
+				let patient = world
+					.characters
+					.iter()
+					.filter(|x| !Rc::ptr_eq(x, character))
+					.map(|x| x.borrow())
+					.filter(|x| x.effective_alliance() == alliance)
+					.map(|x| {
+						let max_hp = x.sheet.stats().heart.max(1) as i64;
+						(x.hp as i64 * 100 / max_hp, (x.x, x.y))
+					})
+					.filter(|(percent, _)| *percent < hp_percent as i64)
+					.min_by_key(|(percent, _)| *percent)?;
+				let (x, y) = patient.1;
+				Some(Patient {
+					hp_percent,
+					x,
+					y,
+					distances: distance_map(&world.current_floor, [(x, y)].into_iter()),
+				})
+			})
+			.collect();
+
+		let leader_distance = self
+			.evaluators
+			.iter()
+			.any(|x| matches!(x.evaluator, Evaluator::Follow { .. }))
+			.then(|| {
+				world
+					.characters
+					.iter()
+					.filter(|x| !Rc::ptr_eq(x, character))
+					.map(|x| x.borrow())
+					.find(|x| x.controlled_by_player() && x.effective_alliance() == alliance)
+					.map(|leader| {
+						distance_map(&world.current_floor, [(leader.x, leader.y)].into_iter())
+					})
+			})
+			.flatten();
+
+		// Supplies belong to the party, so only its side goes looking for food.
+		let food_distance = (alliance == character::Alliance::Friendly
+			&& world.hungers()
+			&& self
+				.evaluators
+				.iter()
+				.any(|x| matches!(x.evaluator, Evaluator::Forage)))
+		.then(|| {
+			let foods = &world.level.attrition.foods;
+			let food = world
+				.items
+				.iter()
+				.filter(|item| foods.contains_key(&item.item.name))
+				.map(|item| (item.x, item.y))
+				.collect::<Vec<_>>();
+			(!food.is_empty()).then(|| distance_map(&world.current_floor, food.into_iter()))
+		})
+		.flatten();
+
+		let mut candidates = vec![Candidate::Wait];
+		if food_distance
+			.as_ref()
+			.is_some_and(|distances| distances.get(piece.y, piece.x) == Some(&Some(0)))
+		{
+			candidates.push(Candidate::PickUp);
+		}
+		for dir in OrdDir::ALL {
+			let (xoff, yoff) = dir.as_offset();
+			let (x, y) = (piece.x + xoff, piece.y + yoff);
+			match world.get_character_at(x, y) {
+				Some(other) if other.borrow().effective_alliance() != alliance => {
+					candidates.push(Candidate::Attack { dir });
+				}
+				Some(_) if !world.rules.allies_passable() => {}
+				Some(_) | None => {
+					if world
+						.current_floor
+						.map
+						.get(y, x)
+						.is_some_and(|tile| tile.is_passable())
+					{
+						candidates.push(Candidate::Move { dir, x, y });
+					}
+				}
+			}
+		}
+
+		// Beneficial spells may be cast at any ally in reach;
+		// `world::Manager` aims them at whichever of those is most wounded.
+		for (index, spell) in piece.spells.iter().enumerate() {
+			let outlook = spell.outlook(&piece, None, world.level.economy.overcast_cost);
+			if !spell.beneficial || !outlook.castable {
+				continue;
+			}
+			let spell::Parameters::Target { range, .. } = spell.parameters;
+			for ally in &world.characters {
+				if Rc::ptr_eq(ally, character) {
+					continue;
+				}
+				let ally = ally.borrow();
+				if ally.effective_alliance() == alliance
+					&& world
+						.current_floor
+						.within_range((piece.x, piece.y), (ally.x, ally.y), range)
+				{
+					candidates.push(Candidate::Cast {
+						spell: index,
+						x: ally.x,
+						y: ally.y,
+					});
+				}
+			}
+		}
+
+		let context = Context {
+			piece: &piece,
+			threat_distance,
+			target_distance,
+			target,
+			nearest_hostile,
+			patients,
+			leader_distance,
+			food_distance,
+		};
+		// Difficulty makes hostile pieces more or less eager to fight; weights are scaled by 100 to keep precision.
+		let aggression = if piece.effective_alliance() == character::Alliance::Friendly {
+			100
+		} else {
+			world.rules.aggression as i32
+		};
+		let mut best = (i32::MIN, Candidate::Wait);
+		for candidate in candidates {
+			let score = self
+				.evaluators
+				.iter()
+				.map(|x| {
+					let scale = match x.evaluator {
+						Evaluator::Approach | Evaluator::Attack => aggression,
+						Evaluator::Flee { .. }
+						| Evaluator::Guard { .. }
+						| Evaluator::Support { .. }
+						| Evaluator::Conserve { .. }
+						| Evaluator::Wander
+						| Evaluator::Follow { .. }
+						| Evaluator::Forage => 100,
+					};
+					x.weight * scale * x.evaluator.score(&context, candidate, rng)
+				})
+				.sum();
+			if score > best.0 {
+				best = (score, candidate);
+			}
+		}
+
+		match best.1 {
+			Candidate::Move { dir, .. } | Candidate::Attack { dir } => Action::Move(dir),
+			Candidate::Cast { spell, .. } => Action::Cast(piece.spells[spell].clone()),
+			Candidate::PickUp => Action::PickUp,
+			Candidate::Wait => Action::Wait(TURN),
+		}
+	}
+}
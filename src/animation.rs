@@ -0,0 +1,84 @@
+//! Describes how a texture is split into animation frames.
+//!
+//! Metadata lives in `res/animations`, named after the texture it describes.
+//! Textures without metadata are drawn as a single, static frame.
+
+use std::collections::HashMap;
+
+/// What a piece is doing, as far as its animation is concerned.
+#[derive(
+	Clone, Copy, Debug, Default, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+	/// Loops whenever nothing else is playing.
+	#[default]
+	Idle,
+	Walk,
+	Hurt,
+	Cast,
+}
+
+/// A row of frames within a texture.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Strip {
+	/// Which row of frames to use, counting from the top.
+	#[serde(default)]
+	pub row: u32,
+	/// Which column the first frame is in.
+	#[serde(default)]
+	pub column: u32,
+	pub frames: u32,
+	/// How long each frame is shown, in seconds.
+	pub duration: f64,
+}
+
+impl Strip {
+	/// How long it takes to play every frame once, in seconds.
+	pub fn length(&self) -> f64 {
+		self.frames as f64 * self.duration
+	}
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sheet {
+	#[serde(default = "default_frame_size")]
+	pub frame_width: u32,
+	#[serde(default = "default_frame_size")]
+	pub frame_height: u32,
+	/// States without a strip fall back to `idle`.
+	pub states: HashMap<State, Strip>,
+}
+
+fn default_frame_size() -> u32 {
+	16
+}
+
+impl Sheet {
+	pub fn strip(&self, state: State) -> Option<&Strip> {
+		self.states
+			.get(&state)
+			.or_else(|| self.states.get(&State::Idle))
+	}
+
+	/// The position of the frame to show `time` seconds into `state`.
+	///
+	/// Idle loops; every other state holds its last frame.
+	pub fn frame(&self, state: State, time: f64) -> (u32, u32) {
+		let Some(strip) = self.strip(state) else {
+			return (0, 0);
+		};
+		let frame = (time / strip.duration.max(f64::EPSILON)) as u32;
+		let frame = if state == State::Idle {
+			frame % strip.frames.max(1)
+		} else {
+			frame.min(strip.frames.saturating_sub(1))
+		};
+		(
+			(strip.column + frame) * self.frame_width,
+			strip.row * self.frame_height,
+		)
+	}
+}
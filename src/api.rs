@@ -0,0 +1,25 @@
+//! A list of the types outside code (frontends, tools, alternative game loops) is meant to use.
+//!
+//! Every other module is `#[doc(hidden)]`: they stay public because the game's own frontend needs them,
+//! but they're left out of the documentation and may change at any time.
+//! Breaking changes to anything exported here should come with a version bump.
+
+pub use crate::{Error, Result};
+
+// World stepping
+pub use crate::world::{ActionRequest, CharacterRef, Location, Manager as World};
+
+// Actions
+pub use crate::character::{Action, Alliance, OrdDir};
+
+// Snapshots
+// `World` itself is (de)serializable, and serves as the snapshot format.
+pub use crate::character::{Piece, Sheet, StatOutcomes, Stats};
+pub use crate::floor::{Floor, Tile};
+
+// Resources
+pub use crate::attack::Attack;
+pub use crate::resource::{Handle as ResourceHandle, Manager as Resources};
+pub use crate::spell::{Affinity, Outlook as SpellOutlook, Spell};
+pub use crate::status::Status;
+pub use crate::vault::Vault;
@@ -9,6 +9,9 @@ pub struct Attack {
 	pub magnitude: Expression,
 	pub on_use: script::MaybeInline,
 	pub messages: Messages,
+	/// Played when the attack is used.
+	#[serde(default)]
+	pub sound: Option<String>,
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
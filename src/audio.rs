@@ -0,0 +1,218 @@
+//! Sound effects and music.
+//!
+//! Anything can request a sound through a `Handle` (including scripts, as the `Audio` global),
+//! and the `Mixer` plays whatever has been requested once per frame.
+
+use crate::prelude::*;
+use sdl2::mixer::{Channel, Chunk, Music};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tracing::{error, warn};
+
+/// How long the current track takes to fade out, and the next to fade in.
+const CROSSFADE_MS: i32 = 1000;
+/// File extensions tried, in order, when loading a sound or track.
+const EXTENSIONS: [&str; 2] = ["ogg", "wav"];
+
+#[derive(Clone, Debug)]
+pub enum Cue {
+	/// Play a sound from `res/sounds/` once.
+	Sound(String),
+	/// Switch to a track from `res/music/`, looping it until another is requested.
+	Music(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Handle {
+	sender: mpsc::Sender<Cue>,
+}
+
+impl Default for Handle {
+	/// A handle that isn't connected to a mixer, for when audio is unavailable.
+	fn default() -> Self {
+		Self {
+			sender: mpsc::channel().0,
+		}
+	}
+}
+
+impl Handle {
+	pub fn play(&self, sound: &str) {
+		let _ = self.sender.send(Cue::Sound(sound.into()));
+	}
+
+	pub fn music(&self, track: &str) {
+		let _ = self.sender.send(Cue::Music(track.into()));
+	}
+
+	/// Switch tracks in response to something happening on the current floor.
+	pub fn react(&self, event: &world::Event, biome: &Biome) {
+		let track = match event {
+			world::Event::FloorEntered(_) | world::Event::BossDefeated(_) => &biome.music,
+			world::Event::BossStarted(_) => &biome.boss_music,
+			world::Event::Killed(_)
+			| world::Event::Talked(_)
+			| world::Event::HpChanged { .. }
+			| world::Event::Moved(_)
+			| world::Event::SpellCast(_) => return,
+		};
+		if let Some(track) = track {
+			self.music(track);
+		}
+	}
+}
+
+impl mlua::UserData for Handle {
+	fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+		methods.add_method("play", |_, this, sound: String| {
+			this.play(&sound);
+			Ok(())
+		});
+		methods.add_method("music", |_, this, track: String| {
+			this.music(&track);
+			Ok(())
+		});
+	}
+}
+
+/// Volume levels, as percentages.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Volume {
+	pub master: u8,
+	pub sound: u8,
+	pub music: u8,
+}
+
+impl Default for Volume {
+	fn default() -> Self {
+		Self {
+			master: 100,
+			sound: 100,
+			music: 70,
+		}
+	}
+}
+
+impl Volume {
+	fn scale(&self, percent: u8) -> i32 {
+		sdl2::mixer::MAX_VOLUME * self.master.min(100) as i32 / 100 * percent.min(100) as i32 / 100
+	}
+}
+
+pub struct Mixer {
+	directory: PathBuf,
+	handle: Handle,
+	receiver: mpsc::Receiver<Cue>,
+	/// Sounds that failed to load are remembered as `None`, so that they're only reported once.
+	sounds: HashMap<String, Option<Chunk>>,
+	track: Option<(String, Music<'static>)>,
+	/// The track to start once the current one finishes fading out.
+	next_track: Option<String>,
+	_context: sdl2::mixer::Sdl2MixerContext,
+}
+
+fn find(directory: &Path, key: &str) -> Option<PathBuf> {
+	EXTENSIONS
+		.iter()
+		.map(|extension| directory.join(format!("{key}.{extension}")))
+		.find(|path| path.exists())
+}
+
+impl Mixer {
+	/// Open the audio device, loading sounds and music from `directory` as they're requested.
+	///
+	/// # Errors
+	///
+	/// Returns an error if SDL fails to open the audio device.
+	pub fn open(directory: impl Into<PathBuf>) -> Result<Self> {
+		let context = sdl2::mixer::init(sdl2::mixer::InitFlag::OGG).map_err(crate::Error::Sdl)?;
+		sdl2::mixer::open_audio(
+			sdl2::mixer::DEFAULT_FREQUENCY,
+			sdl2::mixer::DEFAULT_FORMAT,
+			sdl2::mixer::DEFAULT_CHANNELS,
+			1024,
+		)
+		.map_err(crate::Error::Sdl)?;
+		sdl2::mixer::allocate_channels(16);
+		let (sender, receiver) = mpsc::channel();
+		Ok(Self {
+			directory: directory.into(),
+			handle: Handle { sender },
+			receiver,
+			sounds: HashMap::new(),
+			track: None,
+			next_track: None,
+			_context: context,
+		})
+	}
+
+	pub fn handle(&self) -> Handle {
+		self.handle.clone()
+	}
+
+	/// Play any requested sounds, and advance music transitions.
+	pub fn update(&mut self, volume: &Volume) {
+		Channel::all().set_volume(volume.scale(volume.sound));
+		Music::set_volume(volume.scale(volume.music));
+
+		for cue in self.receiver.try_iter().collect::<Vec<_>>() {
+			match cue {
+				Cue::Sound(key) => self.play(&key),
+				Cue::Music(key) => {
+					let current = self
+						.next_track
+						.as_ref()
+						.or(self.track.as_ref().map(|x| &x.0));
+					if current != Some(&key) {
+						if Music::is_playing() {
+							let _ = Music::fade_out(CROSSFADE_MS);
+						}
+						self.next_track = Some(key);
+					}
+				}
+			}
+		}
+
+		if !Music::is_playing() {
+			if let Some(key) = self.next_track.take() {
+				self.start_track(key);
+			}
+		}
+	}
+
+	fn play(&mut self, key: &str) {
+		let directory = self.directory.join("sounds");
+		let chunk = self.sounds.entry(key.into()).or_insert_with(|| {
+			let Some(path) = find(&directory, key) else {
+				warn!("sound {key} not found");
+				return None;
+			};
+			Chunk::from_file(&path)
+				.map_err(|msg| error!("failed to load {}: {msg}", path.display()))
+				.ok()
+		});
+		if let Some(chunk) = chunk {
+			// Running out of channels just means that the sound is skipped.
+			let _ = Channel::all().play(chunk, 0);
+		}
+	}
+
+	fn start_track(&mut self, key: String) {
+		self.track = None;
+		let Some(path) = find(&self.directory.join("music"), &key) else {
+			warn!("music {key} not found");
+			return;
+		};
+		match Music::from_file(&path) {
+			Ok(music) => {
+				if let Err(msg) = music.fade_in(-1, CROSSFADE_MS) {
+					error!("failed to play {}: {msg}", path.display());
+				}
+				self.track = Some((key, music));
+			}
+			Err(msg) => error!("failed to load {}: {msg}", path.display()),
+		}
+	}
+}
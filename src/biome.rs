@@ -0,0 +1,131 @@
+use crate::prelude::*;
+
+/// A themed area spanning a range of floors.
+///
+/// Biomes decide how floors are laid out, what lives on them, and how they look.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Biome {
+	/// The shallowest floor this biome may appear on.
+	#[serde(default)]
+	pub min_depth: usize,
+	/// The deepest floor this biome may appear on.
+	#[serde(default)]
+	pub max_depth: Option<usize>,
+	pub vault_set: String,
+	/// Overrides the vault set's spawn table, if present.
+	#[serde(default)]
+	pub spawns: Option<String>,
+	pub palette: String,
+	/// Textures for the floor's tiles; without one, tiles are drawn with the palette's flat colors.
+	#[serde(default)]
+	pub tileset: Option<String>,
+	/// The color of unlit tiles; without one, floors are fully lit.
+	#[serde(default)]
+	pub ambient: Option<Color>,
+	/// Flavor text printed to the console when entering a floor of this biome.
+	#[serde(default)]
+	pub ambience: Vec<String>,
+	/// Track played while exploring this biome.
+	#[serde(default)]
+	pub music: Option<String>,
+	/// Track played while a boss is alive on this biome's floors.
+	#[serde(default)]
+	pub boss_music: Option<String>,
+}
+
+impl Biome {
+	pub fn contains(&self, depth: usize) -> bool {
+		self.min_depth <= depth && !self.max_depth.is_some_and(|max| depth > max)
+	}
+}
+
+/// Colors used to draw a floor's tiles.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Palette {
+	pub floor: Color,
+	pub wall: Color,
+	pub exit: Color,
+	pub rest: Color,
+}
+
+impl Default for Palette {
+	fn default() -> Self {
+		Self {
+			floor: (20, 20, 20, 255),
+			wall: (255, 255, 255, 255),
+			exit: (255, 255, 255, 255),
+			rest: (255, 160, 64, 255),
+		}
+	}
+}
+
+/// Where to find a tile within a tileset's texture, in tiles.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TileCoord {
+	pub row: u32,
+	pub column: u32,
+}
+
+/// Textures used to draw a floor's tiles, with walls that connect to their neighbors.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tileset {
+	pub texture: String,
+	#[serde(default = "default_tile_size")]
+	pub tile_size: u32,
+	/// Interchangeable floor tiles; each tile picks one based on its position.
+	pub floors: Vec<TileCoord>,
+	/// The first of 16 wall tiles in a row, one for every combination of neighboring walls.
+	///
+	/// Each tile's offset from the first is a bitmask of which sides connect to another wall:
+	/// 1 for up, 2 for right, 4 for down, and 8 for left.
+	pub walls: TileCoord,
+	/// The first of 4 tiles in a row, drawn over walls to fill in inner corners:
+	/// up-right, down-right, down-left, then up-left.
+	#[serde(default)]
+	pub corners: Option<TileCoord>,
+}
+
+fn default_tile_size() -> u32 {
+	16
+}
+
+impl Tileset {
+	/// Pick a floor variant for the tile at (`x`, `y`).
+	///
+	/// This is stable, so tiles don't change as the floor is redrawn.
+	pub fn floor(&self, x: i32, y: i32) -> Option<TileCoord> {
+		let hash = (x as u32)
+			.wrapping_mul(73_856_093)
+			.wrapping_add((y as u32).wrapping_mul(19_349_663));
+		self.floors
+			.get((hash as usize).checked_rem(self.floors.len())?)
+			.copied()
+	}
+
+	pub fn wall(&self, mask: u8) -> TileCoord {
+		TileCoord {
+			row: self.walls.row,
+			column: self.walls.column + u32::from(mask & 0xF),
+		}
+	}
+
+	/// Inner corners to draw over the wall at (`x`, `y`).
+	pub fn corners(&self, floor: &Floor, x: i32, y: i32) -> Vec<TileCoord> {
+		let Some(first) = self.corners else {
+			return Vec::new();
+		};
+		let wall = |x, y| floor.map.get(y, x) == Some(&floor::Tile::Wall);
+		[(1, -1), (1, 1), (-1, 1), (-1, -1)]
+			.into_iter()
+			.enumerate()
+			.filter(|(_, (dx, dy))| wall(x + dx, y) && wall(x, y + dy) && !wall(x + dx, y + dy))
+			.map(|(i, _)| TileCoord {
+				row: first.row,
+				column: first.column + i as u32,
+			})
+			.collect()
+	}
+}
@@ -1,6 +1,8 @@
 use crate::prelude::*;
 use nouns::StrExt;
 use std::{collections::HashMap, rc::Rc};
+use tracing::error;
+use uuid::Uuid;
 
 mod piece {
 	use super::*;
@@ -26,13 +28,24 @@ mod piece {
 	/// While fields of `Piece` are settable from Lua,
 	/// fields of `Sheet` and `Stats` are not.
 	/// This method circumvents that.
-	fn force_level(_lua: &mlua::Lua, this: &mut Piece, _: ()) -> mlua::Result<()> {
-		this.sheet.level = this.sheet.level.saturating_add(1);
+	fn force_level(lua: &mlua::Lua, this: &mut Piece, _: ()) -> mlua::Result<()> {
+		let resources = lua.globals().get::<&str, resource::Catalog>("Resources")?;
+		this.level_up(&resources);
 		Ok(())
 	}
 
+	/// Teach the piece a spell (by resource key), returning false if it already knew it.
+	pub fn learn_spell(lua: &mlua::Lua, this: &mut Piece, key: String) -> mlua::Result<bool> {
+		let resources = lua.globals().get::<&str, resource::Catalog>("Resources")?;
+		let spell = resources
+			.get_spell(&key)
+			.map_err(mlua::Error::external)?
+			.clone();
+		Ok(this.learn(learnset::Ability::Spell { key, spell }, None))
+	}
+
 	pub fn alliance(_lua: &mlua::Lua, this: &mut Piece, _: ()) -> mlua::Result<u32> {
-		Ok(this.alliance as u32)
+		Ok(this.effective_alliance() as u32)
 	}
 
 	/// Initializes an effect with the given magnitude, or adds the magnitude to the effect if it already exists.
@@ -44,19 +57,105 @@ mod piece {
 		let statuses = lua
 			.globals()
 			.get::<&str, resource::Handle<Status>>("Status")?;
-		let Some(status) = statuses.0.get(key.as_str()).cloned() else {
-			return Err(mlua::Error::external(resource::Error::NotFound(key)));
+		this.inflict(&statuses, key, magnitude)
+			.map_err(mlua::Error::external)
+	}
+
+	/// Remove a status, returning false if the piece didn't have it.
+	pub fn cure(_lua: &mlua::Lua, this: &mut Piece, key: String) -> mlua::Result<bool> {
+		Ok(this.cure(&key))
+	}
+
+	/// Transform into another sheet until the given status is removed.
+	///
+	/// The status is inflicted (with an optional magnitude) as part of the transformation.
+	pub fn polymorph(
+		lua: &mlua::Lua,
+		this: &mut Piece,
+		(sheet, status, magnitude): (String, String, Option<u32>),
+	) -> mlua::Result<()> {
+		let resources = lua.globals().get::<&str, resource::Catalog>("Resources")?;
+		let load = || -> Result<_> {
+			let sheet = resources.get_sheet(&sheet)?.clone();
+			let attacks = sheet
+				.attacks
+				.iter()
+				.map(|x| resources.get_attack(x).cloned())
+				.collect::<Result<_>>()?;
+			let spells = sheet
+				.spells
+				.iter()
+				.map(|x| resources.get_spell(x).cloned())
+				.collect::<Result<_>>()?;
+			Ok((sheet, attacks, spells))
 		};
-		let entry = this
-			.statuses
-			.entry(key.into_boxed_str())
-			.or_insert_with(|| status);
-		if let Some(magnitude) = magnitude {
-			entry.add_magnitude(magnitude);
-		}
+		let (sheet, attacks, spells) = load().map_err(mlua::Error::external)?;
+		inflict(lua, this, (status.clone(), magnitude))?;
+		this.transform(sheet, attacks, spells, status.into_boxed_str());
 		Ok(())
 	}
 
+	/// Predict the outcome of casting one of this piece's spells (by resource key), optionally at a target.
+	///
+	/// Returns nil if the piece doesn't know the spell.
+	pub fn spell_outlook(
+		lua: &mlua::Lua,
+		this: &mut Piece,
+		(key, target): (String, Option<mlua::UserDataRef<world::CharacterRef>>),
+	) -> mlua::Result<Option<spell::Outlook>> {
+		let Some(index) = this.sheet.spells.iter().position(|x| *x == key) else {
+			return Ok(None);
+		};
+		let Some(spell) = this.spells.get(index) else {
+			return Ok(None);
+		};
+		// A piece targeting itself is already borrowed as `this`, so it's treated as having no target.
+		let target = target
+			.as_ref()
+			.map(|target| target.try_borrow())
+			.transpose()
+			.ok()
+			.flatten();
+		Ok(Some(spell.outlook(
+			this,
+			target.as_deref(),
+			overcast_cost(lua),
+		)))
+	}
+
+	/// Spend SP, paying for any shortfall with HP if the level allows overcasting.
+	///
+	/// Returns false, spending nothing, if the piece can't afford it.
+	pub fn spend_sp(lua: &mlua::Lua, this: &mut Piece, amount: u32) -> mlua::Result<bool> {
+		Ok(this.spend_sp(amount, overcast_cost(lua)))
+	}
+
+	/// Bring a downed piece back to its feet with `hp` (or all of its heart).
+	///
+	/// Returns false if the piece wasn't downed.
+	pub fn revive(_lua: &mlua::Lua, this: &mut Piece, hp: Option<u32>) -> mlua::Result<bool> {
+		Ok(this.revive(hp))
+	}
+
+	/// Returns every piece that has provoked this one, as `{ piece, threat }` tables, highest threat first.
+	pub fn threats<'lua>(
+		lua: &'lua mlua::Lua,
+		this: &mut Piece,
+		_: (),
+	) -> mlua::Result<mlua::Table<'lua>> {
+		lua.create_sequence_from(
+			this.threat
+				.iter()
+				.map(|(piece, threat)| {
+					let entry = lua.create_table()?;
+					entry.set("piece", piece)?;
+					entry.set("threat", threat)?;
+					Ok(entry)
+				})
+				.collect::<mlua::Result<Vec<_>>>()?,
+		)
+	}
+
 	#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, alua::UserData)]
 	#[alua(
 		method = replace_nouns,
@@ -65,8 +164,19 @@ mod piece {
 		method = stats,
 		method = alliance,
 		method = inflict,
+		method = cure,
+		method = polymorph,
+		method = spell_outlook,
+		method = learn_spell,
+		method = spend_sp,
+		method = threats,
+		method = revive,
 	)]
 	pub struct Piece {
+		/// Identifies this piece for as long as it exists, even across saves and snapshots.
+		#[alua(as_lua = "string", get)]
+		#[serde(default = "Uuid::new_v4")]
+		pub id: Uuid,
 		#[alua(get)]
 		pub sheet: Sheet,
 
@@ -74,6 +184,13 @@ mod piece {
 		pub hp: i32,
 		#[alua(get, set)]
 		pub sp: i32,
+		/// Turns left before a fallen piece bleeds out, while it's downed.
+		#[alua(get)]
+		#[serde(default)]
+		pub downed: Option<u32>,
+		/// Whoever brought this piece to 0 HP, credited once it actually dies.
+		#[serde(default)]
+		pub defeated_by: Option<String>,
 
 		pub statuses: HashMap<Box<str>, Status>,
 		pub attacks: Vec<Rc<Attack>>,
@@ -86,14 +203,72 @@ mod piece {
 		pub next_action: Option<Action>,
 		#[alua(get, set)]
 		pub player_controlled: bool,
+		/// Set by the player for party members they aren't controlling, overriding the sheet's AI.
+		#[serde(default)]
+		pub order: Option<ai::Order>,
 		pub alliance: Alliance,
+		/// How long this piece must wait before its next turn.
+		///
+		/// `world::Manager` keeps its characters sorted by this value,
+		/// so it should only be modified through the manager.
+		#[alua(get)]
+		#[serde(default)]
+		pub action_delay: Aut,
+		/// Sheets that have been temporarily replaced, oldest first.
+		#[serde(default)]
+		pub transformations: Vec<Transformation>,
+		/// How many of the sheet's phases have begun.
+		#[alua(get)]
+		#[serde(default)]
+		pub phase: usize,
+		/// A tile this piece is walking towards, one turn at a time.
+		///
+		/// Only used by player-controlled pieces; cleared once reached or interrupted.
+		#[serde(default)]
+		pub chase_point: Option<(i32, i32)>,
+		/// Repeats actions on the player's behalf until interrupted.
+		#[serde(skip)]
+		pub autopilot: Option<Autopilot>,
+		/// The last spell this piece cast, and who it was aimed at (if anyone).
+		#[serde(skip)]
+		pub last_cast: Option<(Rc<Spell>, Option<Uuid>)>,
+		/// Items being worn, at most one per slot.
+		#[serde(default)]
+		pub equipment: Vec<item::Equipped>,
+		/// Which pieces this one is most eager to fight.
+		#[serde(skip)]
+		pub threat: ai::ThreatTable,
+		/// Status hooks waiting to be run.
+		#[serde(skip)]
+		pub pending_hooks: Vec<status::Pending>,
+		/// Newly learned abilities waiting to be announced.
+		#[serde(skip)]
+		pub pending_lessons: Vec<learnset::Lesson>,
 	}
 }
 
 pub use piece::Piece;
 
+/// The current level's overcast rule, as seen by scripts.
+fn overcast_cost(lua: &mlua::Lua) -> u32 {
+	lua.globals()
+		.get::<&str, query::Query>("World")
+		.map_or(0, |world| world.economy().overcast_cost)
+}
+
 impl expression::Variables for Piece {
 	fn get(&self, s: &str) -> Result<expression::Integer, expression::Error> {
+		if let Some(key) = s.strip_prefix("has(").and_then(|s| s.strip_suffix(')')) {
+			return Ok(self.statuses.contains_key(key).into());
+		}
+		// Statuses the piece doesn't have count as zero,
+		// so that formulas can scale with them without checking `has` first.
+		if let Some((key, variable)) = s.split_once('.') {
+			return match self.statuses.get(key) {
+				Some(status) => status.get(variable),
+				None => Ok(0),
+			};
+		}
 		match s {
 			"hp" => Ok(self.hp as expression::Integer),
 			"sp" => Ok(self.sp as expression::Integer),
@@ -104,6 +279,11 @@ impl expression::Variables for Piece {
 
 impl Piece {
 	pub fn new(sheet: Sheet, resources: &resource::Manager) -> Result<Self> {
+		Self::from_catalog(sheet, &resources.catalog())
+	}
+
+	/// Like `new`, but usable where only the `'static` resource catalog is available (such as from Lua).
+	pub fn from_catalog(sheet: Sheet, resources: &resource::Catalog) -> Result<Self> {
 		let stats = sheet.stats();
 		let hp = stats.heart as i32;
 		let sp = stats.soul as i32;
@@ -118,10 +298,13 @@ impl Piece {
 			.map(|x| resources.get_spell(x).cloned())
 			.collect::<Result<_>>()?;
 
-		Ok(Self {
+		let mut piece = Self {
+			id: Uuid::new_v4(),
 			sheet,
 			hp,
 			sp,
+			downed: None,
+			defeated_by: None,
 			statuses: HashMap::new(),
 			attacks,
 			spells,
@@ -129,14 +312,210 @@ impl Piece {
 			y: 0,
 			next_action: None,
 			player_controlled: false,
+			order: None,
 			alliance: Alliance::default(),
-		})
+			action_delay: 0,
+			transformations: Vec::new(),
+			phase: 0,
+			chase_point: None,
+			autopilot: None,
+			last_cast: None,
+			equipment: Vec::new(),
+			threat: ai::ThreatTable::default(),
+			pending_hooks: Vec::new(),
+			pending_lessons: Vec::new(),
+		};
+		// Pieces start out knowing everything from the levels they've skipped.
+		if let Some(key) = &piece.sheet.learnset {
+			let learnset = resources.get_learnset(key)?;
+			for level in 1..=piece.sheet.level {
+				for ability in learnset.abilities(level, resources) {
+					piece.learn(ability, None);
+				}
+			}
+			piece.pending_lessons.clear();
+		}
+		Ok(piece)
+	}
+
+	/// Gain a level, along with anything the sheet's learnset teaches at that level.
+	pub fn level_up(&mut self, resources: &resource::Catalog) {
+		self.sheet.level = self.sheet.level.saturating_add(1);
+		let Some(key) = self.sheet.learnset.clone() else {
+			return;
+		};
+		match resources.get_learnset(&key) {
+			Ok(learnset) => {
+				for ability in learnset.abilities(self.sheet.level, resources) {
+					self.learn(ability, learnset.on_learn.clone());
+				}
+			}
+			Err(msg) => error!("failed to open learnset {key}: {msg}"),
+		}
+	}
+
+	/// Learn a spell or skill, queuing an announcement (and `on_learn`) for `world::Manager`.
+	///
+	/// Returns false if the piece already knew the spell or already had a minor skill.
+	pub fn learn(
+		&mut self,
+		ability: learnset::Ability,
+		on_learn: Option<script::MaybeInline>,
+	) -> bool {
+		let learned = match &ability {
+			learnset::Ability::Spell { key, spell } => {
+				// `sheet.spells` and `spells` are kept in the same order.
+				let known = self.sheet.spells.contains(key);
+				if !known {
+					self.sheet.spells.push(key.clone());
+					self.spells.push(spell.clone());
+				}
+				!known
+			}
+			learnset::Ability::Skill(skill) => self.sheet.skillset.unlock_minor(*skill),
+		};
+		if learned {
+			self.pending_lessons
+				.push(learnset::Lesson { ability, on_learn });
+		}
+		learned
+	}
+
+	/// Initializes an effect with the given magnitude, or adds the magnitude to the effect if it already exists.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the status could not be found.
+	pub fn inflict(
+		&mut self,
+		statuses: &resource::Handle<Status>,
+		key: String,
+		magnitude: Option<u32>,
+	) -> Result<()> {
+		let Some(status) = statuses.0.get(key.as_str()).cloned() else {
+			return Err(resource::Error::NotFound(key).into());
+		};
+		let key = key.into_boxed_str();
+		if !self.statuses.contains_key(&key) && status.has_hook(status::Hook::Inflict) {
+			self.pending_hooks
+				.push(status::Pending::Active(key.clone(), status::Hook::Inflict));
+		}
+		let entry = self.statuses.entry(key).or_insert_with(|| status);
+		if let Some(magnitude) = magnitude {
+			entry.add_magnitude(magnitude);
+		}
+		Ok(())
+	}
+
+	/// Remove a status, returning false if the piece didn't have it.
+	pub fn cure(&mut self, key: &str) -> bool {
+		let Some(status) = self.statuses.remove(key) else {
+			return false;
+		};
+		if status.has_hook(status::Hook::Remove) {
+			self.pending_hooks.push(status::Pending::Removed(
+				Box::new(status),
+				status::Hook::Remove,
+			));
+		}
+		self.revert_expired_transformations();
+		true
+	}
+
+	/// Remove every status matching `expired`, queuing their expiration hooks.
+	fn expire_statuses(&mut self, mut expired: impl FnMut(&mut Status) -> bool) {
+		let keys: Vec<_> = self
+			.statuses
+			.iter_mut()
+			.filter_map(|(key, status)| expired(status).then(|| key.clone()))
+			.collect();
+		for key in keys {
+			let Some(status) = self.statuses.remove(&key) else {
+				continue;
+			};
+			for hook in [status::Hook::Expire, status::Hook::Remove] {
+				if status.has_hook(hook) {
+					self.pending_hooks
+						.push(status::Pending::Removed(Box::new(status.clone()), hook));
+				}
+			}
+		}
 	}
 
 	pub fn new_turn(&mut self) {
-		// Remove any status effects with the duration of one turn.
-		self.statuses
-			.retain(|_, status| !matches!(status.duration, status::Duration::Turn));
+		for (key, status) in &self.statuses {
+			if status.has_hook(status::Hook::Turn) {
+				self.pending_hooks
+					.push(status::Pending::Active(key.clone(), status::Hook::Turn));
+			}
+		}
+		// Remove any status effects with the duration of one turn,
+		// and count down those that last for several.
+		self.expire_statuses(|status| match &mut status.duration {
+			status::Duration::Turn => true,
+			status::Duration::Turns(turns) => {
+				*turns = turns.saturating_sub(1);
+				*turns == 0
+			}
+			status::Duration::Rest => false,
+		});
+		self.revert_expired_transformations();
+	}
+
+	/// Whether `amount` SP can be spent, overcasting at `overcast_cost` HP per missing SP.
+	///
+	/// Overcasting can't bring a piece below 1 HP.
+	pub fn can_afford(&self, amount: u32, overcast_cost: u32) -> bool {
+		let shortfall = (amount as i32 - self.sp.max(0)).max(0);
+		shortfall == 0 || (overcast_cost > 0 && self.hp > shortfall * overcast_cost as i32)
+	}
+
+	/// Spend SP, making up any shortfall with HP at `overcast_cost` HP per SP.
+	///
+	/// Returns false, spending nothing, if the piece can't afford it.
+	pub fn spend_sp(&mut self, amount: u32, overcast_cost: u32) -> bool {
+		if !self.can_afford(amount, overcast_cost) {
+			return false;
+		}
+		let shortfall = (amount as i32 - self.sp.max(0)).max(0);
+		self.sp -= amount as i32 - shortfall;
+		self.hp -= shortfall * overcast_cost as i32;
+		true
+	}
+
+	pub fn is_charmed(&self) -> bool {
+		self.statuses.values().any(Status::charms)
+	}
+
+	/// The alliance this piece is currently fighting for,
+	/// which may differ from `alliance` if it has been charmed.
+	pub fn effective_alliance(&self) -> Alliance {
+		if self.is_charmed() {
+			match self.alliance {
+				Alliance::Friendly => Alliance::Enemy,
+				Alliance::Enemy => Alliance::Friendly,
+			}
+		} else {
+			self.alliance
+		}
+	}
+
+	/// Whether the player may choose this piece's actions.
+	///
+	/// Charmed pieces act on their own, even if they belong to the player.
+	pub fn controlled_by_player(&self) -> bool {
+		self.player_controlled && !self.is_charmed()
+	}
+
+	/// Wear an item, returning whatever was previously worn in the same slot.
+	pub fn equip(&mut self, equipped: item::Equipped) -> Option<item::Equipped> {
+		let previous = self
+			.equipment
+			.iter()
+			.position(|x| x.slot == equipped.slot)
+			.map(|index| self.equipment.remove(index));
+		self.equipment.push(equipped);
+		previous
 	}
 
 	pub fn rest(&mut self) {
@@ -144,8 +523,19 @@ impl Piece {
 		self.restore_hp(stats.heart / 2);
 		self.restore_sp(stats.soul);
 		// Remove any status effects lasting until the next rest.
-		self.statuses
-			.retain(|_, status| !matches!(status.duration, status::Duration::Rest));
+		self.expire_statuses(|status| matches!(status.duration, status::Duration::Rest));
+		self.revert_expired_transformations();
+		self.downed = None;
+	}
+
+	/// Returns false if the piece wasn't downed.
+	pub fn revive(&mut self, hp: Option<u32>) -> bool {
+		if self.downed.take().is_none() {
+			return false;
+		}
+		self.hp = 0;
+		self.restore_hp(hp.unwrap_or(self.stats().heart).max(1));
+		true
 	}
 
 	pub fn restore_hp(&mut self, amount: u32) {
@@ -155,6 +545,74 @@ impl Piece {
 	pub fn restore_sp(&mut self, amount: u32) {
 		self.sp = i32::min(self.sp + amount as i32, self.stats().soul as i32);
 	}
+
+	/// Replace this piece's sheet until `status` is removed.
+	///
+	/// The piece keeps its name, level, and experience,
+	/// and its HP and SP are scaled to keep the same ratio.
+	pub fn transform(
+		&mut self,
+		mut sheet: Sheet,
+		attacks: Vec<Rc<Attack>>,
+		spells: Vec<Rc<Spell>>,
+		status: Box<str>,
+	) {
+		let previous_stats = self.stats();
+		sheet.nouns = self.sheet.nouns.clone();
+		sheet.level = self.sheet.level;
+		sheet.experience = self.sheet.experience;
+		self.transformations.push(Transformation {
+			status,
+			sheet: std::mem::replace(&mut self.sheet, sheet),
+			attacks: std::mem::replace(&mut self.attacks, attacks),
+			spells: std::mem::replace(&mut self.spells, spells),
+		});
+		self.rescale_resources(previous_stats);
+	}
+
+	/// Undo any transformations whose status has been removed.
+	///
+	/// Transformations stacked on top of an expired one are discarded along with it.
+	pub fn revert_expired_transformations(&mut self) {
+		while let Some(index) = self
+			.transformations
+			.iter()
+			.position(|x| !self.statuses.contains_key(&x.status))
+		{
+			let previous_stats = self.stats();
+			let Some(original) = self.transformations.drain(index..).next() else {
+				break;
+			};
+			let mut sheet = original.sheet;
+			// Progress made while transformed still counts.
+			sheet.level = self.sheet.level;
+			sheet.experience = self.sheet.experience;
+			self.sheet = sheet;
+			self.attacks = original.attacks;
+			self.spells = original.spells;
+			self.rescale_resources(previous_stats);
+		}
+	}
+
+	fn rescale_resources(&mut self, previous_stats: Stats) {
+		let stats = self.stats();
+		let rescale = |value: i32, previous: u32, current: u32| {
+			(value as i64 * current as i64 / previous.max(1) as i64) as i32
+		};
+		self.hp = rescale(self.hp, previous_stats.heart, stats.heart);
+		self.sp = rescale(self.sp, previous_stats.soul, stats.soul);
+	}
+}
+
+/// A sheet that has been temporarily replaced by another, such as when polymorphed.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Transformation {
+	/// The status holding this transformation in place.
+	/// Once it's removed, the piece reverts to `sheet`.
+	pub status: Box<str>,
+	pub sheet: Sheet,
+	pub attacks: Vec<Rc<Attack>>,
+	pub spells: Vec<Rc<Spell>>,
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -169,8 +627,16 @@ impl Piece {
 		self.stat_outcomes().stats
 	}
 
+	/// All of the experience this piece has earned, with each level worth 100.
+	pub fn total_experience(&self) -> u32 {
+		self.sheet.level * 100 + self.sheet.experience
+	}
+
 	pub fn stat_outcomes(&self) -> StatOutcomes {
-		let buffs = Stats::default();
+		let buffs = self
+			.equipment
+			.iter()
+			.fold(Stats::default(), |buffs, x| buffs + x.bonuses);
 		let mut debuffs = Stats::default();
 
 		for debuff in self.statuses.values().filter_map(|x| x.on_debuff()) {
@@ -193,7 +659,7 @@ impl Piece {
 	}
 }
 
-#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum OrdDir {
 	Up,
 	UpRight,
@@ -206,6 +672,17 @@ pub enum OrdDir {
 }
 
 impl OrdDir {
+	pub const ALL: [OrdDir; 8] = [
+		OrdDir::Up,
+		OrdDir::UpRight,
+		OrdDir::Right,
+		OrdDir::DownRight,
+		OrdDir::Down,
+		OrdDir::DownLeft,
+		OrdDir::Left,
+		OrdDir::UpLeft,
+	];
+
 	pub fn as_offset(self) -> (i32, i32) {
 		let (x, y) = match self {
 			OrdDir::Up => (0, -1),
@@ -221,6 +698,31 @@ impl OrdDir {
 	}
 }
 
+/// Something a player-controlled piece keeps doing until a new enemy comes into view.
+///
+/// `seen` is how many hostile pieces were already in view; it's counted on the first step.
+/// Explore counts items the same way.
+#[derive(Clone, Debug)]
+pub enum Autopilot {
+	/// Walk towards the nearest unexplored tile.
+	Explore {
+		interruptions: options::Interruptions,
+		seen: Option<usize>,
+		/// Items in view.
+		items: Option<usize>,
+		/// HP as of the last step.
+		hp: Option<i32>,
+	},
+	/// Attack adjacent hostile pieces until there are none left or health runs low.
+	Fight { seen: Option<usize> },
+	/// Cast a spell again, at the same target as last time.
+	Recast {
+		spell: Rc<Spell>,
+		target: Option<Uuid>,
+		seen: Option<usize>,
+	},
+}
+
 /// Anything a character piece can "do".
 ///
 /// This is the only way that character logic or player input should communicate with pieces.
@@ -228,6 +730,53 @@ impl OrdDir {
 pub enum Action {
 	Move(OrdDir),
 	Cast(Rc<Spell>),
+	/// Do nothing for the given amount of time.
+	Wait(Aut),
+	/// Give up the rest of this turn, acting again directly after the given ally.
+	Delay(world::CharacterRef),
+	/// Pass turns until a hostile piece comes within the given range.
+	Hold(u32),
+	/// Collect every item on the piece's tile.
+	PickUp,
+	/// Camp at a rest site, restoring the party (unless ambushed).
+	Rest,
+	/// Use or equip the item at the given index of the party's inventory.
+	UseItem(usize),
+	/// Drop the item at the given index of the party's inventory onto the piece's tile.
+	DropItem(usize),
+}
+
+/// How long camping at a rest site takes.
+const REST_DURATION: Aut = TURN * 10;
+
+impl Action {
+	/// How long this action delays the next turn of the piece performing it.
+	///
+	/// `Delay` doesn't have a fixed cost; it depends on the ally being waited for.
+	pub fn delay(&self, piece: &Piece) -> Aut {
+		match self {
+			Action::Move(_) | Action::Cast(_) => piece.sheet.speed,
+			Action::Wait(time) => *time,
+			Action::Delay(_) => 0,
+			Action::Hold(_) | Action::PickUp | Action::UseItem(_) | Action::DropItem(_) => TURN,
+			Action::Rest => REST_DURATION,
+		}
+	}
+
+	/// A short name for this kind of action, for logging.
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Action::Move(_) => "move",
+			Action::Cast(_) => "cast",
+			Action::Wait(_) => "wait",
+			Action::Delay(_) => "delay",
+			Action::Hold(_) => "hold",
+			Action::PickUp => "pick_up",
+			Action::Rest => "rest",
+			Action::UseItem(_) => "use_item",
+			Action::DropItem(_) => "drop_item",
+		}
+	}
 }
 
 #[derive(Copy, PartialEq, Eq, Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -245,6 +794,10 @@ mod sheet {
 		Ok(this.stats())
 	}
 
+	fn default_light() -> u32 {
+		2
+	}
+
 	fn growth_bonuses() -> Stats {
 		use rand::seq::SliceRandom;
 		const BONUS_COUNT: usize = 10;
@@ -301,16 +854,57 @@ mod sheet {
 		pub skillset: spell::Skillset,
 		#[alua(get)]
 		pub speed: Aut,
+		/// How many tiles of darkness this character lights up around itself.
+		#[alua(get)]
+		#[serde(default = "default_light")]
+		pub light: u32,
 
 		#[alua(get)]
 		pub attacks: Vec<String>,
 		#[alua(get)]
 		pub spells: Vec<String>,
+
+		/// Bosses announce themselves when encountered and when defeated.
+		#[alua(get)]
+		#[serde(default)]
+		pub boss: bool,
+		/// A cutscene to play when this boss is encountered.
+		/// See the `cutscene` module.
+		#[serde(default)]
+		pub entrance: Option<script::MaybeInline>,
+		/// Scripts to run as this character's HP falls, in order.
+		#[serde(default)]
+		pub phases: Vec<Phase>,
+
+		#[serde(default)]
+		pub drops: item::DropTable,
+		/// Given `dropper` and a list of item names (`drops`),
+		/// may return a new list of items to drop instead.
+		#[serde(default)]
+		pub on_drop: Option<script::MaybeInline>,
+		/// Spells and skills to learn while leveling up; see `Learnset`.
+		#[serde(default)]
+		pub learnset: Option<String>,
+		/// What this character says when talked to.
+		#[serde(default)]
+		pub dialogue: Option<String>,
+		/// The AI profile used when this character isn't controlled by the player.
+		/// Characters without one simply pass their turns.
+		#[serde(default)]
+		pub ai: Option<String>,
 	}
 }
 
 pub use sheet::Sheet;
 
+/// A stage of a (usually boss) fight.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Phase {
+	/// The phase begins once HP falls to this percentage of its maximum.
+	pub threshold: u32,
+	pub on_enter: script::MaybeInline,
+}
+
 impl Sheet {
 	pub fn stats(&self) -> Stats {
 		const BONUS_WEIGHTS: Stats = Stats {
@@ -454,22 +1048,15 @@ impl expression::Variables for Stats {
 	}
 }
 
-const HEART_COLOR: Color = (96, 67, 18, 255);
-const SOUL_COLOR: Color = (128, 128, 128, 255);
-const POWER_COLOR: Color = (255, 11, 64, 255);
-const DEFENSE_COLOR: Color = (222, 120, 64, 255);
-const MAGIC_COLOR: Color = (59, 115, 255, 255);
-const RESISTANCE_COLOR: Color = (222, 64, 255, 255);
-
 impl gui::VariableColors for Stats {
-	fn get(s: &str) -> Option<Color> {
+	fn get(s: &str, colors: &options::Colors) -> Option<Color> {
 		match s {
-			"heart" => Some(HEART_COLOR),
-			"soul" => Some(SOUL_COLOR),
-			"power" => Some(POWER_COLOR),
-			"defense" => Some(DEFENSE_COLOR),
-			"magic" => Some(MAGIC_COLOR),
-			"resistance" => Some(RESISTANCE_COLOR),
+			"heart" => Some(colors.stats.heart),
+			"soul" => Some(colors.stats.soul),
+			"power" => Some(colors.stats.power),
+			"defense" => Some(colors.stats.defense),
+			"magic" => Some(colors.stats.magic),
+			"resistance" => Some(colors.stats.resistance),
 			_ => None,
 		}
 	}
@@ -0,0 +1,170 @@
+//! Developer commands, entered through the drop-down console.
+//!
+//! Anything that isn't a built-in command is evaluated as Lua, with the usual script globals.
+
+use crate::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("usage: {0}")]
+	Usage(&'static str),
+	#[error("there is no room to spawn {0}")]
+	NoRoom(String),
+}
+
+#[derive(Clone, Debug)]
+pub enum Command {
+	/// Place a piece with the given sheet next to the acting character.
+	Spawn(String),
+	/// Restore every piece's HP and SP.
+	HealAll,
+	/// Generate the given floor, as if the party had descended to it.
+	Goto(usize),
+	/// Control script profiling; see `script::timed`.
+	Profile(Profile),
+	Lua(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Profile {
+	Start,
+	Stop,
+	Reset,
+	/// Print the most expensive scripts.
+	Report,
+}
+
+/// How many scripts `profile report` lists.
+const PROFILE_REPORT_LENGTH: usize = 10;
+
+impl std::str::FromStr for Command {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Error> {
+		let words: Vec<_> = s.split_whitespace().collect();
+		match words.as_slice() {
+			["spawn", sheet] => Ok(Command::Spawn((*sheet).into())),
+			["spawn", ..] => Err(Error::Usage("spawn <sheet>")),
+			["heal", "all"] => Ok(Command::HealAll),
+			["goto", "floor", floor] => floor
+				.parse()
+				.map(Command::Goto)
+				.map_err(|_| Error::Usage("goto floor <number>")),
+			["goto", ..] => Err(Error::Usage("goto floor <number>")),
+			["profile", "start"] => Ok(Command::Profile(Profile::Start)),
+			["profile", "stop"] => Ok(Command::Profile(Profile::Stop)),
+			["profile", "reset"] => Ok(Command::Profile(Profile::Reset)),
+			["profile", "report"] => Ok(Command::Profile(Profile::Report)),
+			["profile", ..] => Err(Error::Usage("profile start|stop|reset|report")),
+			_ => Ok(Command::Lua(s.into())),
+		}
+	}
+}
+
+impl Command {
+	/// # Errors
+	///
+	/// Returns an error if the command fails or its Lua could not be evaluated.
+	pub fn execute(
+		self,
+		world_manager: &mut world::Manager,
+		resources: &resource::Manager,
+		lua: &mlua::Lua,
+	) -> Result<()> {
+		match self {
+			Command::Spawn(key) => {
+				let sheet = resources.get_sheet(&key)?.clone();
+				let (x, y) = {
+					let next_character = world_manager.next_character().borrow();
+					(next_character.x, next_character.y)
+				};
+				let Some((x, y)) = character::OrdDir::ALL
+					.into_iter()
+					.map(|dir| {
+						let (xoff, yoff) = dir.as_offset();
+						(x + xoff, y + yoff)
+					})
+					.find(|&(x, y)| {
+						world_manager
+							.current_floor
+							.map
+							.get(y, x)
+							.is_some_and(|tile| tile.is_passable())
+							&& world_manager.get_character_at(x, y).is_none()
+					})
+				else {
+					return Err(Error::NoRoom(key).into());
+				};
+				let piece = character::Piece {
+					x,
+					y,
+					..character::Piece::new(sheet, resources)?
+				};
+				world_manager.add_character(Rc::new(RefCell::new(piece)));
+				world_manager
+					.console
+					.print_system(format!("Spawned {key}."));
+			}
+			Command::HealAll => {
+				for character in &world_manager.characters {
+					let mut piece = character.borrow_mut();
+					let stats = piece.stats();
+					piece.restore_hp(stats.heart);
+					piece.restore_sp(stats.soul);
+				}
+				world_manager
+					.console
+					.print_system("Healed every piece.".into());
+			}
+			Command::Goto(floor) => {
+				// `new_floor` descends by one.
+				world_manager.location.floor = floor.saturating_sub(1);
+				world_manager.new_floor(resources)?;
+			}
+			Command::Profile(Profile::Start) => {
+				script::set_profiling(true);
+				world_manager
+					.console
+					.print_system("Started profiling scripts.".into());
+			}
+			Command::Profile(Profile::Stop) => {
+				script::set_profiling(false);
+				world_manager
+					.console
+					.print_system("Stopped profiling scripts.".into());
+			}
+			Command::Profile(Profile::Reset) => {
+				script::reset_profile();
+				world_manager
+					.console
+					.print_system("Cleared script timings.".into());
+			}
+			Command::Profile(Profile::Report) => {
+				let report = script::profile_report();
+				if report.is_empty() {
+					world_manager
+						.console
+						.print_system("No scripts have been timed.".into());
+				}
+				for (name, timing) in report.into_iter().take(PROFILE_REPORT_LENGTH) {
+					world_manager.console.print_system(format!(
+						"{name}: {:?} total, {} calls, {:?} average, {:?} longest",
+						timing.total,
+						timing.calls,
+						timing.average(),
+						timing.longest
+					));
+				}
+			}
+			Command::Lua(source) => {
+				let results: mlua::MultiValue = lua.load(&source).set_name("console").eval()?;
+				for value in results {
+					world_manager.console.print_system(value.to_string()?);
+				}
+			}
+		}
+		Ok(())
+	}
+}
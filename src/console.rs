@@ -4,17 +4,27 @@ use paste::paste;
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::rect::Rect;
 use sdl2::render::TextureQuery;
+use std::cell::Cell;
 use std::collections::VecDeque;
 use std::sync::{mpsc, Arc};
 
 const MINIMUM_NAMEPLATE_WIDTH: u32 = 100;
+const DEFAULT_CAPACITY: usize = 1000;
 
 #[derive(Debug)]
 pub struct Console {
 	pub handle: Handle,
 	message_reciever: mpsc::Receiver<Message>,
-	history: Vec<Message>,
+	/// Once full, the oldest messages are forgotten.
+	history: VecDeque<Message>,
+	capacity: usize,
 	in_progress: VecDeque<usize>,
+	/// How many of the latest messages are scrolled out of view.
+	scroll: usize,
+	/// How many messages fit in the console when it was last drawn.
+	visible: Cell<usize>,
+	/// Messages containing this (lowercase) text are highlighted.
+	highlight: Option<String>,
 }
 
 impl std::ops::Deref for Console {
@@ -38,10 +48,91 @@ pub enum MessagePrinter {
 	Combat(combat::Log),
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Category {
+	General,
+	Combat,
+	Dialogue,
+	System,
+	Debug,
+}
+
+impl Category {
+	/// Categorize messages printed with one of the console's colors.
+	fn of_color(name: &str) -> Self {
+		match name {
+			"system" => Category::System,
+			"debug" => Category::Debug,
+			_ => Category::General,
+		}
+	}
+}
+
+/// Which messages are shown in the console.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filter {
+	pub general: bool,
+	pub combat: bool,
+	/// Whether combat messages are followed by their outcome, like "(-4 HP)".
+	pub annotations: bool,
+	pub dialogue: bool,
+	pub system: bool,
+	pub debug: bool,
+}
+
+impl Default for Filter {
+	fn default() -> Self {
+		Self {
+			general: true,
+			combat: true,
+			annotations: true,
+			dialogue: true,
+			system: true,
+			debug: false,
+		}
+	}
+}
+
+impl Filter {
+	pub fn shows(&self, category: Category) -> bool {
+		match category {
+			Category::General => self.general,
+			Category::Combat => self.combat,
+			Category::Dialogue => self.dialogue,
+			Category::System => self.system,
+			Category::Debug => self.debug,
+		}
+	}
+
+	pub fn toggles(&self) -> [(&'static str, bool); 6] {
+		[
+			("General", self.general),
+			("Combat", self.combat),
+			("Combat annotations", self.annotations),
+			("Dialogue", self.dialogue),
+			("System", self.system),
+			("Debug", self.debug),
+		]
+	}
+
+	pub fn toggles_mut(&mut self) -> [&mut bool; 6] {
+		[
+			&mut self.general,
+			&mut self.combat,
+			&mut self.annotations,
+			&mut self.dialogue,
+			&mut self.system,
+			&mut self.debug,
+		]
+	}
+}
+
 #[derive(Clone, Debug)]
 pub struct Message {
 	text: String,
 	printer: MessagePrinter,
+	category: Category,
 }
 
 macro_rules! console_colored_print {
@@ -50,6 +141,7 @@ macro_rules! console_colored_print {
 			let _ = self.message_sender.send(Message {
 				text,
 				printer: MessagePrinter::Console(self.colors.normal),
+				category: Category::General,
 			});
 		}
 	};
@@ -60,6 +152,7 @@ macro_rules! console_colored_print {
 				let _ = self.message_sender.send(Message {
 					text,
 					printer: MessagePrinter::Console(self.colors.$which),
+					category: Category::of_color(stringify!($which)),
 				});
 			}
 		}
@@ -73,6 +166,7 @@ macro_rules! handle_colored_print {
 				.send(Message {
 					text: value,
 					printer: MessagePrinter::Console(this.colors.normal),
+					category: Category::General,
 				})
 				.map_err(mlua::Error::external)
 		});
@@ -85,6 +179,7 @@ macro_rules! handle_colored_print {
 					.send(Message {
 						text: value,
 						printer: MessagePrinter::Console(this.colors.$which),
+						category: Category::of_color(stringify!($which)),
 					})
 					.map_err(mlua::Error::external)
 			});
@@ -98,6 +193,7 @@ macro_rules! impl_console {
 		$(let $colors:ident: $value:expr,)+
 	) => {
 		#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+		#[serde(default)]
 		pub struct Colors {
 			$(pub $colors: Color,)*
 			$(pub $impl_colors: Color,)*
@@ -119,6 +215,7 @@ macro_rules! impl_console {
 				let _ = self.message_sender.send(Message {
 					text,
 					printer: MessagePrinter::Console(color),
+					category: Category::General,
 				});
 			}
 
@@ -129,6 +226,7 @@ macro_rules! impl_console {
 						speaker,
 						progress: 0.0,
 					},
+					category: Category::Dialogue,
 				});
 			}
 
@@ -136,6 +234,7 @@ macro_rules! impl_console {
 				let  _ = self.message_sender.send(Message {
 					text,
 					printer: MessagePrinter::Combat(log),
+					category: Category::Combat,
 				});
 			}
 		}
@@ -149,6 +248,7 @@ macro_rules! impl_console {
 						.send(Message {
 							text,
 							printer: MessagePrinter::Combat(log),
+							category: Category::Combat,
 						})
 						.map_err(mlua::Error::external)
 				});
@@ -166,6 +266,7 @@ impl_console! {
 	impl danger: (255, 0, 0, 255),
 	impl important: (255, 255, 0, 255),
 	impl special: (0, 255, 0, 255),
+	impl debug: (128, 128, 255, 255),
 	let combat: (255, 255, 128, 255),
 }
 
@@ -174,8 +275,12 @@ impl Default for Console {
 		let (message_sender, message_reciever) = mpsc::channel();
 		Self {
 			message_reciever,
-			history: Vec::new(),
+			history: VecDeque::new(),
+			capacity: DEFAULT_CAPACITY,
 			in_progress: VecDeque::new(),
+			scroll: 0,
+			visible: Cell::new(0),
+			highlight: None,
 			handle: Handle {
 				message_sender,
 				colors: Colors::default(),
@@ -185,18 +290,108 @@ impl Default for Console {
 }
 
 impl Console {
-	pub fn new(colors: console::Colors) -> Self {
+	pub fn new(colors: console::Colors, capacity: usize) -> Self {
 		let mut result = Self::default();
 		result.handle.colors = colors;
+		result.capacity = capacity.max(1);
 		result
 	}
 }
 
 impl Console {
+	/// How many messages in history pass `filter`.
+	fn shown(&self, filter: &Filter) -> usize {
+		self.history
+			.iter()
+			.filter(|x| filter.shows(x.category))
+			.count()
+	}
+
+	/// Scroll back through history; positive values show older messages.
+	///
+	/// Only messages which pass `filter` count, since they're the only ones drawn.
+	pub fn scroll(&mut self, lines: i32, filter: &Filter) {
+		self.scroll = self
+			.scroll
+			.saturating_add_signed(lines as isize)
+			.min(self.shown(filter).saturating_sub(1));
+	}
+
+	/// Scroll back by however many messages fit in the console.
+	pub fn page(&mut self, pages: i32, filter: &Filter) {
+		let lines = self.visible.get().saturating_sub(1).max(1) as i32;
+		self.scroll(pages.saturating_mul(lines), filter);
+	}
+
+	pub fn scroll_to_top(&mut self, filter: &Filter) {
+		self.scroll = self.shown(filter).saturating_sub(1);
+	}
+
+	pub fn scroll_to_bottom(&mut self) {
+		self.scroll = 0;
+	}
+
+	/// Highlight messages containing `query` and scroll to the next older one,
+	/// wrapping around to the newest message after the oldest.
+	///
+	/// Returns false if no messages match.
+	pub fn search(&mut self, query: &str, filter: &Filter) -> bool {
+		if query.is_empty() {
+			self.clear_search();
+			return false;
+		}
+		let query = query.to_lowercase();
+		let history: Vec<_> = self
+			.history
+			.iter()
+			.rev()
+			.filter(|x| filter.shows(x.category))
+			.collect();
+		let len = history.len();
+		// Searching for the same thing again moves on to the next match.
+		let start = if self.highlight.as_ref() == Some(&query) {
+			self.scroll + 1
+		} else {
+			self.scroll
+		}
+		.min(len);
+		let found = (start..len)
+			.chain(0..start)
+			.find(|&i| history[i].text.to_lowercase().contains(&query));
+		self.highlight = Some(query);
+		if let Some(i) = found {
+			self.scroll = i;
+		}
+		found.is_some()
+	}
+
+	pub fn clear_search(&mut self) {
+		self.highlight = None;
+	}
+
+	/// The newest combat message which has been received.
+	pub fn last_combat_message(&self) -> Option<&str> {
+		self.history
+			.iter()
+			.rev()
+			.find(|message| message.category == Category::Combat)
+			.map(|message| message.text.as_str())
+	}
+
 	pub fn update(&mut self, delta: f64) {
 		for message in self.message_reciever.try_iter() {
 			let is_dialogue = matches!(message.printer, MessagePrinter::Dialogue { .. });
-			self.history.push(message);
+			if self.history.len() >= self.capacity {
+				self.history.pop_front();
+				self.in_progress.retain_mut(|i| {
+					let Some(shifted) = i.checked_sub(1) else {
+						return false;
+					};
+					*i = shifted;
+					true
+				});
+			}
+			self.history.push_back(message);
 			if is_dialogue {
 				self.in_progress.push_back(self.history.len() - 1);
 			}
@@ -235,7 +430,7 @@ impl Console {
 	}
 
 	#[allow(clippy::unwrap_used, reason = "SDL")]
-	pub fn draw(&self, gui: &mut gui::Context) {
+	pub fn draw(&self, gui: &mut gui::Context, filter: &Filter) {
 		let canvas = &mut gui.canvas;
 		let rect = Rect::new(
 			gui.x,
@@ -260,7 +455,15 @@ impl Console {
 			let TextureQuery { width, height, .. } = texture.query();
 			(texture, width, height)
 		};
-		for message in self.history.iter().rev() {
+		let mut visible = 0;
+		for message in self
+			.history
+			.iter()
+			.rev()
+			.filter(|x| filter.shows(x.category))
+			.skip(self.scroll)
+		{
+			let bottom = cursor;
 			match &message.printer {
 				MessagePrinter::Console(color) => {
 					let (font_texture, width, height) = text(&message.text, *color);
@@ -321,31 +524,51 @@ impl Console {
 					canvas
 						.copy(&texture, None, Rect::new(rect.x, cursor, width, height))
 						.unwrap();
-					let last_width = width as i32;
-					let info = format!("({log})");
-					let texture = gui
-						.typography
-						.annotation
-						.render(&info)
-						.blended(self.colors.combat)
-						.unwrap()
-						.as_texture(&font_texture_creator)
-						.unwrap();
-					let TextureQuery { width, height, .. } = texture.query();
-					canvas
-						.copy(
-							&texture,
-							None,
-							Rect::new(rect.x + last_width + 10, cursor, width, height),
-						)
-						.unwrap();
+					if filter.annotations {
+						let last_width = width as i32;
+						let info = format!("({log})");
+						let texture = gui
+							.typography
+							.annotation
+							.render(&info)
+							.blended(self.colors.combat)
+							.unwrap()
+							.as_texture(&font_texture_creator)
+							.unwrap();
+						let TextureQuery { width, height, .. } = texture.query();
+						canvas
+							.copy(
+								&texture,
+								None,
+								Rect::new(rect.x + last_width + 10, cursor, width, height),
+							)
+							.unwrap();
+					}
 				}
 			}
 
+			if self
+				.highlight
+				.as_ref()
+				.is_some_and(|query| message.text.to_lowercase().contains(query))
+			{
+				canvas.set_draw_color(self.colors.important);
+				canvas
+					.draw_rect(Rect::new(
+						rect.x,
+						cursor,
+						rect.width(),
+						(bottom - cursor) as u32,
+					))
+					.unwrap();
+			}
+
 			if cursor < rect.y {
 				break;
 			}
+			visible += 1;
 		}
+		self.visible.set(visible);
 
 		canvas.set_clip_rect(None);
 	}
@@ -0,0 +1,115 @@
+//! Player-defined starting characters.
+
+use crate::prelude::*;
+use std::fs;
+use std::path::Path;
+
+/// Describes a starting character in terms of an existing sheet.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Definition {
+	/// The sheet to base this character on.
+	pub sheet: String,
+	pub name: String,
+	pub skillset: spell::Skillset,
+	pub accent_color: Color,
+}
+
+/// Limits on which characters may be created.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Rules {
+	pub allowed_sheets: Vec<String>,
+	/// Total points that may be spent on a skillset.
+	pub point_budget: u32,
+	pub major_cost: u32,
+	pub minor_cost: u32,
+}
+
+impl Default for Rules {
+	fn default() -> Self {
+		Self {
+			allowed_sheets: vec!["luvui".into(), "aris".into()],
+			point_budget: 3,
+			major_cost: 2,
+			minor_cost: 1,
+		}
+	}
+}
+
+impl Rules {
+	/// # Errors
+	///
+	/// Fails if the file could not be opened or parsed.
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		Ok(toml::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	pub fn cost(&self, skillset: &spell::Skillset) -> u32 {
+		let has_minor = match skillset {
+			spell::Skillset::EnergyMajor { minor, .. } => minor.is_some(),
+			spell::Skillset::HarmonyMajor { minor, .. } => minor.is_some(),
+		};
+		self.major_cost + if has_minor { self.minor_cost } else { 0 }
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("{0} may not be used as a starting character")]
+	DisallowedSheet(String),
+	#[error("names may not be empty")]
+	EmptyName,
+	#[error("skillset costs {cost} points, but only {budget} are available")]
+	OverBudget { cost: u32, budget: u32 },
+}
+
+impl Definition {
+	/// Open a definition, such as one saved by a character creation screen.
+	///
+	/// # Errors
+	///
+	/// Fails if the file could not be opened or parsed.
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		Ok(toml::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	/// # Errors
+	///
+	/// Returns an error if the definition breaks any of the given rules.
+	pub fn validate(&self, rules: &Rules) -> Result<(), Error> {
+		if !rules.allowed_sheets.contains(&self.sheet) {
+			return Err(Error::DisallowedSheet(self.sheet.clone()));
+		}
+		if self.name.trim().is_empty() {
+			return Err(Error::EmptyName);
+		}
+		let cost = rules.cost(&self.skillset);
+		if cost > rules.point_budget {
+			return Err(Error::OverBudget {
+				cost,
+				budget: rules.point_budget,
+			});
+		}
+		Ok(())
+	}
+
+	/// Validate the definition and build a party member from it.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the definition is invalid or its sheet could not be found.
+	pub fn build(
+		&self,
+		rules: &Rules,
+		resources: &resource::Manager,
+	) -> Result<world::PartyReferenceBase> {
+		self.validate(rules)?;
+		let mut sheet = resources.get_sheet(&self.sheet)?.clone();
+		sheet.nouns.name = self.name.trim().into();
+		sheet.skillset = self.skillset;
+		Ok(world::PartyReferenceBase {
+			sheet,
+			accent_color: self.accent_color,
+		})
+	}
+}
@@ -0,0 +1,65 @@
+//! Scripted sequences which suspend normal turn processing.
+//!
+//! A cutscene is a Lua coroutine which yields commands, such as:
+//! ```lua
+//! coroutine.yield({ type = "Say", speaker = "Luvui", text = "Meow!" })
+//! coroutine.yield({ type = "Wait", ms = 500 })
+//! coroutine.yield({ type = "Move", piece = boss, x = 3, y = 4 })
+//! coroutine.yield({ type = "Shake", intensity = 8, ms = 300 })
+//! ```
+//! The cutscene ends once the coroutine returns.
+
+use crate::prelude::*;
+
+/// Offsets the world's rendering for a short time.
+#[derive(Clone, Copy, Debug)]
+pub struct Shake {
+	/// Maximum offset, in pixels.
+	pub intensity: f32,
+	/// Remaining time, in seconds.
+	pub remaining: f64,
+}
+
+impl Shake {
+	/// Returns a random offset for this frame.
+	pub fn offset(&self) -> (i32, i32) {
+		let mut rng = rand::thread_rng();
+		let intensity = self.intensity as i32;
+		if intensity <= 0 {
+			return (0, 0);
+		}
+		(
+			rng.gen_range(-intensity..=intensity),
+			rng.gen_range(-intensity..=intensity),
+		)
+	}
+}
+
+pub enum Cutscene {
+	/// Waiting to be started by the world, which has access to Lua.
+	Pending {
+		script: script::MaybeInline,
+		/// Available to the script as the `subject` global.
+		subject: Option<world::CharacterRef>,
+	},
+	Running {
+		thread: mlua::RegistryKey,
+		/// Seconds until the coroutine is resumed.
+		wait: f64,
+	},
+}
+
+impl std::fmt::Debug for Cutscene {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Cutscene::Pending { .. } => write!(f, "Cutscene::Pending"),
+			Cutscene::Running { wait, .. } => write!(f, "Cutscene::Running {{ wait: {wait} }}"),
+		}
+	}
+}
+
+impl Cutscene {
+	pub fn new(script: script::MaybeInline, subject: Option<world::CharacterRef>) -> Self {
+		Self::Pending { script, subject }
+	}
+}
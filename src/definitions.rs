@@ -0,0 +1,273 @@
+//! Lua type definitions (luals/EmmyLua annotations) for the scripting API.
+//!
+//! Rather than being written by hand, these are recorded by running each type's
+//! `mlua::UserData` registrations against a recorder,
+//! so they can't drift from what scripts actually have access to.
+//! Argument names aren't known to Rust, so they're numbered instead.
+
+use crate::prelude::*;
+use std::any::type_name;
+use std::fmt::Write;
+
+/// A userdata type, as seen by scripts.
+struct Class {
+	name: &'static str,
+	/// The Rust type this class was recorded from, used to resolve references to it.
+	rust_type: &'static str,
+	/// Names and Rust types.
+	fields: Vec<(String, &'static str)>,
+	methods: Vec<Method>,
+}
+
+struct Method {
+	name: String,
+	/// Whether this is called with `:`, receiving the userdata as `self`.
+	receiver: bool,
+	arguments: &'static str,
+	returns: &'static str,
+}
+
+impl Class {
+	fn record<T: mlua::UserData>(name: &'static str) -> Self {
+		let mut class = Self {
+			name,
+			rust_type: type_name::<T>(),
+			fields: Vec::new(),
+			methods: Vec::new(),
+		};
+		T::add_fields(&mut class);
+		T::add_methods(&mut class);
+		class
+	}
+
+	fn push_field(&mut self, name: impl AsRef<str>, ty: &'static str) {
+		let name = name.as_ref();
+		// Fields with both getters and setters are only listed once.
+		if !self.fields.iter().any(|(field, _)| field == name) {
+			self.fields.push((name.into(), ty));
+		}
+	}
+
+	fn push_method<A, R>(&mut self, name: impl AsRef<str>, receiver: bool) {
+		self.methods.push(Method {
+			name: name.as_ref().into(),
+			receiver,
+			arguments: type_name::<A>(),
+			returns: type_name::<R>(),
+		});
+	}
+}
+
+// Only the types are of interest, so none of the callbacks are kept.
+// Metamethods and metafields aren't part of the annotations.
+impl<'lua, T> mlua::UserDataFields<'lua, T> for Class {
+	fn add_field<V>(&mut self, name: impl AsRef<str>, _value: V) {
+		self.push_field(name, type_name::<V>());
+	}
+
+	fn add_field_method_get<M, R>(&mut self, name: impl AsRef<str>, _method: M) {
+		self.push_field(name, type_name::<R>());
+	}
+
+	fn add_field_method_set<M, A>(&mut self, name: impl AsRef<str>, _method: M) {
+		self.push_field(name, type_name::<A>());
+	}
+
+	fn add_field_function_get<F, R>(&mut self, name: impl AsRef<str>, _function: F) {
+		self.push_field(name, type_name::<R>());
+	}
+
+	fn add_field_function_set<F, A>(&mut self, name: impl AsRef<str>, _function: F) {
+		self.push_field(name, type_name::<A>());
+	}
+
+	fn add_meta_field<V>(&mut self, _name: impl AsRef<str>, _value: V) {}
+
+	fn add_meta_field_with<F, R>(&mut self, _name: impl AsRef<str>, _function: F) {}
+}
+
+impl<'lua, T> mlua::UserDataMethods<'lua, T> for Class {
+	fn add_method<M, A, R>(&mut self, name: impl AsRef<str>, _method: M) {
+		self.push_method::<A, R>(name, true);
+	}
+
+	fn add_method_mut<M, A, R>(&mut self, name: impl AsRef<str>, _method: M) {
+		self.push_method::<A, R>(name, true);
+	}
+
+	fn add_function<F, A, R>(&mut self, name: impl AsRef<str>, _function: F) {
+		self.push_method::<A, R>(name, false);
+	}
+
+	fn add_function_mut<F, A, R>(&mut self, name: impl AsRef<str>, _function: F) {
+		self.push_method::<A, R>(name, false);
+	}
+
+	fn add_meta_method<M, A, R>(&mut self, _name: impl AsRef<str>, _method: M) {}
+
+	fn add_meta_method_mut<M, A, R>(&mut self, _name: impl AsRef<str>, _method: M) {}
+
+	fn add_meta_function<F, A, R>(&mut self, _name: impl AsRef<str>, _function: F) {}
+
+	fn add_meta_function_mut<F, A, R>(&mut self, _name: impl AsRef<str>, _function: F) {}
+}
+
+/// Split a list of types on the commas that aren't nested within generics or tuples.
+fn split_types(list: &str) -> Vec<&str> {
+	let mut types = Vec::new();
+	let mut depth = 0;
+	let mut start = 0;
+	for (i, c) in list.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				types.push(list[start..i].trim());
+				start = i + 1;
+			}
+			_ => {}
+		}
+	}
+	let last = list[start..].trim();
+	if !last.is_empty() {
+		types.push(last);
+	}
+	types
+}
+
+/// Returns the types of a Lua argument or return list.
+fn unpack(ty: &str) -> Vec<&str> {
+	match ty.strip_prefix('(').and_then(|ty| ty.strip_suffix(')')) {
+		Some(tuple) => split_types(tuple),
+		None => vec![ty],
+	}
+}
+
+/// Translate a Rust type name into its Lua equivalent.
+fn lua_type(ty: &str, classes: &[Class]) -> String {
+	let ty = ty.trim_start_matches('&');
+	if let Some(class) = classes.iter().find(|class| class.rust_type == ty) {
+		return class.name.into();
+	}
+	if let Some(element) = ty.strip_prefix('[').and_then(|ty| ty.strip_suffix(']')) {
+		return lua_type(element, classes) + "[]";
+	}
+	let (path, arguments) = match ty.split_once('<') {
+		Some((path, arguments)) => (
+			path,
+			split_types(arguments.strip_suffix('>').unwrap_or(arguments)),
+		),
+		None => (ty, Vec::new()),
+	};
+	let base = path.rsplit("::").next().unwrap_or(path);
+	match (base, arguments.as_slice()) {
+		("()", _) => "nil".into(),
+		("i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize", _) => {
+			"integer".into()
+		}
+		("f32" | "f64", _) => "number".into(),
+		("bool", _) => "boolean".into(),
+		("str" | "String" | "CString" | "CStr", _) => "string".into(),
+		("Option", [inner]) => lua_type(inner, classes) + "?",
+		("Vec" | "VecDeque", [inner]) => lua_type(inner, classes) + "[]",
+		("HashMap" | "BTreeMap", [key, value, ..]) => format!(
+			"table<{}, {}>",
+			lua_type(key, classes),
+			lua_type(value, classes)
+		),
+		// Wrappers which are invisible to Lua.
+		(
+			"Box" | "Rc" | "Arc" | "RefCell" | "Cow" | "UserDataRef" | "UserDataRefMut"
+			| "Variadic",
+			[inner, ..],
+		) => lua_type(inner, classes),
+		("Table", _) => "table".into(),
+		("Function", _) => "function".into(),
+		("AnyUserData", _) => "userdata".into(),
+		_ => "any".into(),
+	}
+}
+
+fn signature(method: &Method, class: &Class, classes: &[Class]) -> String {
+	let mut arguments = Vec::new();
+	if method.receiver {
+		arguments.push(format!("self: {}", class.name));
+	}
+	for (i, argument) in unpack(method.arguments).into_iter().enumerate() {
+		if argument.contains("Variadic<") {
+			arguments.push(format!("...: {}", lua_type(argument, classes)));
+		} else if argument != "()" {
+			arguments.push(format!("arg{}: {}", i + 1, lua_type(argument, classes)));
+		}
+	}
+	let returns = unpack(method.returns)
+		.into_iter()
+		.filter(|ty| *ty != "()")
+		.map(|ty| lua_type(ty, classes))
+		.collect::<Vec<_>>();
+	let mut signature = format!("fun({})", arguments.join(", "));
+	if !returns.is_empty() {
+		let _ = write!(signature, ": {}", returns.join(", "));
+	}
+	signature
+}
+
+/// Generate a luals definition file describing every global and userdata type available to scripts.
+///
+/// Save the output as a `.lua` file somewhere in the workspace (`res/scripts/` works)
+/// and language servers will pick it up for autocompletion and type checking.
+pub fn lua_defs() -> String {
+	let classes = [
+		Class::record::<character::Piece>("Piece"),
+		Class::record::<character::Sheet>("Sheet"),
+		Class::record::<character::Stats>("Stats"),
+		Class::record::<Nouns>("Nouns"),
+		Class::record::<world::Level>("Level"),
+		Class::record::<world::Rules>("Rules"),
+		Class::record::<Status>("Status"),
+		Class::record::<spell::Affinity>("Affinity"),
+		Class::record::<spell::Outlook>("SpellOutlook"),
+		Class::record::<console::Handle>("Console"),
+		Class::record::<audio::Handle>("Audio"),
+		Class::record::<resource::Handle<Status>>("StatusCatalog"),
+		Class::record::<resource::Catalog>("Resources"),
+		Class::record::<spatial::Index>("Pieces"),
+		Class::record::<query::Query>("World"),
+		Class::record::<schedule::Handle>("Scheduler"),
+	];
+	// Global names and their classes; see `world::Manager::new`.
+	let globals = [
+		("Console", "Console"),
+		("Audio", "Audio"),
+		("Status", "StatusCatalog"),
+		("Resources", "Resources"),
+		("Pieces", "Pieces"),
+		("World", "World"),
+		("Scheduler", "Scheduler"),
+		("Rules", "Rules"),
+	];
+
+	let mut out = format!(
+		"---@meta\n-- Generated for API version {}.\n",
+		script::API_VERSION
+	);
+	for class in &classes {
+		let _ = writeln!(out, "\n---@class {}", class.name);
+		for (name, ty) in &class.fields {
+			let _ = writeln!(out, "---@field {name} {}", lua_type(ty, &classes));
+		}
+		for method in &class.methods {
+			let _ = writeln!(
+				out,
+				"---@field {} {}",
+				method.name,
+				signature(method, class, &classes)
+			);
+		}
+	}
+	out += "\n---@type integer\nAPI_VERSION = nil\n";
+	for (global, class) in globals {
+		let _ = write!(out, "\n---@type {class}\n{global} = nil\n");
+	}
+	out
+}
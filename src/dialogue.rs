@@ -0,0 +1,91 @@
+//! Branching conversations between pieces.
+//!
+//! Dialogues are driven by `world::Manager`, which pauses the current turn while one is active.
+
+use crate::prelude::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Dialogue {
+	/// The node to begin with.
+	pub start: String,
+	pub nodes: HashMap<String, Node>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Node {
+	/// Who is speaking; defaults to the piece being talked to.
+	#[serde(default)]
+	pub speaker: Option<String>,
+	pub text: String,
+	/// If empty (or none pass their conditions), the dialogue continues to `next` (or ends) once the player confirms.
+	#[serde(default)]
+	pub choices: Vec<Choice>,
+	#[serde(default)]
+	pub next: Option<String>,
+	/// Run upon entering this node.
+	#[serde(default)]
+	pub on_enter: Option<script::MaybeInline>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Choice {
+	pub text: String,
+	/// The node to continue to; ends the dialogue if absent.
+	#[serde(default)]
+	pub next: Option<String>,
+	/// Lua which must evaluate to true for this choice to be offered.
+	#[serde(default)]
+	pub condition: Option<script::MaybeInline>,
+	/// Run upon selecting this choice.
+	#[serde(default)]
+	pub effect: Option<script::MaybeInline>,
+}
+
+/// A dialogue in progress.
+#[derive(Clone, Debug)]
+pub struct State {
+	pub dialogue: Dialogue,
+	/// The piece being talked to.
+	pub speaker: world::CharacterRef,
+	/// The piece who started the conversation.
+	pub listener: world::CharacterRef,
+	pub node: String,
+	/// Whether `node` has been entered yet (which requires Lua).
+	pub entered: bool,
+	/// Indices into the current node's choices which passed their conditions.
+	pub available: Vec<usize>,
+	/// Set by input; `None` within `Some` means "continue" for nodes without choices.
+	pub selection: Option<Option<usize>>,
+}
+
+impl State {
+	pub fn new(
+		dialogue: Dialogue,
+		speaker: world::CharacterRef,
+		listener: world::CharacterRef,
+	) -> Self {
+		let node = dialogue.start.clone();
+		Self {
+			dialogue,
+			speaker,
+			listener,
+			node,
+			entered: false,
+			available: Vec::new(),
+			selection: None,
+		}
+	}
+
+	pub fn current(&self) -> Option<&Node> {
+		self.dialogue.nodes.get(&self.node)
+	}
+
+	/// Iterate over the choices currently offered, paired with their indices.
+	pub fn choices(&self) -> impl Iterator<Item = (usize, &Choice)> {
+		let node = self.current();
+		self.available
+			.iter()
+			.filter_map(move |&i| Some((i, node?.choices.get(i)?)))
+	}
+}
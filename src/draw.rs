@@ -4,124 +4,1009 @@ use crate::prelude::*;
 use rand::Rng;
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect::{Point, Rect};
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use std::collections::HashMap;
 use std::f64::consts::{PI, TAU};
+use uuid::Uuid;
 
 const TILE_SIZE: u32 = 64;
 const ITILE_SIZE: i32 = TILE_SIZE as i32;
 
-pub fn tilemap(canvas: &mut Canvas<Window>, world_manager: &world::Manager) {
-	canvas.set_draw_color(Color::WHITE);
-	for (x, col) in world_manager.current_floor.map.iter_cols().enumerate() {
+/// How quickly cameras catch up to their party member, as a fraction of the distance per second.
+const CAMERA_SPEED: f64 = 8.0;
+
+/// Follows a party member around the board.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Camera {
+	position: Option<(f64, f64)>,
+}
+
+impl Camera {
+	/// Ease towards `target`, in tiles. The first tick snaps straight to it.
+	pub fn tick(&mut self, target: (f64, f64), delta: f64) {
+		let (x, y) = self.position.unwrap_or(target);
+		let t = (delta * CAMERA_SPEED).min(1.0);
+		self.position = Some((x + (target.0 - x) * t, y + (target.1 - y) * t));
+	}
+
+	/// Where the board's origin belongs for this camera to be centered in a `width` by `height` area.
+	pub fn offset(&self, width: u32, height: u32) -> (i32, i32) {
+		let (x, y) = self.position.unwrap_or_default();
+		(
+			(width as f64 / 2.0 - (x + 0.5) * TILE_SIZE as f64) as i32,
+			(height as f64 / 2.0 - (y + 0.5) * TILE_SIZE as f64) as i32,
+		)
+	}
+
+	/// Move the camera by a number of tiles.
+	pub fn pan(&mut self, x: f64, y: f64) {
+		let (from_x, from_y) = self.position.unwrap_or_default();
+		self.position = Some((from_x + x, from_y + y));
+	}
+}
+
+/// Multiples of the board's scale that the free-look camera can zoom to.
+const ZOOM_LEVELS: [f32; 5] = [0.5, 0.75, 1.0, 1.5, 2.0];
+const DEFAULT_ZOOM: usize = 2;
+
+/// A camera detached from the party, for looking around the floor.
+#[derive(Clone, Copy, Debug)]
+pub struct FreeLook {
+	pub camera: Camera,
+	/// Index into `ZOOM_LEVELS`.
+	zoom: usize,
+	/// A piece to keep centered; panning by hand stops following it.
+	pub follow: Option<Uuid>,
+}
+
+impl FreeLook {
+	/// Start looking around from wherever `camera` is.
+	pub fn new(camera: Camera) -> Self {
+		Self {
+			camera,
+			zoom: DEFAULT_ZOOM,
+			follow: None,
+		}
+	}
+
+	pub fn pan(&mut self, x: f64, y: f64) {
+		self.follow = None;
+		self.camera.pan(x, y);
+	}
+
+	/// Step through the zoom levels; positive values zoom in.
+	pub fn zoom(&mut self, step: i32) {
+		self.zoom = (self.zoom as i32 + step).clamp(0, ZOOM_LEVELS.len() as i32 - 1) as usize;
+	}
+
+	/// The board's scale while looking around.
+	pub fn scale(&self, board_scale: f32) -> f32 {
+		board_scale * ZOOM_LEVELS[self.zoom]
+	}
+
+	/// Pan along with a mouse drag of `x` by `y` pixels, at the board's current `scale`.
+	pub fn drag(&mut self, x: i32, y: i32, scale: f32) {
+		let tile_size = TILE_SIZE as f64 * scale as f64;
+		self.pan(-x as f64 / tile_size, -y as f64 / tile_size);
+	}
+
+	/// Ease towards the followed piece, if it's still around.
+	pub fn tick(&mut self, world_manager: &world::Manager, movement: &Movement, delta: f64) {
+		let Some(id) = self.follow else {
+			return;
+		};
+		let piece = world_manager
+			.characters
+			.iter()
+			.find(|character| character.borrow().id == id);
+		match piece {
+			Some(piece) => self.camera.tick(movement.position(&piece.borrow()), delta),
+			None => self.follow = None,
+		}
+	}
+}
+
+/// The board's scale, zoomed if the player is looking around.
+pub fn board_scale(mode: &input::Mode, board_scale: f32) -> f32 {
+	match mode {
+		input::Mode::FreeLook(free_look) => free_look.scale(board_scale),
+		_ => board_scale,
+	}
+}
+
+/// Where the board's origin belongs within `world_area`,
+/// following the focused party member unless the player is looking around.
+pub fn board_offset(
+	world_manager: &world::Manager,
+	mode: &input::Mode,
+	world_area: Rect,
+	scale: f32,
+) -> (i32, i32) {
+	let camera = match mode {
+		input::Mode::FreeLook(free_look) => free_look.camera,
+		_ => world_manager.camera(),
+	};
+	camera.offset(
+		(world_area.width() as f32 / scale) as u32,
+		(world_area.height() as f32 / scale) as u32,
+	)
+}
+
+/// Convert a position on the world viewport to the tile drawn there, given the board's scale.
+pub fn tile_at(x: i32, y: i32, scale: f32) -> (i32, i32) {
+	let tile_size = ((TILE_SIZE as f32 * scale) as i32).max(1);
+	(x.div_euclid(tile_size), y.div_euclid(tile_size))
+}
+
+pub fn tilemap(
+	canvas: &mut Canvas<Window>,
+	world_manager: &world::Manager,
+	resources: &resource::Manager<'_>,
+) {
+	let default_palette = biome::Palette::default();
+	let biome = resources.get_biome(&world_manager.biome).ok();
+	let palette = biome
+		.and_then(|biome| resources.get_palette(&biome.palette).ok())
+		.unwrap_or(&default_palette);
+	let tileset = biome
+		.and_then(|biome| biome.tileset.as_ref())
+		.and_then(|tileset| resources.get_tileset(tileset).ok());
+	let floor = &world_manager.current_floor;
+	for (x, col) in floor.map.iter_cols().enumerate() {
 		for (y, tile) in col.enumerate() {
+			let (x, y) = (x as i32, y as i32);
+			let rect = Rect::new(x * ITILE_SIZE, y * ITILE_SIZE, TILE_SIZE, TILE_SIZE);
+			if let Some(tileset) = tileset {
+				let texture = resources.get_texture(&tileset.texture);
+				let size = tileset.tile_size;
+				let source = |coord: biome::TileCoord| {
+					Rect::new(
+						(coord.column * size) as i32,
+						(coord.row * size) as i32,
+						size,
+						size,
+					)
+				};
+				let coords = if *tile == floor::Tile::Wall {
+					let mut coords = vec![tileset.wall(floor.wall_mask(x, y))];
+					coords.extend(tileset.corners(floor, x, y));
+					coords
+				} else {
+					tileset.floor(x, y).into_iter().collect()
+				};
+				for coord in coords {
+					canvas
+						.copy(texture, Some(source(coord)), Some(rect))
+						.unwrap();
+				}
+			} else {
+				canvas.set_draw_color(if *tile == floor::Tile::Wall {
+					palette.wall
+				} else {
+					palette.floor
+				});
+				canvas.fill_rect(rect).unwrap();
+			}
 			match tile {
-				floor::Tile::Floor => (),
-				floor::Tile::Wall => canvas
-					.fill_rect(Rect::new(
-						(x as i32) * ITILE_SIZE,
-						(y as i32) * ITILE_SIZE,
-						TILE_SIZE,
-						TILE_SIZE,
-					))
-					.unwrap(),
-				floor::Tile::Exit => canvas
-					.draw_rect(Rect::new(
-						(x as i32) * ITILE_SIZE + 4,
-						(y as i32) * ITILE_SIZE + 4,
-						TILE_SIZE - 8,
-						TILE_SIZE - 8,
-					))
-					.unwrap(),
+				floor::Tile::Floor | floor::Tile::Wall => {}
+				floor::Tile::Exit => {
+					canvas.set_draw_color(palette.exit);
+					canvas
+						.draw_rect(Rect::new(
+							x * ITILE_SIZE + 4,
+							y * ITILE_SIZE + 4,
+							TILE_SIZE - 8,
+							TILE_SIZE - 8,
+						))
+						.unwrap();
+				}
+				floor::Tile::Rest => {
+					canvas.set_draw_color(palette.rest);
+					canvas
+						.fill_rect(Rect::new(
+							x * ITILE_SIZE + ITILE_SIZE / 4,
+							y * ITILE_SIZE + ITILE_SIZE / 4,
+							TILE_SIZE / 2,
+							TILE_SIZE / 2,
+						))
+						.unwrap();
+				}
 			}
 		}
 	}
 }
 
+/// How many turns ahead the move preview looks.
+const MOVE_PREVIEW_TURNS: usize = 5;
+
+/// Shade the tiles the acting piece could reach before any enemy acts,
+/// and outline the enemies it could attack.
+pub fn move_preview(
+	input_mode: &input::Mode,
+	world_manager: &world::Manager,
+	options: &options::UserInterface,
+	canvas: &mut Canvas<Window>,
+) {
+	let character = world_manager.next_character();
+	if !options.move_preview
+		|| !matches!(input_mode, input::Mode::Normal)
+		|| !character.borrow().controlled_by_player()
+	{
+		return;
+	}
+	let (reachable, attackable) = world_manager.move_preview(character, MOVE_PREVIEW_TURNS);
+
+	let blend_mode = canvas.blend_mode();
+	canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+	canvas.set_draw_color(Color::from(options.colors.reachable));
+	for (x, y) in reachable {
+		canvas
+			.fill_rect(Rect::new(
+				x * ITILE_SIZE,
+				y * ITILE_SIZE,
+				TILE_SIZE,
+				TILE_SIZE,
+			))
+			.unwrap();
+	}
+	canvas.set_blend_mode(blend_mode);
+
+	canvas.set_draw_color(Color::from(options.colors.attackable));
+	for target in attackable {
+		let target = target.borrow();
+		canvas
+			.draw_rect(Rect::new(
+				target.x * ITILE_SIZE,
+				target.y * ITILE_SIZE,
+				TILE_SIZE,
+				TILE_SIZE,
+			))
+			.unwrap();
+	}
+}
+
 pub fn cursor(
 	input_mode: &input::Mode,
+	world_manager: &world::Manager,
 	resources: &resource::Manager<'_>,
+	options: &options::UserInterface,
 	canvas: &mut Canvas<Window>,
 ) {
-	if let input::Mode::Cursor {
-		x,
-		y,
-		state: input::CursorState { float, .. },
-		..
-	} = *input_mode
-	{
-		enum Side {
-			TopLeft,
-			TopRight,
-			BottomLeft,
-			BottomRight,
+	enum Side {
+		TopLeft,
+		TopRight,
+		BottomLeft,
+		BottomRight,
+	}
+
+	let (x, y, float, targets) = match *input_mode {
+		input::Mode::Cursor {
+			x,
+			y,
+			origin,
+			range,
+			state: input::CursorState { float, .. },
+			..
+		} => {
+			// Trace the line to the cursor, showing where it leaves the range.
+			let floor = &world_manager.current_floor;
+			for (tile_x, tile_y) in floor.line(origin, (x, y)) {
+				let color = if floor.within_range(origin, (tile_x, tile_y), range) {
+					options.colors.cursor_mode
+				} else {
+					options.colors.out_of_range
+				};
+				canvas.set_draw_color(Color::from(color));
+				canvas
+					.fill_rect(Rect::new(
+						tile_x * ITILE_SIZE + ITILE_SIZE * 3 / 8,
+						tile_y * ITILE_SIZE + ITILE_SIZE * 3 / 8,
+						TILE_SIZE / 4,
+						TILE_SIZE / 4,
+					))
+					.unwrap();
+			}
+			(x, y, float, world_manager.aiming_targets(origin, range))
 		}
+		input::Mode::Inspect {
+			x,
+			y,
+			state: input::CursorState { float, .. },
+		} => (x, y, float, world_manager.visible_pieces()),
+		_ => return,
+	};
 
-		let cursor = resources.get_texture("cursor");
-		let cursor_info = cursor.query();
-		let cursor_scale = TILE_SIZE / 16;
-		let cursor_width = cursor_info.width * cursor_scale;
-		let cursor_height = cursor_info.height * cursor_scale;
-		let right_offset = ITILE_SIZE - cursor_width as i32;
-		let bottom_offset = ITILE_SIZE - cursor_height as i32;
-		let float = ((float.sin() + 1.0) * ((TILE_SIZE / 16) as f64)) as i32;
-
-		for side in [
-			Side::TopLeft,
-			Side::TopRight,
-			Side::BottomLeft,
-			Side::BottomRight,
-		] {
-			let (x_off, y_off) = match side {
-				Side::TopLeft => (-float, -float),
-				Side::TopRight => (right_offset + float, -float),
-				Side::BottomLeft => (-float, bottom_offset + float),
-				Side::BottomRight => (right_offset + float, bottom_offset + float),
-			};
-			let hflip = match side {
-				Side::TopLeft | Side::BottomLeft => false,
-				Side::TopRight | Side::BottomRight => true,
-			};
-			let vflip = match side {
-				Side::TopLeft | Side::TopRight => false,
-				Side::BottomLeft | Side::BottomRight => true,
-			};
+	// Outline everything that can be cycled to.
+	canvas.set_draw_color(Color::from(options.colors.cursor_mode));
+	for target in targets {
+		let target = target.borrow();
+		canvas
+			.draw_rect(Rect::new(
+				target.x * ITILE_SIZE,
+				target.y * ITILE_SIZE,
+				TILE_SIZE,
+				TILE_SIZE,
+			))
+			.unwrap();
+	}
 
-			let rect = Rect::new(
-				x * ITILE_SIZE + x_off,
-				y * ITILE_SIZE + y_off,
-				cursor_width,
-				cursor_height,
-			);
-			canvas
-				.copy_ex(cursor, None, Some(rect), 0.0, None, hflip, vflip)
-				.unwrap();
-		}
+	let cursor = resources.get_texture("cursor");
+	let cursor_info = cursor.query();
+	let cursor_scale = TILE_SIZE / 16;
+	let cursor_width = cursor_info.width * cursor_scale;
+	let cursor_height = cursor_info.height * cursor_scale;
+	let right_offset = ITILE_SIZE - cursor_width as i32;
+	let bottom_offset = ITILE_SIZE - cursor_height as i32;
+	let float = ((float.sin() + 1.0) * ((TILE_SIZE / 16) as f64)) as i32;
+
+	for side in [
+		Side::TopLeft,
+		Side::TopRight,
+		Side::BottomLeft,
+		Side::BottomRight,
+	] {
+		let (x_off, y_off) = match side {
+			Side::TopLeft => (-float, -float),
+			Side::TopRight => (right_offset + float, -float),
+			Side::BottomLeft => (-float, bottom_offset + float),
+			Side::BottomRight => (right_offset + float, bottom_offset + float),
+		};
+		let hflip = match side {
+			Side::TopLeft | Side::BottomLeft => false,
+			Side::TopRight | Side::BottomRight => true,
+		};
+		let vflip = match side {
+			Side::TopLeft | Side::TopRight => false,
+			Side::BottomLeft | Side::BottomRight => true,
+		};
+
+		let rect = Rect::new(
+			x * ITILE_SIZE + x_off,
+			y * ITILE_SIZE + y_off,
+			cursor_width,
+			cursor_height,
+		);
+		canvas
+			.copy_ex(cursor, None, Some(rect), 0.0, None, hflip, vflip)
+			.unwrap();
 	}
 }
 
-pub fn characters(
+pub fn items(
 	world_manager: &world::Manager,
 	canvas: &mut Canvas<Window>,
 	resources: &resource::Manager<'_>,
 ) {
-	for character in world_manager.characters.iter().map(|x| x.borrow()) {
+	for item in &world_manager.items {
 		canvas
 			.copy(
-				resources.get_texture(&character.sheet.icon),
-				Some(Rect::new(0, 0, 16, 16)),
+				resources.get_item_texture(&item.item.name),
+				None,
 				Some(Rect::new(
-					character.x * ITILE_SIZE,
-					character.y * ITILE_SIZE,
-					TILE_SIZE,
-					TILE_SIZE,
+					item.x * ITILE_SIZE + ITILE_SIZE / 4,
+					item.y * ITILE_SIZE + ITILE_SIZE / 4,
+					TILE_SIZE / 2,
+					TILE_SIZE / 2,
 				)),
 			)
 			.unwrap();
 	}
 }
 
+/// How dark corpses are drawn, out of 255.
+const CORPSE_SHADE: u8 = 160;
+
+/// Fallen party members, lying where they died.
+pub fn corpses(
+	world_manager: &world::Manager,
+	canvas: &mut Canvas<Window>,
+	resources: &resource::Manager<'_>,
+) {
+	let blend_mode = canvas.blend_mode();
+	canvas.set_blend_mode(BlendMode::Blend);
+	canvas.set_draw_color(Color::RGBA(0, 0, 0, CORPSE_SHADE));
+	for corpse in &world_manager.corpses {
+		let frame = match resources.get_animation(&corpse.icon) {
+			Some(sheet) => Rect::new(0, 0, sheet.frame_width, sheet.frame_height),
+			None => Rect::new(0, 0, 16, 16),
+		};
+		let rect = Rect::new(
+			corpse.x * ITILE_SIZE,
+			corpse.y * ITILE_SIZE,
+			TILE_SIZE,
+			TILE_SIZE,
+		);
+		canvas
+			.copy_ex(
+				resources.get_texture(&corpse.icon),
+				Some(frame),
+				Some(rect),
+				90.0,
+				None,
+				false,
+				false,
+			)
+			.unwrap();
+		canvas.fill_rect(rect).unwrap();
+	}
+	canvas.set_blend_mode(blend_mode);
+}
+
+pub fn characters(
+	world_manager: &world::Manager,
+	canvas: &mut Canvas<Window>,
+	resources: &resource::Manager<'_>,
+	movement: &Movement,
+	animations: &Animations,
+	options: &options::UserInterface,
+) {
+	for character in &world_manager.characters {
+		let character = character.borrow();
+		let (x, y) = movement.position(&character);
+		let (x, y) = ((x * TILE_SIZE as f64) as i32, (y * TILE_SIZE as f64) as i32);
+		// Downed pieces are drawn lying on their side.
+		let angle = if character.downed.is_some() {
+			90.0
+		} else {
+			0.0
+		};
+		canvas
+			.copy_ex(
+				resources.get_texture(&character.sheet.icon),
+				Some(animations.frame(&character, resources)),
+				Some(Rect::new(x, y, TILE_SIZE, TILE_SIZE)),
+				angle,
+				None,
+				false,
+				false,
+			)
+			.unwrap();
+		health_bar(&character, x, y, canvas, resources, options);
+	}
+}
+
+const HEALTH_BAR_HEIGHT: u32 = 4;
+const STATUS_ICON_SIZE: u32 = 12;
+
+/// Draw a piece's HP and statuses above the tile at (`x`, `y`).
+fn health_bar(
+	piece: &character::Piece,
+	x: i32,
+	y: i32,
+	canvas: &mut Canvas<Window>,
+	resources: &resource::Manager<'_>,
+	options: &options::UserInterface,
+) {
+	let heart = piece.stats().heart.max(1);
+	let visible = match options.health_bars {
+		options::HealthBars::Always => true,
+		options::HealthBars::OnDamage => piece.hp < heart as i32 || !piece.statuses.is_empty(),
+		options::HealthBars::Never => false,
+	};
+	if !visible {
+		return;
+	}
+
+	let bar_y = y - HEALTH_BAR_HEIGHT as i32 - 2;
+	let filled = TILE_SIZE * piece.hp.clamp(0, heart as i32) as u32 / heart;
+	let color = match piece.effective_alliance() {
+		character::Alliance::Friendly => options.colors.friendly_health,
+		character::Alliance::Enemy => options.colors.enemy_health,
+	};
+	canvas.set_draw_color(Color::RGB(0x20, 0x20, 0x20));
+	canvas
+		.fill_rect(Rect::new(x, bar_y, TILE_SIZE, HEALTH_BAR_HEIGHT))
+		.unwrap();
+	if filled > 0 {
+		canvas.set_draw_color(Color::from(color));
+		canvas
+			.fill_rect(Rect::new(x, bar_y, filled, HEALTH_BAR_HEIGHT))
+			.unwrap();
+	}
+
+	let icon_y = bar_y - STATUS_ICON_SIZE as i32 - 2;
+	for (i, status) in piece.statuses.values().enumerate() {
+		let rect = Rect::new(
+			x + (i as u32 * (STATUS_ICON_SIZE + 2)) as i32,
+			icon_y,
+			STATUS_ICON_SIZE,
+			STATUS_ICON_SIZE,
+		);
+		if let Some(icon) = &status.icon {
+			canvas
+				.copy(resources.get_texture(icon), None, rect)
+				.unwrap();
+		} else {
+			// Statuses without icons are still worth noticing.
+			canvas.set_draw_color(Color::from(status.color()));
+			canvas.fill_rect(rect).unwrap();
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Slide {
+	from: (f64, f64),
+	to: (i32, i32),
+	/// From 0 to 1.
+	progress: f64,
+}
+
+impl Slide {
+	fn position(self) -> (f64, f64) {
+		let (fx, fy) = self.from;
+		let (tx, ty) = (self.to.0 as f64, self.to.1 as f64);
+		(
+			fx + (tx - fx) * self.progress,
+			fy + (ty - fy) * self.progress,
+		)
+	}
+}
+
+/// Smooths pieces' movement between tiles, rather than having them teleport.
+#[derive(Clone, Debug, Default)]
+pub struct Movement {
+	slides: HashMap<Uuid, Slide>,
+}
+
+impl Movement {
+	/// Follow any pieces that have moved since the last tick.
+	///
+	/// Slides take `duration` seconds; a duration of 0 disables animation.
+	pub fn tick(&mut self, world_manager: &world::Manager, delta: f64, duration: f64) {
+		self.slides
+			.retain(|id, _| world_manager.get_piece(*id).is_some());
+		for character in &world_manager.characters {
+			let (id, to) = {
+				let piece = character.borrow();
+				(piece.id, (piece.x, piece.y))
+			};
+			match self.slides.get_mut(&id) {
+				Some(slide) if slide.to != to => {
+					// Distant moves (such as changing floors) shouldn't drag the piece across the screen.
+					let (x, y) = slide.position();
+					let far = (x - to.0 as f64).abs() > 1.5 || (y - to.1 as f64).abs() > 1.5;
+					*slide = Slide {
+						from: if far {
+							(to.0 as f64, to.1 as f64)
+						} else {
+							(x, y)
+						},
+						to,
+						progress: if far || duration <= 0.0 { 1.0 } else { 0.0 },
+					};
+				}
+				Some(slide) => {
+					slide.progress = if duration <= 0.0 {
+						1.0
+					} else {
+						(slide.progress + delta / duration).min(1.0)
+					};
+				}
+				None => {
+					self.slides.insert(
+						id,
+						Slide {
+							from: (to.0 as f64, to.1 as f64),
+							to,
+							progress: 1.0,
+						},
+					);
+				}
+			}
+		}
+	}
+
+	/// Where to draw a piece, in tiles.
+	pub fn position(&self, piece: &character::Piece) -> (f64, f64) {
+		self.position_of(piece.id, (piece.x, piece.y))
+	}
+
+	/// Like `position`, for a piece which may no longer exist; `tile` is where it was last seen.
+	pub fn position_of(&self, id: Uuid, tile: (i32, i32)) -> (f64, f64) {
+		self.slides
+			.get(&id)
+			.map_or((tile.0 as f64, tile.1 as f64), |slide| slide.position())
+	}
+}
+
+/// How many lightmap pixels cover each tile.
+const LIGHTMAP_TILE_SIZE: u32 = 16;
+
+/// Darkens the board according to the biome's ambient color, except around light sources.
+pub struct Lighting<'texture> {
+	texture_creator: &'texture TextureCreator<WindowContext>,
+	/// Drawn additively onto the lightmap for every light source.
+	light: Texture<'texture>,
+	/// Recreated whenever the floor changes size.
+	lightmap: Option<Texture<'texture>>,
+}
+
+impl<'texture> Lighting<'texture> {
+	/// # Errors
+	///
+	/// Returns an error if the light texture can't be loaded.
+	pub fn new(
+		texture_creator: &'texture TextureCreator<WindowContext>,
+		resources: &'texture resource::Manager<'_>,
+	) -> Result<Self> {
+		let mut light = resources.get_owned_texture("light")?;
+		light.set_blend_mode(BlendMode::Add);
+		Ok(Self {
+			texture_creator,
+			light,
+			lightmap: None,
+		})
+	}
+
+	/// Multiply the board by its lightmap.
+	///
+	/// Does nothing if the biome has no ambient color, or if darkness is disabled.
+	pub fn draw(
+		&mut self,
+		canvas: &mut Canvas<Window>,
+		world_manager: &world::Manager,
+		resources: &resource::Manager<'_>,
+		movement: &Movement,
+		options: &options::UserInterface,
+	) {
+		let Some(ambient) = resources
+			.get_biome(&world_manager.biome)
+			.ok()
+			.and_then(|biome| biome.ambient)
+		else {
+			return;
+		};
+		if !options.darkness {
+			return;
+		}
+		let (rows, cols) = world_manager.current_floor.map.size();
+		let (width, height) = (
+			cols as u32 * LIGHTMAP_TILE_SIZE,
+			rows as u32 * LIGHTMAP_TILE_SIZE,
+		);
+		if self.lightmap.as_ref().is_none_or(|lightmap| {
+			(lightmap.query().width, lightmap.query().height) != (width, height)
+		}) {
+			let Ok(mut lightmap) = self.texture_creator.create_texture_target(
+				PixelFormatEnum::RGBA8888,
+				width,
+				height,
+			) else {
+				return;
+			};
+			lightmap.set_blend_mode(BlendMode::Mod);
+			self.lightmap = Some(lightmap);
+		}
+		let Some(lightmap) = &mut self.lightmap else {
+			return;
+		};
+
+		let pieces = world_manager.characters.iter().map(|piece| {
+			let piece = piece.borrow();
+			(movement.position(&piece), piece.sheet.light)
+		});
+		let items = world_manager.items.iter().filter_map(|item| {
+			let light = resources.get_item(&item.item.name).ok()?.light;
+			Some(((item.x as f64, item.y as f64), light))
+		});
+		let lights: Vec<_> = pieces
+			.chain(items)
+			.filter(|(_, light)| *light > 0)
+			.collect();
+		let light = &self.light;
+		let _ = canvas.with_texture_canvas(lightmap, |canvas| {
+			canvas.set_draw_color(Color::from(ambient));
+			canvas.clear();
+			for ((x, y), radius) in lights {
+				let size = (radius * 2 + 1) * LIGHTMAP_TILE_SIZE;
+				let center = |position: f64| {
+					((position + 0.5) * LIGHTMAP_TILE_SIZE as f64) as i32 - size as i32 / 2
+				};
+				canvas
+					.copy(light, None, Rect::new(center(x), center(y), size, size))
+					.unwrap();
+			}
+		});
+		canvas
+			.copy(
+				lightmap,
+				None,
+				Rect::new(0, 0, cols as u32 * TILE_SIZE, rows as u32 * TILE_SIZE),
+			)
+			.unwrap();
+	}
+}
+
+/// Which animation each piece is playing, and for how long it's been playing it.
+#[derive(Clone, Debug, Default)]
+pub struct Animations {
+	states: HashMap<Uuid, (animation::State, f64)>,
+}
+
+impl Animations {
+	/// Start whichever animation `event` calls for.
+	pub fn push(&mut self, event: &world::Event) {
+		let (id, state) = match event {
+			world::Event::Moved(id) => (id, animation::State::Walk),
+			world::Event::SpellCast(id) => (id, animation::State::Cast),
+			world::Event::HpChanged { piece, change, .. } if *change < 0 => {
+				(piece, animation::State::Hurt)
+			}
+			_ => return,
+		};
+		self.states.insert(*id, (state, 0.0));
+	}
+
+	/// Advance every animation, returning pieces to idle once theirs has finished.
+	pub fn tick(
+		&mut self,
+		world_manager: &world::Manager,
+		resources: &resource::Manager<'_>,
+		delta: f64,
+	) {
+		self.states
+			.retain(|id, _| world_manager.get_piece(*id).is_some());
+		// Every piece idles, not just those that have done something.
+		for character in &world_manager.characters {
+			self.states.entry(character.borrow().id).or_default();
+		}
+		for (id, (state, time)) in &mut self.states {
+			*time += delta;
+			if *state == animation::State::Idle {
+				continue;
+			}
+			let finished = world_manager
+				.get_piece(*id)
+				.and_then(|piece| resources.get_animation(&piece.borrow().sheet.icon))
+				.and_then(|sheet| sheet.strip(*state))
+				.is_none_or(|strip| *time >= strip.length());
+			if finished {
+				*state = animation::State::Idle;
+				*time = 0.0;
+			}
+		}
+	}
+
+	/// The part of `piece`'s icon to draw this frame.
+	///
+	/// Icons without animation metadata are drawn as a single 16x16 frame.
+	pub fn frame(&self, piece: &character::Piece, resources: &resource::Manager<'_>) -> Rect {
+		let Some(sheet) = resources.get_animation(&piece.sheet.icon) else {
+			return Rect::new(0, 0, 16, 16);
+		};
+		let (state, time) = self.states.get(&piece.id).copied().unwrap_or_default();
+		let (x, y) = sheet.frame(state, time);
+		Rect::new(x as i32, y as i32, sheet.frame_width, sheet.frame_height)
+	}
+}
+
+/// How far the screen shakes after a hit that takes a piece's entire HP, in pixels.
+const MAX_SHAKE: f32 = 16.0;
+/// How long the screen shakes after a big hit, in seconds.
+const SHAKE_DURATION: f64 = 0.25;
+/// How long the screen flashes when a piece dies, in seconds.
+const DEATH_FLASH_DURATION: f64 = 0.2;
+/// How long the board freezes after a devastating hit, in seconds.
+const HITSTOP_DURATION: f64 = 0.1;
+
+/// Screen-wide reactions to world events.
+///
+/// Big hits shake the camera, deaths flash the screen,
+/// and devastating hits (taking at least half of a piece's HP) briefly freeze the board.
+#[derive(Clone, Debug, Default)]
+pub struct Effects {
+	shake: Option<cutscene::Shake>,
+	flash: f64,
+	hitstop: f64,
+}
+
+impl Effects {
+	pub fn push(&mut self, event: &world::Event, options: &options::UserInterface) {
+		if !options.effects || options.effect_intensity <= 0.0 {
+			return;
+		}
+		match event {
+			world::Event::HpChanged { change, heart, .. } if *change < 0 => {
+				let heart = (*heart).max(1);
+				let damage = change.unsigned_abs();
+				if damage * 4 >= heart {
+					let fraction = (damage as f32 / heart as f32).min(1.0);
+					self.shake = Some(cutscene::Shake {
+						intensity: MAX_SHAKE * fraction * options.effect_intensity,
+						remaining: SHAKE_DURATION,
+					});
+				}
+				if damage * 2 >= heart {
+					self.hitstop = HITSTOP_DURATION;
+				}
+			}
+			world::Event::Killed(_) => self.flash = DEATH_FLASH_DURATION,
+			_ => {}
+		}
+	}
+
+	pub fn tick(&mut self, delta: f64) {
+		if let Some(shake) = &mut self.shake {
+			shake.remaining -= delta;
+			if shake.remaining <= 0.0 {
+				self.shake = None;
+			}
+		}
+		self.flash = (self.flash - delta).max(0.0);
+		self.hitstop = (self.hitstop - delta).max(0.0);
+	}
+
+	/// Whether the board should stand still this frame.
+	pub fn frozen(&self) -> bool {
+		self.hitstop > 0.0
+	}
+
+	/// How far to offset the board this frame.
+	pub fn offset(&self) -> (i32, i32) {
+		self.shake.map(|shake| shake.offset()).unwrap_or_default()
+	}
+
+	/// Cover `rect` with a fading white flash, if one is playing.
+	pub fn draw(&self, canvas: &mut Canvas<Window>, rect: Rect, options: &options::UserInterface) {
+		if self.flash <= 0.0 {
+			return;
+		}
+		let alpha =
+			(self.flash / DEATH_FLASH_DURATION) * 128.0 * options.effect_intensity.min(1.0) as f64;
+		let blend_mode = canvas.blend_mode();
+		canvas.set_blend_mode(BlendMode::Blend);
+		canvas.set_draw_color(Color::RGBA(255, 255, 255, alpha as u8));
+		canvas.fill_rect(rect).unwrap();
+		canvas.set_blend_mode(blend_mode);
+	}
+}
+
+/// Dim `rect` to show that time is frozen.
+pub fn pause_overlay(canvas: &mut Canvas<Window>, rect: Rect) {
+	let blend_mode = canvas.blend_mode();
+	canvas.set_blend_mode(BlendMode::Blend);
+	canvas.set_draw_color(Color::RGBA(0, 0, 0, 128));
+	canvas.fill_rect(rect).unwrap();
+	canvas.set_blend_mode(blend_mode);
+}
+
+/// How long floating numbers stay on screen, in seconds.
+const POPUP_DURATION: f64 = 1.0;
+/// How far floating numbers rise before disappearing, in tiles.
+const POPUP_RISE: f64 = 0.5;
+/// How long a piece flashes after being hit, in seconds.
+const FLASH_DURATION: f64 = 0.15;
+
+#[derive(Clone, Debug)]
+struct Popup {
+	/// Where the popup started, in tiles.
+	x: f64,
+	y: f64,
+	text: String,
+	color: Color,
+	/// Drawn larger, for hits that take a large portion of a piece's HP.
+	emphasized: bool,
+	age: f64,
+}
+
+/// Floating damage numbers and hit flashes.
+#[derive(Clone, Debug, Default)]
+pub struct Feedback {
+	popups: Vec<Popup>,
+	flashes: Vec<(Uuid, f64)>,
+}
+
+impl Feedback {
+	pub fn push(
+		&mut self,
+		event: &world::Event,
+		movement: &Movement,
+		options: &options::UserInterface,
+	) {
+		let world::Event::HpChanged {
+			piece: id,
+			change,
+			x,
+			y,
+			heart,
+		} = event
+		else {
+			return;
+		};
+		// Pieces that were removed (like by dying) still get feedback in their last position.
+		if options.damage_numbers {
+			let (x, y) = movement.position_of(*id, (*x, *y));
+			let (text, color) = if *change < 0 {
+				(change.to_string(), options.colors.damage)
+			} else {
+				(format!("+{change}"), options.colors.healing)
+			};
+			self.popups.push(Popup {
+				x,
+				y,
+				text,
+				color: color.into(),
+				emphasized: *change < 0 && change.unsigned_abs() * 4 >= *heart,
+				age: 0.0,
+			});
+		}
+		if options.hit_flash && *change < 0 {
+			self.flashes.push((*id, FLASH_DURATION));
+		}
+	}
+
+	pub fn tick(&mut self, delta: f64) {
+		for popup in &mut self.popups {
+			popup.age += delta;
+		}
+		self.popups.retain(|x| x.age < POPUP_DURATION);
+		for (_, remaining) in &mut self.flashes {
+			*remaining -= delta;
+		}
+		self.flashes.retain(|(_, remaining)| *remaining > 0.0);
+	}
+
+	pub fn draw(
+		&self,
+		canvas: &mut Canvas<Window>,
+		typography: &Typography<'_, '_>,
+		world_manager: &world::Manager,
+		movement: &Movement,
+	) {
+		let blend_mode = canvas.blend_mode();
+		canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+		for (id, remaining) in &self.flashes {
+			let Some(piece) = world_manager.get_piece(*id) else {
+				continue;
+			};
+			let (x, y) = movement.position(&piece.borrow());
+			let alpha = (remaining / FLASH_DURATION * 192.0) as u8;
+			canvas.set_draw_color(Color::RGBA(255, 255, 255, alpha));
+			canvas
+				.fill_rect(Rect::new(
+					(x * TILE_SIZE as f64) as i32,
+					(y * TILE_SIZE as f64) as i32,
+					TILE_SIZE,
+					TILE_SIZE,
+				))
+				.unwrap();
+		}
+		canvas.set_blend_mode(blend_mode);
+
+		let texture_creator = canvas.texture_creator();
+		for popup in &self.popups {
+			let progress = popup.age / POPUP_DURATION;
+			let font = if popup.emphasized {
+				&typography.title
+			} else {
+				&typography.normal
+			};
+			let text = if popup.emphasized {
+				format!("{}!", popup.text)
+			} else {
+				popup.text.clone()
+			};
+			let mut texture = font
+				.render(&text)
+				.blended(popup.color)
+				.unwrap()
+				.as_texture(&texture_creator)
+				.unwrap();
+			texture.set_alpha_mod(((1.0 - progress) * 255.0) as u8);
+			let sdl2::render::TextureQuery { width, height, .. } = texture.query();
+			let x = ((popup.x + 0.5) * TILE_SIZE as f64) as i32 - width as i32 / 2;
+			let y = ((popup.y - progress * POPUP_RISE) * TILE_SIZE as f64) as i32;
+			canvas
+				.copy(&texture, None, Rect::new(x, y, width, height))
+				.unwrap();
+		}
+	}
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct CloudState {
 	timer: f64,
@@ -122,7 +122,8 @@ impl TryFrom<String> for Expression {
 							.parse()
 							.expect("parser must return valid integer characters"),
 					),
-					Rule::identifier => {
+					// Calls are passed to `Variables` as-is, parentheses and all.
+					Rule::identifier | Rule::call => {
 						let span = primary.as_span();
 						Operation::Variable(span.start(), span.end())
 					}
@@ -1,4 +1,6 @@
 use grid::Grid;
+use rand::seq::IteratorRandom;
+use rand::RngCore;
 use tracing::warn;
 
 use crate::vault::Vault;
@@ -11,6 +13,15 @@ pub enum Tile {
 	#[default]
 	Wall,
 	Exit,
+	/// A safe-ish place for the party to camp.
+	Rest,
+}
+
+impl Tile {
+	/// Whether pieces may stand on this tile.
+	pub fn is_passable(self) -> bool {
+		matches!(self, Tile::Floor | Tile::Exit | Tile::Rest)
+	}
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -45,4 +56,92 @@ impl Floor {
 			y += 1;
 		}
 	}
+
+	/// Pick a random floor tile, if there are any.
+	pub fn random_floor_tile(&self, rng: &mut dyn RngCore) -> Option<(i32, i32)> {
+		self.map
+			.indexed_iter()
+			.filter(|(_, tile)| **tile == Tile::Floor)
+			.map(|((y, x), _)| (x as i32, y as i32))
+			.choose(rng)
+	}
+
+	/// Which sides of the tile at (`x`, `y`) border a wall:
+	/// 1 for up, 2 for right, 4 for down, and 8 for left.
+	///
+	/// Tiles outside of the map count as walls, so the edges of the map connect.
+	pub fn wall_mask(&self, x: i32, y: i32) -> u8 {
+		[(0, -1), (1, 0), (0, 1), (-1, 0)]
+			.into_iter()
+			.enumerate()
+			.filter(|(_, (dx, dy))| {
+				self.map
+					.get(y + dy, x + dx)
+					.is_none_or(|tile| *tile == Tile::Wall)
+			})
+			.fold(0, |mask, (i, _)| mask | 1 << i)
+	}
+
+	/// Every tile on a straight line from `from` to `to`, excluding `from`.
+	pub fn line(&self, from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+		let (mut x, mut y) = from;
+		let dx = (to.0 - x).abs();
+		let dy = -(to.1 - y).abs();
+		let step_x = if x < to.0 { 1 } else { -1 };
+		let step_y = if y < to.1 { 1 } else { -1 };
+		let mut error = dx + dy;
+		let mut line = Vec::new();
+		while (x, y) != to {
+			let doubled = error * 2;
+			if doubled >= dy {
+				error += dy;
+				x += step_x;
+			}
+			if doubled <= dx {
+				error += dx;
+				y += step_y;
+			}
+			line.push((x, y));
+		}
+		line
+	}
+
+	/// Whether `to` is no more than `range` tiles from `from`, with a line of sight between them.
+	///
+	/// A range of `None` is unlimited.
+	pub fn within_range(&self, from: (i32, i32), to: (i32, i32), range: Option<u32>) -> bool {
+		let distance = (to.0 - from.0)
+			.unsigned_abs()
+			.max((to.1 - from.1).unsigned_abs());
+		range.is_none_or(|range| distance <= range) && self.line_of_sight(from, to)
+	}
+
+	/// Whether a straight line between two tiles is free of impassable tiles.
+	///
+	/// The endpoints themselves aren't checked.
+	pub fn line_of_sight(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+		let (mut x, mut y) = from;
+		let dx = (to.0 - x).abs();
+		let dy = -(to.1 - y).abs();
+		let step_x = if x < to.0 { 1 } else { -1 };
+		let step_y = if y < to.1 { 1 } else { -1 };
+		let mut error = dx + dy;
+		loop {
+			if (x, y) == to {
+				return true;
+			}
+			if (x, y) != from && !self.map.get(y, x).is_some_and(|tile| tile.is_passable()) {
+				return false;
+			}
+			let doubled = error * 2;
+			if doubled >= dy {
+				error += dy;
+				x += step_x;
+			}
+			if doubled <= dx {
+				error += dx;
+				y += step_y;
+			}
+		}
+	}
 }
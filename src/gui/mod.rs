@@ -196,7 +196,12 @@ impl<'canvas, 'ttf_module, 'rwops> Context<'canvas, 'ttf_module, 'rwops> {
 		self.advance(width, height);
 	}
 
-	pub fn expression<Colors: VariableColors>(&mut self, expression: &Expression, font: &Font) {
+	pub fn expression<Colors: VariableColors>(
+		&mut self,
+		expression: &Expression,
+		font: &Font,
+		colors: &options::Colors,
+	) {
 		fn enter_op(
 			op: &expression::Operation,
 			expression: &Expression,
@@ -244,7 +249,7 @@ impl<'canvas, 'ttf_module, 'rwops> Context<'canvas, 'ttf_module, 'rwops> {
 			let colored_range = span.start..span.end;
 			if !colored_range.is_empty() {
 				let var = &expression.source[colored_range];
-				let color = Colors::get(var).unwrap_or((255, 0, 0, 255));
+				let color = Colors::get(var, colors).unwrap_or((255, 0, 0, 255));
 				self.label_styled(var, color, font);
 			}
 			last_char = span.end;
@@ -274,11 +279,11 @@ impl<'canvas, 'ttf_module, 'rwops> Context<'canvas, 'ttf_module, 'rwops> {
 }
 
 pub trait VariableColors {
-	fn get(s: &str) -> Option<Color>;
+	fn get(s: &str, colors: &options::Colors) -> Option<Color>;
 }
 
 impl VariableColors for () {
-	fn get(_s: &str) -> Option<Color> {
+	fn get(_s: &str, _colors: &options::Colors) -> Option<Color> {
 		None
 	}
 }
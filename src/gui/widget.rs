@@ -4,6 +4,7 @@ use crate::prelude::*;
 use rand::Rng;
 use sdl2::rect::{Point, Rect};
 use sdl2::render::Texture;
+use std::rc::Rc;
 
 pub struct SoulJar<'texture> {
 	souls: Vec<Soul>,
@@ -34,6 +35,7 @@ pub fn menu(
 	options: &Options,
 	input_mode: &input::Mode,
 	world_manager: &world::Manager,
+	resources: &resource::Manager,
 ) {
 	for (i, color) in [(0x14, 0x17, 0x14), (0xE3, 0xBD, 0xEF), (0x14, 0x17, 0x14)]
 		.into_iter()
@@ -64,22 +66,227 @@ pub fn menu(
 				options.ui.colors.normal_mode,
 				&menu.typography.annotation,
 			);
-			world_manager.console.draw(menu);
+			world_manager.console.draw(menu, &options.ui.console_filter);
 		}
-		input::Mode::Cast => {
+		input::Mode::Paused { selection } => {
+			menu.label_styled(
+				"Paused",
+				options.ui.colors.settings_mode,
+				&menu.typography.annotation,
+			);
+			pause_menu(menu, *selection);
+		}
+		input::Mode::Results { selection, .. } => {
+			menu.label_styled(
+				"Run Over",
+				options.ui.colors.settings_mode,
+				&menu.typography.annotation,
+			);
+			for (i, option) in input::ResultsOption::ALL.into_iter().enumerate() {
+				if i == *selection {
+					menu.label_color(&format!("> {}", option.name()), (0xFF, 0xFF, 0x00, 0xFF));
+				} else {
+					menu.label(&format!("  {}", option.name()));
+				}
+			}
+		}
+		input::Mode::Cast { selection } => {
 			menu.label_styled(
 				"Cast",
 				options.ui.colors.cast_mode,
 				&menu.typography.annotation,
 			);
-			spell_menu(menu, &world_manager.next_character().borrow());
+			spell_menu(
+				menu,
+				&world_manager.next_character().borrow(),
+				world_manager.level.economy.overcast_cost,
+				*selection,
+			);
+		}
+		input::Mode::Delay { selection } => {
+			menu.label_styled(
+				"Delay",
+				options.ui.colors.delay_mode,
+				&menu.typography.annotation,
+			);
+			delay_menu(menu, world_manager, *selection);
+		}
+		input::Mode::Dialogue { selection } => {
+			menu.label_styled(
+				"Dialogue",
+				options.ui.colors.dialogue_mode,
+				&menu.typography.annotation,
+			);
+			dialogue_menu(menu, world_manager, *selection);
+			world_manager.console.draw(menu, &options.ui.console_filter);
+		}
+		input::Mode::Inventory { selection } => {
+			menu.label_styled(
+				"Inventory",
+				options.ui.colors.inventory_mode,
+				&menu.typography.annotation,
+			);
+			let mut list_fn = |menu: &mut gui::Context| {
+				inventory_menu(menu, world_manager, resources, *selection);
+			};
+			let mut tooltip_fn = |menu: &mut gui::Context| {
+				if let Some(key) = world_manager.inventory.get(*selection) {
+					item_tooltip(menu, key, resources);
+				}
+			};
+			menu.hsplit(&mut [
+				Some((&mut list_fn) as &mut dyn FnMut(&mut gui::Context)),
+				Some(&mut tooltip_fn),
+			]);
+		}
+		input::Mode::Settings {
+			selection,
+			listening,
+		} => {
+			menu.label_styled(
+				"Settings",
+				options.ui.colors.settings_mode,
+				&menu.typography.annotation,
+			);
+			settings_menu(menu, options, *selection, *listening);
 		}
-		input::Mode::Cursor { x, y, .. } => {
+		input::Mode::FreeLook(free_look) => {
 			menu.label_styled(
-				"Cursor",
+				"Free Look",
 				options.ui.colors.cursor_mode,
 				&menu.typography.annotation,
 			);
+			let following = free_look.follow.and_then(|id| {
+				world_manager
+					.characters
+					.iter()
+					.find(|character| character.borrow().id == id)
+			});
+			match following {
+				Some(piece) => {
+					menu.label(&format!("Following {}", piece.borrow().sheet.nouns.name))
+				}
+				None => menu.label("Not following anyone"),
+			}
+			world_manager.console.draw(menu, &options.ui.console_filter);
+		}
+		input::Mode::Inspect { x, y, .. } => {
+			menu.label_styled(
+				"Inspect",
+				options.ui.colors.cursor_mode,
+				&menu.typography.annotation,
+			);
+			let visible = world_manager.visible_pieces();
+			let selected = visible.iter().find(|piece| {
+				let piece = piece.borrow();
+				(piece.x, piece.y) == (*x, *y)
+			});
+			if let Some(selected) = selected {
+				let piece = selected.borrow();
+				let mut stats_fn = |menu: &mut gui::Context| {
+					character_info(menu, &piece);
+					stat_breakdown(menu, &piece);
+				};
+				let mut statuses_fn = |menu: &mut gui::Context| {
+					status_durations(menu, &piece);
+				};
+				let mut techniques_fn = |menu: &mut gui::Context| {
+					techniques(menu, &piece, world_manager.level.economy.overcast_cost);
+				};
+				menu.hsplit(&mut [
+					Some((&mut stats_fn) as &mut dyn FnMut(&mut gui::Context)),
+					Some(&mut statuses_fn),
+					Some(&mut techniques_fn),
+				]);
+			} else {
+				menu.label(
+					"There's nothing to inspect here. (Tab to cycle through visible pieces)",
+				);
+			}
+		}
+		input::Mode::Orders { selection } => {
+			menu.label_styled(
+				"Orders",
+				options.ui.colors.settings_mode,
+				&menu.typography.annotation,
+			);
+			orders_menu(menu, world_manager, *selection);
+		}
+		input::Mode::Options { selection, .. } => {
+			menu.label_styled(
+				"Options",
+				options.ui.colors.settings_mode,
+				&menu.typography.annotation,
+			);
+			options_menu(menu, options, *selection);
+		}
+		input::Mode::Filter { selection } => {
+			menu.label_styled(
+				"Console Filter",
+				options.ui.colors.settings_mode,
+				&menu.typography.annotation,
+			);
+			filter_menu(menu, &options.ui.console_filter, *selection);
+		}
+		input::Mode::Saves {
+			slots,
+			selection,
+			renaming,
+			..
+		} => {
+			menu.label_styled(
+				"Saves",
+				options.ui.colors.settings_mode,
+				&menu.typography.annotation,
+			);
+			saves_menu(
+				menu,
+				resources,
+				&options.controls,
+				slots,
+				*selection,
+				renaming.as_deref(),
+			);
+		}
+		input::Mode::DeveloperConsole { input } => {
+			menu.label_styled(
+				&format!("> {input}"),
+				options.ui.colors.search_mode,
+				&menu.typography.annotation,
+			);
+			world_manager.console.draw(menu, &options.ui.console_filter);
+		}
+		input::Mode::Search { query } => {
+			menu.label_styled(
+				&format!("Search: {query}"),
+				options.ui.colors.search_mode,
+				&menu.typography.annotation,
+			);
+			world_manager.console.draw(menu, &options.ui.console_filter);
+		}
+		input::Mode::Cursor {
+			x,
+			y,
+			origin,
+			range,
+			..
+		} => {
+			if world_manager
+				.current_floor
+				.within_range(*origin, (*x, *y), *range)
+			{
+				menu.label_styled(
+					"Cursor",
+					options.ui.colors.cursor_mode,
+					&menu.typography.annotation,
+				);
+			} else {
+				menu.label_styled(
+					"Cursor (out of range)",
+					options.ui.colors.out_of_range,
+					&menu.typography.annotation,
+				);
+			}
 			if let Some(selected_character) = world_manager.get_character_at(*x, *y) {
 				let mut character_fn = |menu: &mut gui::Context| {
 					character_info(menu, &selected_character.borrow());
@@ -92,26 +299,443 @@ pub fn menu(
 					Some(&mut buff_fn),
 				]);
 			} else {
-				world_manager.console.draw(menu);
+				world_manager.console.draw(menu, &options.ui.console_filter);
+			}
+		}
+	}
+}
+
+pub fn spell_menu(
+	gui: &mut gui::Context,
+	character: &character::Piece,
+	overcast_cost: u32,
+	selection: usize,
+) {
+	for (i, (spell, letter)) in character.spells.iter().zip('a'..='z').enumerate() {
+		let outlook = spell.outlook(character, None, overcast_cost);
+		// Weak spells are castable, but rarely the best choice.
+		let color = match outlook.affinity {
+			_ if !outlook.castable => (255, 0, 0, 255),
+			spell::Affinity::Weak => (0x80, 0x80, 0x80, 0xFF),
+			_ => gui.typography.color,
+		};
+		let marker = if i == selection { '>' } else { ' ' };
+		let mut text = format!("{marker} ({letter}) {} - {} SP", spell.name, spell.level);
+		if let Some(magnitude) = outlook.max_magnitude {
+			text += &format!(" ({magnitude})");
+		}
+		if outlook.castable && character.sp < i32::from(spell.level) {
+			text += " (overcast)";
+		}
+		gui.label_color(&text, color);
+	}
+}
+
+pub fn inventory_menu(
+	gui: &mut gui::Context,
+	world_manager: &world::Manager,
+	resources: &resource::Manager,
+	selection: usize,
+) {
+	if world_manager.inventory.is_empty() {
+		gui.label("The inventory is empty.");
+	}
+	// Keep the selection in view without any real scrolling.
+	let visible = (gui.rect.height() / 25).max(1) as usize;
+	let skip = (selection + 1).saturating_sub(visible);
+	for (i, key) in world_manager.inventory.iter().enumerate().skip(skip) {
+		let name = resources
+			.get_item(key)
+			.map_or(key.as_str(), |x| x.name.as_str());
+		if i == selection {
+			gui.label_color(&format!("> {name}"), (0xFF, 0xFF, 0x00, 0xFF));
+		} else {
+			gui.label(&format!("  {name}"));
+		}
+	}
+}
+
+pub fn item_tooltip(gui: &mut gui::Context, key: &str, resources: &resource::Manager) {
+	let Ok(item) = resources.get_item(key) else {
+		gui.label(key);
+		return;
+	};
+	gui.horizontal();
+	gui.htexture(resources.get_texture(&item.icon), 32);
+	gui.advance(8, 0);
+	gui.label(&item.name);
+	gui.vertical();
+	gui.label(&item.description);
+	match &item.usage {
+		item::Usage::None => {}
+		item::Usage::Consume { .. } => gui.label("Consumable"),
+		item::Usage::Equip { slot, bonuses } => {
+			gui.label(&format!("Equipment ({slot:?})"));
+			for (name, bonus) in [
+				("Heart", bonuses.heart),
+				("Soul", bonuses.soul),
+				("Power", bonuses.power),
+				("Defense", bonuses.defense),
+				("Magic", bonuses.magic),
+				("Resistance", bonuses.resistance),
+			] {
+				if bonus > 0 {
+					gui.label(&format!("+{bonus} {name}"));
+				}
 			}
 		}
 	}
 }
 
-pub fn spell_menu(gui: &mut gui::Context, character: &character::Piece) {
-	for (spell, letter) in character.spells.iter().zip('a'..='z') {
-		let color = if spell.castable_by(character) {
+pub fn settings_menu(gui: &mut gui::Context, options: &Options, selection: usize, listening: bool) {
+	let bindings = options.controls.bindings();
+	if listening {
+		gui.label(&format!(
+			"Press a key to bind to {} (Escape to cancel)",
+			bindings[selection].0
+		));
+	} else {
+		gui.label("Confirm to add a key, Backspace to clear, Escape to save and exit");
+	}
+	let visible = (gui.rect.height() / 25).saturating_sub(1).max(1) as usize;
+	let skip = (selection + 1).saturating_sub(visible);
+	for (i, (name, triggers)) in bindings.iter().enumerate().skip(skip) {
+		let conflicts = options.controls.conflicts(name);
+		let mut text = format!("{name}: {triggers}");
+		if !conflicts.is_empty() {
+			text += &format!(" (conflicts with {})", conflicts.join(", "));
+		}
+		let color = if !conflicts.is_empty() {
+			(0xFF, 0x00, 0x00, 0xFF)
+		} else if i == selection {
+			(0xFF, 0xFF, 0x00, 0xFF)
+		} else {
 			gui.typography.color
+		};
+		if i == selection {
+			gui.label_color(&format!("> {text}"), color);
 		} else {
-			(255, 0, 0, 255)
+			gui.label_color(&format!("  {text}"), color);
+		}
+	}
+}
+
+pub fn orders_menu(gui: &mut gui::Context, world_manager: &world::Manager, selection: usize) {
+	let followers = world_manager.followers();
+	if followers.is_empty() {
+		gui.label("Nobody in the party is awaiting orders.");
+		return;
+	}
+	gui.label("Left and right to change orders, Escape to exit");
+	for (i, follower) in followers.iter().enumerate() {
+		let follower = follower.borrow();
+		let order = follower
+			.order
+			.map_or_else(|| String::from("None"), |order| format!("{order:?}"));
+		let text = format!("{}: {order}", follower.sheet.nouns.name);
+		if i == selection {
+			gui.label_color(&format!("> {text}"), (0xFF, 0xFF, 0x00, 0xFF));
+		} else {
+			gui.label(&format!("  {text}"));
+		}
+	}
+}
+
+/// A summary of the run which just ended, drawn over the board.
+pub fn results(
+	gui: &mut gui::Context,
+	world_manager: &world::Manager,
+	options: &Options,
+	outcome: morgue::Outcome,
+) {
+	let stats = &world_manager.stats;
+	let title = match outcome {
+		morgue::Outcome::Defeat => "Defeat",
+		morgue::Outcome::Victory => "Victory",
+		morgue::Outcome::Quit => "Abandoned",
+	};
+	gui.label_styled(title, gui.typography.color, &gui.typography.title);
+	gui.label(&format!(
+		"Floor {} of {} (deepest {})",
+		world_manager.location.floor, world_manager.level.name, stats.deepest_floor
+	));
+	gui.label(&format!(
+		"{} turns, seed {}",
+		stats.turns(),
+		world_manager.seed
+	));
+	if outcome == morgue::Outcome::Defeat {
+		if let Some(cause) = &stats.cause_of_death {
+			gui.label(&format!("{cause}."));
+		}
+		if let Some(blow) = world_manager.console.last_combat_message() {
+			gui.label_color(blow, options.ui.colors.damage);
+		}
+	}
+	gui.advance(0, 8);
+
+	// Bars are relative to whoever dealt or received the most damage.
+	let damage = |table: &std::collections::HashMap<String, u32>, name: &str| {
+		table.get(name).copied().unwrap_or(0)
+	};
+	let most = world_manager
+		.party
+		.iter()
+		.map(|member| {
+			let name = &*member.piece.borrow().sheet.nouns.name;
+			damage(&stats.damage_dealt, name).max(damage(&stats.damage_received, name))
+		})
+		.max()
+		.unwrap_or(0)
+		.max(1) as f32;
+	for member in &world_manager.party {
+		let piece = member.piece.borrow();
+		let name = &*piece.sheet.nouns.name;
+		let dealt = damage(&stats.damage_dealt, name);
+		let received = damage(&stats.damage_received, name);
+		gui.label(&format!(
+			"{name}: {dealt} dealt, {received} received{}",
+			if piece.hp <= 0 { " (fallen)" } else { "" }
+		));
+		gui.progress_bar(
+			dealt as f32 / most,
+			member.accent_color,
+			(0x20, 0x20, 0x20, 0xFF),
+			10,
+			5,
+		);
+		gui.advance(0, 2);
+		gui.progress_bar(
+			received as f32 / most,
+			options.ui.colors.damage,
+			(0x20, 0x20, 0x20, 0xFF),
+			10,
+			5,
+		);
+		gui.advance(0, 4);
+	}
+
+	gui.advance(0, 8);
+	let mut kills: Vec<_> = stats.kills.iter().collect();
+	kills.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+	let kills = kills
+		.into_iter()
+		.map(|(name, count)| format!("{count} {name}"))
+		.collect::<Vec<_>>();
+	if kills.is_empty() {
+		gui.label("Kills: none");
+	} else {
+		gui.label(&format!("Kills: {}", kills.join(", ")));
+	}
+}
+
+/// The best runs within `scope`, with the current run's entry highlighted.
+pub fn leaderboard(
+	gui: &mut gui::Context,
+	leaderboard: &score::Leaderboard,
+	world_manager: &world::Manager,
+	outcome: morgue::Outcome,
+	rank: Option<usize>,
+	scope: score::Scope,
+) {
+	const SHOWN: usize = 5;
+	let score = score::Score::new(world_manager, outcome);
+	let placement = rank.map_or_else(|| String::from("unranked"), |rank| format!("#{}", rank + 1));
+	gui.label(&format!(
+		"Score: {} ({placement}) - {} depth, {} kills, {} speed, {}%",
+		score.total(),
+		score.depth,
+		score.kills,
+		score.speed,
+		score.modifier
+	));
+	gui.label(&format!(
+		"Leaderboard: {} (left and right to change)",
+		scope.name()
+	));
+	let filter = scope.filter(world_manager);
+	let current = rank.and_then(|rank| leaderboard.entries.get(rank));
+	for (i, entry) in leaderboard.filtered(&filter).take(SHOWN).enumerate() {
+		let text = format!(
+			"{}. {} - {} ({}, floor {}, {} turns)",
+			i + 1,
+			entry.score.total(),
+			entry.name,
+			entry.level,
+			entry.floor,
+			entry.turns
+		);
+		if current.is_some_and(|current| std::ptr::eq(current, entry)) {
+			gui.label_color(&text, (0xFF, 0xFF, 0x00, 0xFF));
+		} else {
+			gui.label(&text);
+		}
+	}
+}
+
+pub fn pause_menu(gui: &mut gui::Context, selection: usize) {
+	for (i, option) in input::PauseOption::ALL.into_iter().enumerate() {
+		if i == selection {
+			gui.label_color(&format!("> {}", option.name()), (0xFF, 0xFF, 0x00, 0xFF));
+		} else {
+			gui.label(&format!("  {}", option.name()));
+		}
+	}
+}
+
+pub fn options_menu(gui: &mut gui::Context, options: &Options, selection: usize) {
+	gui.label("Left and right to adjust, Escape to save and exit");
+	for (i, setting) in options::Setting::ALL.into_iter().enumerate() {
+		let text = format!("{}: {}", setting.name(), setting.value(options));
+		if i == selection {
+			gui.label_color(&format!("> {text}"), (0xFF, 0xFF, 0x00, 0xFF));
+		} else {
+			gui.label(&format!("  {text}"));
+		}
+	}
+}
+
+/// Describe how long ago a save was written.
+fn age(timestamp: u64) -> String {
+	let now = std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs();
+	let minutes = now.saturating_sub(timestamp) / 60;
+	match minutes {
+		0 => "just now".into(),
+		1..60 => format!("{minutes} min ago"),
+		60..1440 => format!("{} h ago", minutes / 60),
+		_ => format!("{} days ago", minutes / 1440),
+	}
+}
+
+pub fn saves_menu(
+	gui: &mut gui::Context,
+	resources: &resource::Manager<'_>,
+	controls: &options::Controls,
+	slots: &[save::Slot],
+	selection: usize,
+	renaming: Option<&str>,
+) {
+	const ICON_SIZE: u32 = 24;
+	if renaming.is_some() {
+		gui.label("Type a new name, Confirm to rename, Escape to cancel");
+	} else {
+		gui.label(&format!(
+			"Confirm to save or load, {} to overwrite, {} to rename, {} to delete",
+			controls.overwrite_save, controls.rename_save, controls.delete_save
+		));
+	}
+	let color = |i| {
+		if i == selection {
+			(0xFF, 0xFF, 0x00, 0xFF)
+		} else {
+			gui.typography.color
+		}
+	};
+	let marker = |i| if i == selection { "> " } else { "  " };
+	gui.label_color(&format!("{}New save", marker(0)), color(0));
+	for (i, slot) in slots.iter().enumerate() {
+		let i = i + 1;
+		let metadata = &slot.metadata;
+		let name = match renaming {
+			Some(name) if i == selection => format!("{name}_"),
+			_ => metadata.name.clone(),
 		};
+		gui.horizontal();
+		gui.label_color(marker(i), color(i));
+		for icon in &metadata.party {
+			gui.htexture(resources.get_texture(icon), ICON_SIZE);
+		}
+		gui.advance(4, 0);
 		gui.label_color(
-			&format!("({letter}) {} - {} SP", spell.name, spell.level),
-			color,
+			&format!(
+				"{name} - {} floor {} ({})",
+				metadata.level,
+				metadata.floor,
+				age(metadata.timestamp)
+			),
+			color(i),
 		);
+		gui.vertical();
+	}
+}
+
+pub fn filter_menu(gui: &mut gui::Context, filter: &console::Filter, selection: usize) {
+	gui.label("Confirm to toggle, Escape to save and exit");
+	for (i, (name, shown)) in filter.toggles().into_iter().enumerate() {
+		let check = if shown { 'x' } else { ' ' };
+		if i == selection {
+			gui.label_color(&format!("> [{check}] {name}"), (0xFF, 0xFF, 0x00, 0xFF));
+		} else {
+			gui.label(&format!("  [{check}] {name}"));
+		}
 	}
 }
 
+pub fn delay_menu(gui: &mut gui::Context, world_manager: &world::Manager, selection: usize) {
+	let next_character = world_manager.next_character();
+	for (i, (ally, letter)) in world_manager
+		.allies_of(next_character)
+		.zip('a'..='z')
+		.enumerate()
+	{
+		let ally = ally.borrow();
+		let text = format!(
+			"({letter}) {} - acts in {} aut",
+			ally.sheet.nouns.name, ally.action_delay
+		);
+		if i == selection {
+			gui.label_color(&format!("> {text}"), (0xFF, 0xFF, 0x00, 0xFF));
+		} else {
+			gui.label(&format!("  {text}"));
+		}
+	}
+}
+
+pub fn dialogue_menu(gui: &mut gui::Context, world_manager: &world::Manager, selection: usize) {
+	let Some(state) = &world_manager.dialogue else {
+		return;
+	};
+	let mut choices = state.choices().peekable();
+	if choices.peek().is_none() {
+		gui.label("(confirm) Continue");
+	}
+	for (i, ((_, choice), letter)) in choices.zip('a'..='z').enumerate() {
+		if i == selection {
+			gui.label_color(
+				&format!("> ({letter}) {}", choice.text),
+				(0xFF, 0xFF, 0x00, 0xFF),
+			);
+		} else {
+			gui.label(&format!("  ({letter}) {}", choice.text));
+		}
+	}
+}
+
+/// How many upcoming turns the turn order strip shows.
+const TURN_FORECAST_LENGTH: usize = 8;
+
+/// A row of icons showing who will act next, starting with whoever is acting now.
+pub fn turn_order(
+	gui: &mut gui::Context,
+	world_manager: &world::Manager,
+	resources: &resource::Manager<'_>,
+) {
+	const ICON_SIZE: u32 = 24;
+	gui.label("Turn Order");
+	gui.horizontal();
+	for character in world_manager.turn_forecast(TURN_FORECAST_LENGTH) {
+		gui.htexture(
+			resources.get_texture(&character.borrow().sheet.icon),
+			ICON_SIZE,
+		);
+		gui.advance(4, 0);
+	}
+	gui.vertical();
+}
+
 pub fn pamphlet(
 	pamphlet: &mut gui::Context,
 	world_manager: &world::Manager,
@@ -157,6 +781,10 @@ pub fn pamphlet(
 					rect.height(),
 				));
 				let piece = character_id.piece.borrow();
+				let focused = world_manager
+					.party
+					.get(world_manager.focus)
+					.is_some_and(|member| Rc::ptr_eq(&member.piece, &character_id.piece));
 				let texture = resources.get_texture("luvui_sleep");
 				character_thinking(
 					character_id,
@@ -165,6 +793,15 @@ pub fn pamphlet(
 					layout.flipped,
 					|player_window| {
 						character_info(player_window, &piece);
+						player_window.label(&format!(
+							"{}{}",
+							if piece.player_controlled {
+								"Controlled"
+							} else {
+								"Independent"
+							},
+							if focused { " (watching)" } else { "" },
+						));
 						character_buffs(player_window, &piece);
 					},
 				);
@@ -174,21 +811,49 @@ pub fn pamphlet(
 	}
 	pamphlet.advance(0, 10);
 
+	if !world_manager.quests.is_empty() {
+		pamphlet.label("Quests");
+		for quest in &world_manager.quests {
+			match quest.current() {
+				Some(stage) => {
+					pamphlet.label(&format!("{}: {}", quest.quest.name, stage.description));
+				}
+				None => pamphlet.label(&format!("{} (complete)", quest.quest.name)),
+			}
+		}
+		pamphlet.advance(0, 10);
+	}
+
+	turn_order(pamphlet, world_manager, resources);
+	pamphlet.advance(0, 10);
+
 	let mut inventory_fn = |pamphlet: &mut gui::Context| {
 		pamphlet.label("Inventory");
+		if world_manager.level.attrition.enabled {
+			pamphlet.label(&format!("Supplies: {}", world_manager.supplies));
+		}
 		let mut items = world_manager.inventory.iter().peekable();
 		while items.peek().is_some() {
 			let textures_per_row = pamphlet.rect.width() / (32 + 8);
 			pamphlet.horizontal();
 			for _ in 0..textures_per_row {
 				if let Some(item_name) = items.next() {
-					pamphlet.htexture(resources.get_texture(item_name), 32);
+					pamphlet.htexture(resources.get_item_texture(item_name), 32);
 					pamphlet.advance(8, 0);
 				}
 			}
 			pamphlet.vertical();
 			pamphlet.advance(8, 8);
 		}
+		for member in &world_manager.party {
+			let piece = member.piece.borrow();
+			for equipped in &piece.equipment {
+				let name = resources
+					.get_item(&equipped.item)
+					.map_or(equipped.item.as_str(), |x| x.name.as_str());
+				pamphlet.label(&format!("{}: {name}", piece.sheet.nouns.name));
+			}
+		}
 	};
 	let mut souls_fn = |pamphlet: &mut gui::Context| {
 		const SOUL_SIZE: u32 = 50;
@@ -413,3 +1078,90 @@ fn character_buffs(player_window: &mut gui::Context<'_, '_, '_>, piece: &charact
 		)
 	}
 }
+
+/// Where each stat's value comes from: the sheet, equipment, and statuses.
+fn stat_breakdown(gui: &mut gui::Context<'_, '_, '_>, piece: &character::Piece) {
+	let base = piece.sheet.stats();
+	let character::StatOutcomes {
+		stats,
+		buffs,
+		debuffs,
+	} = piece.stat_outcomes();
+	for (name, stat, base, buff, debuff) in [
+		("Heart", stats.heart, base.heart, buffs.heart, debuffs.heart),
+		("Soul", stats.soul, base.soul, buffs.soul, debuffs.soul),
+		("Power", stats.power, base.power, buffs.power, debuffs.power),
+		(
+			"Defense",
+			stats.defense,
+			base.defense,
+			buffs.defense,
+			debuffs.defense,
+		),
+		("Magic", stats.magic, base.magic, buffs.magic, debuffs.magic),
+		(
+			"Resistance",
+			stats.resistance,
+			base.resistance,
+			buffs.resistance,
+			debuffs.resistance,
+		),
+	] {
+		let mut text = format!("{name}: {stat} ({base}");
+		if buff > 0 {
+			text += &format!(" +{buff}");
+		}
+		if debuff > 0 {
+			text += &format!(" -{debuff}");
+		}
+		text.push(')');
+		gui.label(&text);
+	}
+}
+
+fn status_durations(gui: &mut gui::Context<'_, '_, '_>, piece: &character::Piece) {
+	gui.label("Statuses");
+	if piece.statuses.is_empty() {
+		gui.label("  None");
+	}
+	for status in piece.statuses.values() {
+		let duration = match status.duration {
+			status::Duration::Rest => "until rest".into(),
+			status::Duration::Turn => "this turn".into(),
+			status::Duration::Turns(1) => "1 turn left".into(),
+			status::Duration::Turns(turns) => format!("{turns} turns left"),
+		};
+		gui.label_color(&format!("{} ({duration})", status.tip()), status.color());
+	}
+}
+
+/// Attacks and spells, with their magnitudes evaluated for this piece.
+fn techniques(gui: &mut gui::Context<'_, '_, '_>, piece: &character::Piece, overcast_cost: u32) {
+	gui.label("Attacks");
+	for attack in &piece.attacks {
+		gui.label(&format!(
+			"  {}: {} ({})",
+			attack.name,
+			u32::evalv(&attack.magnitude, piece),
+			attack.magnitude.source
+		));
+	}
+	gui.label("Spells");
+	for spell in &piece.spells {
+		let outlook = spell.outlook(piece, None, overcast_cost);
+		let mut text = format!(
+			"  {} - {} SP, {:?}",
+			spell.name, spell.level, outlook.affinity
+		);
+		let spell::Parameters::Target { magnitude, .. } = &spell.parameters;
+		if let (Some(expression), Some(magnitude)) = (magnitude, outlook.max_magnitude) {
+			text += &format!(": {magnitude} ({})", expression.source);
+		}
+		let color = if outlook.castable {
+			gui.typography.color
+		} else {
+			(255, 0, 0, 255)
+		};
+		gui.label_color(&text, color);
+	}
+}
@@ -1,6 +1,11 @@
 use crate::prelude::*;
+use options::Key;
+use sdl2::controller::{Axis, GameController};
+use sdl2::mouse::MouseButton;
+use sdl2::rect::Rect;
 use sdl2::{event::Event, keyboard::Keycode};
-use tracing::warn;
+use std::rc::Rc;
+use tracing::{info, warn};
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct SinWave(u16);
@@ -21,160 +26,1155 @@ impl SinWave {
 
 /// Anything beyond the bare minimum for cursor input.
 /// This doesn't have anything to do with input,
-/// but it is exclusive to the `Cursor` `input::Mode`.
+/// but it is exclusive to the `Cursor` and `Inspect` `input::Mode`s.
 #[derive(Clone, Copy, Default)]
 pub struct CursorState {
 	pub float: SinWave,
+	/// Index into `world::Manager::targets` of the last target cycled to.
+	pub target: Option<usize>,
+}
+
+/// How far away an enemy must be to interrupt a held turn.
+const HOLD_RANGE: u32 = 1;
+
+/// Entries of the pause menu.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauseOption {
+	Resume,
+	Options,
+	Saves,
+	Quit,
+}
+
+impl PauseOption {
+	pub const ALL: [PauseOption; 4] = [
+		PauseOption::Resume,
+		PauseOption::Options,
+		PauseOption::Saves,
+		PauseOption::Quit,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			PauseOption::Resume => "Resume",
+			PauseOption::Options => "Options",
+			PauseOption::Saves => "Saves",
+			PauseOption::Quit => "Quit",
+		}
+	}
+}
+
+/// Entries of the end-of-run screen.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResultsOption {
+	WriteMorgue,
+	NewRun,
+	Quit,
+}
+
+impl ResultsOption {
+	pub const ALL: [ResultsOption; 3] = [
+		ResultsOption::WriteMorgue,
+		ResultsOption::NewRun,
+		ResultsOption::Quit,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			ResultsOption::WriteMorgue => "Write morgue file",
+			ResultsOption::NewRun => "New run with the same seed",
+			ResultsOption::Quit => "Quit",
+		}
+	}
 }
 
 pub enum Mode {
 	Normal,
-	Cast,
+	/// Time is frozen until the player resumes.
+	Paused {
+		selection: usize,
+	},
+	/// The run is over; the world is frozen while its results are shown.
+	Results {
+		outcome: morgue::Outcome,
+		selection: usize,
+		/// Where the run placed on the leaderboard, if it made it on.
+		rank: Option<usize>,
+		/// Which runs the leaderboard is showing.
+		scope: score::Scope,
+	},
+	/// Select a spell, by letter or by moving `selection`.
+	Cast {
+		selection: usize,
+	},
+	/// Select an ally to delay the current turn for.
+	Delay {
+		selection: usize,
+	},
+	/// Select a response to the active dialogue.
+	Dialogue {
+		selection: usize,
+	},
+	/// Browse the party's inventory.
+	Inventory {
+		selection: usize,
+	},
+	/// Rebind controls.
+	///
+	/// While `listening`, the next key pressed is bound to the selected control.
+	Settings {
+		selection: usize,
+		listening: bool,
+	},
+	/// Look around the floor with a camera detached from the party.
+	FreeLook(draw::FreeLook),
+	/// Look over any visible piece in detail.
+	Inspect {
+		x: i32,
+		y: i32,
+		state: CursorState,
+	},
+	/// Give orders to the party members the player isn't controlling.
+	Orders {
+		selection: usize,
+	},
+	/// Adjust display and audio options, which take effect immediately.
+	Options {
+		selection: usize,
+		/// Opened from the pause menu, which time stays frozen for and closing returns to.
+		paused: bool,
+	},
+	/// Choose which kinds of console messages are shown.
+	Filter {
+		selection: usize,
+	},
+	/// Save, load, and manage saved runs.
+	///
+	/// The first entry creates a new save; the rest are `slots`.
+	Saves {
+		slots: Vec<save::Slot>,
+		selection: usize,
+		/// While renaming the selected save, the name typed so far.
+		renaming: Option<String>,
+		/// Opened from the pause menu, which time stays frozen for and closing returns to.
+		paused: bool,
+	},
+	/// Type something to find in the console.
+	Search {
+		query: String,
+	},
+	/// Type a command to execute.
+	DeveloperConsole {
+		input: String,
+	},
 	Cursor {
 		x: i32,
 		y: i32,
+		/// Where the line to the cursor is drawn from.
+		origin: (i32, i32),
+		/// How far from `origin` the cursor may select, if there's a limit.
+		range: Option<u32>,
 		submitted: bool,
 		state: CursorState,
 	},
 }
 
+impl Mode {
+	/// Whether time is frozen, such as while the pause menu (or a menu opened from it) is open.
+	pub fn paused(&self) -> bool {
+		matches!(
+			self,
+			Mode::Paused { .. }
+				| Mode::Results { .. }
+				| Mode::Options { paused: true, .. }
+				| Mode::Saves { paused: true, .. }
+		)
+	}
+
+	/// The mode to return to after closing a menu opened from `option`.
+	fn close(paused: bool, option: PauseOption) -> Mode {
+		if paused {
+			Mode::Paused {
+				selection: PauseOption::ALL
+					.iter()
+					.position(|x| *x == option)
+					.unwrap_or_default(),
+			}
+		} else {
+			Mode::Normal
+		}
+	}
+}
+
+/// The menu entry that `key` picks by letter, if it's one of a to z.
+fn letter_index(key: Key) -> Option<usize> {
+	let index = key.keycode()?.into_i32() - Keycode::A.into_i32();
+	(0..26).contains(&index).then_some(index as usize)
+}
+
+/// Where `selection` moves to if `key` is up or down, staying within `length` entries.
+fn move_selection(key: Key, options: &Options, selection: usize, length: usize) -> Option<usize> {
+	if options.controls.up.contains(key) {
+		Some(selection.saturating_sub(1))
+	} else if options.controls.down.contains(key) {
+		Some((selection + 1).min(length.saturating_sub(1)))
+	} else {
+		None
+	}
+}
+
+/// How far a stick must be tilted before it points in a direction.
+const STICK_DEADZONE: i16 = i16::MAX / 2;
+
+/// Game controllers are opened as they're plugged in, and closed when they're removed.
+pub struct Controllers {
+	subsystem: sdl2::GameControllerSubsystem,
+	open: Vec<Controller>,
+}
+
+struct Controller {
+	controller: GameController,
+	stick: (i16, i16),
+	/// Only changes in direction are reported, so that holding the stick doesn't repeat inputs.
+	direction: Option<character::OrdDir>,
+}
+
+impl Controllers {
+	pub fn new(subsystem: sdl2::GameControllerSubsystem) -> Self {
+		Self {
+			subsystem,
+			open: Vec::new(),
+		}
+	}
+
+	fn connect(&mut self, index: u32) {
+		match self.subsystem.open(index) {
+			Ok(controller) => {
+				info!("connected {}", controller.name());
+				self.open.push(Controller {
+					controller,
+					stick: (0, 0),
+					direction: None,
+				});
+			}
+			Err(msg) => warn!("failed to open game controller: {msg}"),
+		}
+	}
+
+	fn disconnect(&mut self, instance_id: u32) {
+		self.open
+			.retain(|x| x.controller.instance_id() != instance_id);
+	}
+
+	/// Returns a direction if the left stick has been tilted towards a new one.
+	fn tilt(&mut self, instance_id: u32, axis: Axis, value: i16) -> Option<character::OrdDir> {
+		use character::OrdDir;
+
+		let controller = self
+			.open
+			.iter_mut()
+			.find(|x| x.controller.instance_id() == instance_id)?;
+		match axis {
+			Axis::LeftX => controller.stick.0 = value,
+			Axis::LeftY => controller.stick.1 = value,
+			_ => return None,
+		}
+		let sign = |value: i16| {
+			if value > STICK_DEADZONE {
+				1
+			} else if value < -STICK_DEADZONE {
+				-1
+			} else {
+				0
+			}
+		};
+		let offset = (sign(controller.stick.0), sign(controller.stick.1));
+		let direction = OrdDir::ALL.into_iter().find(|x| x.as_offset() == offset);
+		if direction.is_some() && direction != controller.direction {
+			controller.direction = direction;
+			direction
+		} else {
+			controller.direction = direction;
+			None
+		}
+	}
+}
+
 pub enum Response {
 	Exit,
 	Fullscreen,
 	Debug,
+	/// Options were changed and should be saved.
+	SaveOptions,
+	/// Execute a command from the developer console.
+	Command(String),
+	/// Write the world to a new save.
+	Save,
+	/// Write the world over an existing save.
+	Overwrite(save::Slot),
+	/// Replace the world with a saved one.
+	Load(std::path::PathBuf),
+	/// Write a morgue file for the run which just ended.
+	WriteMorgue(morgue::Outcome),
+	/// Start over with the same seed as the run which just ended.
+	NewRun,
+}
+
+/// Walk to a clicked tile, or select it when choosing a target.
+fn click_tile(world_manager: &mut world::Manager, mode: &mut Mode, x: i32, y: i32) {
+	let mut next_character = world_manager.next_character().borrow_mut();
+	if !next_character.controlled_by_player() {
+		return;
+	}
+	match mode {
+		Mode::Normal => {
+			if (next_character.x, next_character.y) != (x, y) {
+				next_character.chase_point = Some((x, y));
+			}
+		}
+		Mode::Cursor {
+			x: cursor_x,
+			y: cursor_y,
+			origin,
+			range,
+			submitted,
+			..
+		} => {
+			*cursor_x = x;
+			*cursor_y = y;
+			if world_manager
+				.current_floor
+				.within_range(*origin, (x, y), *range)
+			{
+				*submitted = true;
+			} else {
+				drop(next_character);
+				world_manager
+					.console
+					.print_unimportant("That's out of range.".into());
+			}
+		}
+		Mode::Cast { .. }
+		| Mode::Delay { .. }
+		| Mode::Dialogue { .. }
+		| Mode::Inventory { .. }
+		| Mode::Settings { .. }
+		| Mode::Inspect { .. }
+		| Mode::FreeLook(_)
+		| Mode::Orders { .. }
+		| Mode::Options { .. }
+		| Mode::Filter { .. }
+		| Mode::Saves { .. }
+		| Mode::Paused { .. }
+		| Mode::Results { .. }
+		| Mode::Search { .. }
+		| Mode::DeveloperConsole { .. } => {}
+	}
 }
 
 pub fn world(
 	event_pump: &mut sdl2::EventPump,
 	world_manager: &mut world::Manager,
 	resources: &resource::Manager,
+	controllers: &mut Controllers,
+	world_area: Rect,
 	mode: &mut Mode,
-	options: &Options,
+	options: &mut Options,
 ) -> Result<Option<Response>> {
 	for event in event_pump.poll_iter() {
-		match event {
+		let key = match event {
 			Event::Quit { .. } => return Ok(Some(Response::Exit)),
 			Event::KeyDown {
 				keycode: Some(keycode),
 				..
+			} => Key::Keyboard(keycode),
+			Event::ControllerButtonDown { button, .. } => Key::Button(button),
+			Event::ControllerAxisMotion {
+				which, axis, value, ..
+			} => {
+				let Some(dir) = controllers.tilt(which, axis, value) else {
+					continue;
+				};
+				Key::Stick(dir)
+			}
+			Event::MouseButtonDown {
+				mouse_btn: MouseButton::Left,
+				x,
+				y,
+				..
 			} => {
+				if world_area.contains_point((x, y)) {
+					let scale = draw::board_scale(mode, options.ui.board_scale);
+					let (x_off, y_off) = draw::board_offset(world_manager, mode, world_area, scale);
+					let (x, y) = draw::tile_at(
+						x - world_area.x() - (x_off as f32 * scale) as i32,
+						y - world_area.y() - (y_off as f32 * scale) as i32,
+						scale,
+					);
+					// Clicking a piece while looking around follows it.
+					if let Mode::FreeLook(free_look) = mode {
+						if let Some(piece) = world_manager.get_character_at(x, y) {
+							free_look.follow = Some(piece.borrow().id);
+						}
+					} else {
+						click_tile(world_manager, mode, x, y);
+					}
+				}
+				continue;
+			}
+			Event::MouseMotion {
+				mousestate,
+				xrel,
+				yrel,
+				..
+			} => {
+				let scale = draw::board_scale(mode, options.ui.board_scale);
+				if let Mode::FreeLook(free_look) = mode {
+					if mousestate.left() {
+						free_look.drag(xrel, yrel, scale);
+					}
+				}
+				continue;
+			}
+			Event::MouseWheel { y, .. } => {
+				if let Mode::FreeLook(free_look) = mode {
+					free_look.zoom(y);
+				} else {
+					world_manager.console.scroll(y, &options.ui.console_filter);
+				}
+				continue;
+			}
+			Event::TextInput { text, .. } => {
+				match mode {
+					Mode::Search { query } => query.push_str(&text),
+					Mode::Saves {
+						renaming: Some(name),
+						..
+					} => name.push_str(&text),
+					// The key that opened the console (and closes it again) shouldn't end up in it.
+					Mode::DeveloperConsole { input }
+						if !options.controls.developer_console.types(&text) =>
+					{
+						input.push_str(&text)
+					}
+					_ => (),
+				}
+				continue;
+			}
+			Event::ControllerDeviceAdded { which, .. } => {
+				controllers.connect(which);
+				continue;
+			}
+			Event::ControllerDeviceRemoved { which, .. } => {
+				controllers.disconnect(which);
+				continue;
+			}
+			_ => continue,
+		};
+
+		if let Mode::Search { query } = mode {
+			if options.controls.escape.contains(key) {
+				world_manager.console.clear_search();
+				*mode = Mode::Normal;
+			} else if options.controls.confirm.contains(key) {
+				world_manager
+					.console
+					.search(query, &options.ui.console_filter);
+			} else if key == Key::Keyboard(Keycode::Backspace) {
+				query.pop();
+			}
+			continue;
+		}
+		if let Mode::DeveloperConsole { input } = mode {
+			if options.controls.escape.contains(key)
+				|| options.controls.developer_console.contains(key)
+			{
+				*mode = Mode::Normal;
+			} else if options.controls.confirm.contains(key) {
+				let command = std::mem::take(input);
+				*mode = Mode::Normal;
+				return Ok(Some(Response::Command(command)));
+			} else if key == Key::Keyboard(Keycode::Backspace) {
+				input.pop();
+			}
+			continue;
+		}
+		// Inspecting doesn't take a turn, so it's allowed even while other pieces are acting.
+		if let Mode::Inspect { x, y, state } = mode {
+			let directions = [
+				(-1, 0, &options.controls.left),
+				(1, 0, &options.controls.right),
+				(0, -1, &options.controls.up),
+				(0, 1, &options.controls.down),
+				(-1, -1, &options.controls.up_left),
+				(1, -1, &options.controls.up_right),
+				(-1, 1, &options.controls.down_left),
+				(1, 1, &options.controls.down_right),
+			];
+			for (x_off, y_off, triggers) in directions {
+				if triggers.contains(key) {
+					*x += x_off;
+					*y += y_off;
+				}
+			}
+			if options.controls.cycle_target.contains(key) {
+				let pieces = world_manager.visible_pieces();
+				if !pieces.is_empty() {
+					let next = state.target.map_or(0, |i| (i + 1) % pieces.len());
+					let piece = pieces[next].borrow();
+					*x = piece.x;
+					*y = piece.y;
+					state.target = Some(next);
+				}
+			}
+			if options.controls.escape.contains(key)
+				|| options.controls.confirm.contains(key)
+				|| options.controls.inspect.contains(key)
+			{
+				*mode = Mode::Normal;
+			}
+			continue;
+		}
+		// Neither does looking around.
+		if let Mode::FreeLook(free_look) = mode {
+			let directions = [
+				(-1, 0, &options.controls.left),
+				(1, 0, &options.controls.right),
+				(0, -1, &options.controls.up),
+				(0, 1, &options.controls.down),
+				(-1, -1, &options.controls.up_left),
+				(1, -1, &options.controls.up_right),
+				(-1, 1, &options.controls.down_left),
+				(1, 1, &options.controls.down_right),
+			];
+			for (x_off, y_off, triggers) in directions {
+				if triggers.contains(key) {
+					free_look.pan(x_off as f64, y_off as f64);
+				}
+			}
+			if options.controls.zoom_in.contains(key) {
+				free_look.zoom(1);
+			} else if options.controls.zoom_out.contains(key) {
+				free_look.zoom(-1);
+			}
+			// Cycling through visible pieces follows each in turn.
+			if options.controls.cycle_target.contains(key) {
+				let pieces = world_manager.visible_pieces();
+				let next = free_look
+					.follow
+					.and_then(|id| pieces.iter().position(|piece| piece.borrow().id == id))
+					.map_or(0, |i| i + 1);
+				free_look.follow = pieces
+					.get(next % pieces.len().max(1))
+					.map(|piece| piece.borrow().id);
+			}
+			if options.controls.escape.contains(key) || options.controls.free_look.contains(key) {
+				*mode = Mode::Normal;
+			}
+			continue;
+		}
+		// Orders don't take a turn either.
+		if let Mode::Orders { selection } = mode {
+			let followers = world_manager.followers();
+			if options.controls.escape.contains(key) || options.controls.orders.contains(key) {
+				*mode = Mode::Normal;
+			} else if options.controls.up.contains(key) {
+				*selection = selection.saturating_sub(1);
+			} else if options.controls.down.contains(key) {
+				*selection = (*selection + 1).min(followers.len().saturating_sub(1));
+			} else if let Some(follower) = followers.get(*selection) {
+				let step = if options.controls.left.contains(key) {
+					Some(-1)
+				} else if options.controls.right.contains(key) {
+					Some(1)
+				} else {
+					None
+				};
+				if let Some(step) = step {
+					let mut follower = follower.borrow_mut();
+					follower.order = ai::Order::cycle(follower.order, step);
+				}
+			}
+			continue;
+		}
+		if let Mode::Options { selection, paused } = mode {
+			let setting = options::Setting::ALL[*selection];
+			if options.controls.escape.contains(key) || options.controls.options.contains(key) {
+				*mode = Mode::close(*paused, PauseOption::Options);
+				return Ok(Some(Response::SaveOptions));
+			} else if options.controls.up.contains(key) {
+				*selection = selection.saturating_sub(1);
+			} else if options.controls.down.contains(key) {
+				*selection = (*selection + 1).min(options::Setting::ALL.len() - 1);
+			} else if options.controls.left.contains(key) {
+				setting.adjust(options, -1);
+			} else if options.controls.right.contains(key) {
+				setting.adjust(options, 1);
+			}
+			continue;
+		}
+		if let Mode::Results {
+			outcome,
+			selection,
+			scope,
+			..
+		} = mode
+		{
+			if options.controls.left.contains(key) {
+				*scope = scope.cycle(-1);
+			} else if options.controls.right.contains(key) {
+				*scope = scope.cycle(1);
+			} else if options.controls.up.contains(key) {
+				*selection = selection.saturating_sub(1);
+			} else if options.controls.down.contains(key) {
+				*selection = (*selection + 1).min(ResultsOption::ALL.len() - 1);
+			} else if options.controls.confirm.contains(key) {
+				return Ok(Some(match ResultsOption::ALL[*selection] {
+					ResultsOption::WriteMorgue => Response::WriteMorgue(*outcome),
+					ResultsOption::NewRun => Response::NewRun,
+					ResultsOption::Quit => Response::Exit,
+				}));
+			}
+			continue;
+		}
+		if let Mode::Paused { selection } = mode {
+			if options.controls.escape.contains(key) {
+				*mode = Mode::Normal;
+			} else if options.controls.up.contains(key) {
+				*selection = selection.saturating_sub(1);
+			} else if options.controls.down.contains(key) {
+				*selection = (*selection + 1).min(PauseOption::ALL.len() - 1);
+			} else if options.controls.confirm.contains(key) {
+				match PauseOption::ALL[*selection] {
+					PauseOption::Resume => *mode = Mode::Normal,
+					PauseOption::Options => {
+						*mode = Mode::Options {
+							selection: 0,
+							paused: true,
+						}
+					}
+					PauseOption::Saves => {
+						*mode = Mode::Saves {
+							slots: save::list(save::directory()),
+							selection: 0,
+							renaming: None,
+							paused: true,
+						};
+					}
+					PauseOption::Quit => return Ok(Some(Response::Exit)),
+				}
+			}
+			continue;
+		}
+		// Saving doesn't take a turn, and loading replaces the world entirely.
+		if let Mode::Saves {
+			slots,
+			selection,
+			renaming,
+			paused,
+		} = mode
+		{
+			if let Some(name) = renaming {
+				if options.controls.escape.contains(key) {
+					*renaming = None;
+				} else if options.controls.confirm.contains(key) {
+					if let Some(slot) = selection.checked_sub(1).and_then(|i| slots.get_mut(i)) {
+						if let Err(msg) = save::rename(slot, std::mem::take(name)) {
+							world_manager
+								.console
+								.print_danger(format!("Failed to rename save: {msg}"));
+						}
+					}
+					*renaming = None;
+				} else if key == Key::Keyboard(Keycode::Backspace) {
+					name.pop();
+				}
+				continue;
+			}
+			let slot = selection.checked_sub(1).and_then(|i| slots.get(i));
+			if options.controls.escape.contains(key) || options.controls.saves.contains(key) {
+				*mode = Mode::close(*paused, PauseOption::Saves);
+			} else if options.controls.up.contains(key) {
+				*selection = selection.saturating_sub(1);
+			} else if options.controls.down.contains(key) {
+				*selection = (*selection + 1).min(slots.len());
+			} else if options.controls.confirm.contains(key) {
+				let response = match slot {
+					Some(slot) => Response::Load(slot.path.clone()),
+					None => Response::Save,
+				};
+				*mode = Mode::close(*paused, PauseOption::Saves);
+				return Ok(Some(response));
+			} else if let Some(slot) = slot {
+				if options.controls.rename_save.contains(key) {
+					*renaming = Some(String::new());
+				} else if options.controls.delete_save.contains(key) {
+					if let Err(msg) = save::delete(slot) {
+						world_manager
+							.console
+							.print_danger(format!("Failed to delete save: {msg}"));
+					}
+					*slots = save::list(save::directory());
+					*selection = (*selection).min(slots.len());
+				} else if options.controls.overwrite_save.contains(key) {
+					let slot = slot.clone();
+					*mode = Mode::close(*paused, PauseOption::Saves);
+					return Ok(Some(Response::Overwrite(slot)));
+				}
+			}
+			continue;
+		}
+		if let Mode::Filter { selection } = mode {
+			let toggles = options.ui.console_filter.toggles_mut();
+			if options.controls.escape.contains(key)
+				|| options.controls.console_filter.contains(key)
+			{
+				*mode = Mode::Normal;
+				return Ok(Some(Response::SaveOptions));
+			} else if options.controls.up.contains(key) {
+				*selection = selection.saturating_sub(1);
+			} else if options.controls.down.contains(key) {
+				*selection = (*selection + 1).min(toggles.len() - 1);
+			} else if options.controls.confirm.contains(key) {
+				if let Some(toggle) = toggles.into_iter().nth(*selection) {
+					*toggle ^= true;
+				}
+			}
+			continue;
+		}
+		// The console can be scrolled from any mode, unless a key is being rebound.
+		if !matches!(
+			mode,
+			Mode::Settings {
+				listening: true,
+				..
+			}
+		) {
+			let console = &mut world_manager.console;
+			if options.controls.page_up.contains(key) {
+				console.page(1, &options.ui.console_filter);
+				continue;
+			}
+			if options.controls.page_down.contains(key) {
+				console.page(-1, &options.ui.console_filter);
+				continue;
+			}
+			if options.controls.scroll_top.contains(key) {
+				console.scroll_to_top(&options.ui.console_filter);
+				continue;
+			}
+			if options.controls.scroll_bottom.contains(key) {
+				console.scroll_to_bottom();
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.search.contains(key) {
+				*mode = Mode::Search {
+					query: String::new(),
+				};
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.console_filter.contains(key) {
+				*mode = Mode::Filter { selection: 0 };
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.options.contains(key) {
+				*mode = Mode::Options {
+					selection: 0,
+					paused: false,
+				};
+				continue;
+			}
+			// Pausing also stops whatever the acting piece was doing on its own.
+			if matches!(mode, Mode::Normal) && options.controls.escape.contains(key) {
 				let mut next_character = world_manager.next_character().borrow_mut();
-				if next_character.player_controlled {
-					match mode {
-						Mode::Normal => {
-							// Eventually this will be a more involved binding.
-							if options.controls.escape.contains(keycode) {
-								return Ok(Some(Response::Exit));
-							}
-							if options.controls.debug.contains(keycode) {
-								return Ok(Some(Response::Debug));
-							}
-							if options.controls.fullscreen.contains(keycode) {
-								return Ok(Some(Response::Fullscreen));
-							}
-							let directions = [
-								(&options.controls.left, character::OrdDir::Left),
-								(&options.controls.right, character::OrdDir::Right),
-								(&options.controls.up, character::OrdDir::Up),
-								(&options.controls.down, character::OrdDir::Down),
-								(&options.controls.up_left, character::OrdDir::UpLeft),
-								(&options.controls.up_right, character::OrdDir::UpRight),
-								(&options.controls.down_left, character::OrdDir::DownLeft),
-								(&options.controls.down_right, character::OrdDir::DownRight),
-							];
-							for (triggers, direction) in directions {
-								if triggers.contains(keycode) {
-									next_character.next_action =
-										Some(character::Action::Move(direction));
-								}
-							}
+				if next_character.controlled_by_player() {
+					next_character.chase_point = None;
+					next_character.autopilot = None;
+				}
+				*mode = Mode::Paused { selection: 0 };
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.saves.contains(key) {
+				*mode = Mode::Saves {
+					slots: save::list(save::directory()),
+					selection: 0,
+					renaming: None,
+					paused: false,
+				};
+				continue;
+			}
+			// Switching between party members doesn't take a turn.
+			if matches!(mode, Mode::Normal) && options.controls.cycle_target.contains(key) {
+				world_manager.cycle_focus();
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.take_control.contains(key) {
+				world_manager.toggle_control();
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.orders.contains(key) {
+				*mode = Mode::Orders { selection: 0 };
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.free_look.contains(key) {
+				*mode = Mode::FreeLook(draw::FreeLook::new(world_manager.camera()));
+				continue;
+			}
+			if matches!(mode, Mode::Normal) && options.controls.inspect.contains(key) {
+				let piece = world_manager.next_character().borrow();
+				*mode = Mode::Inspect {
+					x: piece.x,
+					y: piece.y,
+					state: CursorState::default(),
+				};
+				continue;
+			}
+			if matches!(mode, Mode::Normal)
+				&& options.ui.developer_console
+				&& options.controls.developer_console.contains(key)
+			{
+				*mode = Mode::DeveloperConsole {
+					input: String::new(),
+				};
+				continue;
+			}
+		}
 
-							if options.controls.cast.contains(keycode) {
-								*mode = Mode::Cast;
-							}
+		let mut next_character = world_manager.next_character().borrow_mut();
+		if next_character.controlled_by_player() {
+			match mode {
+				Mode::Normal => {
+					next_character.chase_point = None;
+					next_character.autopilot = None;
+					if options.controls.debug.contains(key) {
+						return Ok(Some(Response::Debug));
+					}
+					if options.controls.fullscreen.contains(key) {
+						return Ok(Some(Response::Fullscreen));
+					}
+					let directions = [
+						(&options.controls.left, character::OrdDir::Left),
+						(&options.controls.right, character::OrdDir::Right),
+						(&options.controls.up, character::OrdDir::Up),
+						(&options.controls.down, character::OrdDir::Down),
+						(&options.controls.up_left, character::OrdDir::UpLeft),
+						(&options.controls.up_right, character::OrdDir::UpRight),
+						(&options.controls.down_left, character::OrdDir::DownLeft),
+						(&options.controls.down_right, character::OrdDir::DownRight),
+					];
+					for (triggers, direction) in directions {
+						if triggers.contains(key) {
+							next_character.next_action = Some(character::Action::Move(direction));
+						}
+					}
+
+					if options.controls.cast.contains(key) {
+						*mode = Mode::Cast { selection: 0 };
+					}
+
+					if options.controls.wait.contains(key) {
+						next_character.next_action = Some(character::Action::Wait(TURN));
+					}
+
+					if options.controls.delay.contains(key) {
+						*mode = Mode::Delay { selection: 0 };
+					}
+
+					if options.controls.inventory.contains(key) {
+						*mode = Mode::Inventory { selection: 0 };
+					}
+
+					if options.controls.settings.contains(key) {
+						*mode = Mode::Settings {
+							selection: 0,
+							listening: false,
+						};
+					}
+
+					if options.controls.hold.contains(key) {
+						next_character.next_action = Some(character::Action::Hold(HOLD_RANGE));
+					}
+
+					if options.controls.rest.contains(key) {
+						next_character.next_action = Some(character::Action::Rest);
+					}
+
+					if options.controls.explore.contains(key) {
+						next_character.autopilot = Some(character::Autopilot::Explore {
+							interruptions: options.explore,
+							seen: None,
+							items: None,
+							hp: None,
+						});
+					}
+
+					if options.controls.auto_fight.contains(key) {
+						next_character.autopilot = Some(character::Autopilot::Fight { seen: None });
+					}
+
+					if options.controls.repeat_cast.contains(key) {
+						if let Some((spell, target)) = next_character.last_cast.clone() {
+							next_character.autopilot = Some(character::Autopilot::Recast {
+								spell,
+								target,
+								seen: None,
+							});
+						} else {
+							world_manager
+								.console
+								.print_unimportant("There's no spell to repeat.".into());
+						}
+					}
 
-							let (x, y) = (next_character.x, next_character.y);
-							drop(next_character);
-
-							if options.controls.underfoot.contains(keycode) {
-								match world_manager.current_floor.map.get(y, x) {
-									Some(floor::Tile::Floor) => {
-										world_manager.console.print_unimportant(
-											"There's nothing on the ground here.".into(),
-										);
-									}
-									Some(floor::Tile::Exit) => {
-										world_manager.new_floor(resources)?;
-									}
-									None => {
-										world_manager
-											.console
-											.print_unimportant("That's the void.".into());
-									}
-									Some(floor::Tile::Wall) => (),
+					let (x, y) = (next_character.x, next_character.y);
+					drop(next_character);
+
+					if options.controls.underfoot.contains(key) {
+						if world_manager.get_items_at(x, y).next().is_some() {
+							world_manager.next_character().borrow_mut().next_action =
+								Some(character::Action::PickUp);
+						} else {
+							match world_manager.current_floor.map.get(y, x) {
+								Some(floor::Tile::Floor) => {
+									world_manager.console.print_unimportant(
+										"There's nothing on the ground here.".into(),
+									);
+								}
+								Some(floor::Tile::Exit) => {
+									world_manager.new_floor(resources)?;
+								}
+								Some(floor::Tile::Rest) => {
+									world_manager.console.print_unimportant(
+										"This looks like a good place to rest.".into(),
+									);
 								}
+								None => {
+									world_manager
+										.console
+										.print_unimportant("That's the void.".into());
+								}
+								Some(floor::Tile::Wall) => (),
 							}
+						}
+					}
 
-							if options.controls.talk.contains(keycode) {
-								world_manager.console.say("Luvui".into(), "Meow!".into());
-								world_manager
-									.console
-									.say("Aris".into(), "I am a kitty :3".into());
-							}
+					if options.controls.recruit.contains(key) {
+						let candidate = (-1..=1)
+							.flat_map(|yoff| (-1..=1).map(move |xoff| (x + xoff, y + yoff)))
+							.filter_map(|(x, y)| world_manager.get_character_at(x, y))
+							.find(|piece| {
+								!world_manager
+									.party
+									.iter()
+									.any(|member| Rc::ptr_eq(&member.piece, piece))
+							});
+						let recruited = candidate.is_some_and(|candidate| {
+							let accent_color = options.ui.colors.accent(world_manager.party.len());
+							world_manager.recruit(&candidate, accent_color)
+						});
+						if !recruited {
+							world_manager.console.print_unimportant(
+								"There's nobody here willing to join you.".into(),
+							);
 						}
-						Mode::Cast => {
-							if options.controls.escape.contains(keycode) {
-								*mode = Mode::Normal;
-							}
+					}
 
-							// TODO: just make an array of keys in the options file or something.
-							let selected_index = (keycode.into_i32()) - (Keycode::A.into_i32());
-							if (0..=26).contains(&selected_index)
-								&& (selected_index as usize) < next_character.spells.len()
-							{
-								next_character.next_action = Some(character::Action::Cast(
-									next_character.spells[selected_index as usize].clone(),
-								))
-							}
+					if options.controls.talk.contains(key) {
+						let listener = world_manager.next_character().clone();
+						let speaker = (-1..=1)
+							.flat_map(|yoff| (-1..=1).map(move |xoff| (x + xoff, y + yoff)))
+							.filter_map(|(x, y)| world_manager.get_character_at(x, y))
+							.find_map(|piece| {
+								let dialogue = piece.borrow().sheet.dialogue.clone()?;
+								Some((piece.clone(), dialogue))
+							});
+						if let Some((speaker, dialogue)) = speaker {
+							world_manager
+								.start_dialogue(&dialogue, speaker, listener, resources)?;
+							*mode = Mode::Dialogue { selection: 0 };
+						} else {
+							world_manager
+								.console
+								.print_unimportant("There's nobody here to talk to.".into());
+						}
+					}
+				}
+				Mode::Cast { selection } => {
+					let length = next_character.spells.len();
+					if let Some(moved) = move_selection(key, options, *selection, length) {
+						*selection = moved;
+						continue;
+					}
+					// TODO: just make an array of keys in the options file or something.
+					let selected_index = if options.controls.confirm.contains(key) {
+						Some(*selection)
+					} else {
+						letter_index(key)
+					};
+					if let Some(spell) = selected_index.and_then(|i| next_character.spells.get(i)) {
+						next_character.next_action = Some(character::Action::Cast(spell.clone()));
+					}
+					*mode = Mode::Normal;
+				}
+				Mode::Delay { selection } => {
+					drop(next_character);
+					let next_character = world_manager.next_character();
+					let length = world_manager.allies_of(next_character).count();
+					if let Some(moved) = move_selection(key, options, *selection, length) {
+						*selection = moved;
+						continue;
+					}
+					let selected_index = if options.controls.confirm.contains(key) {
+						Some(*selection)
+					} else {
+						letter_index(key)
+					};
+					if let Some(ally) =
+						selected_index.and_then(|i| world_manager.allies_of(next_character).nth(i))
+					{
+						next_character.borrow_mut().next_action =
+							Some(character::Action::Delay(ally.clone()));
+					}
+					*mode = Mode::Normal;
+				}
+				Mode::Dialogue { selection } => {
+					drop(next_character);
+					let Some(state) = &mut world_manager.dialogue else {
+						*mode = Mode::Normal;
+						continue;
+					};
+					let length = state.choices().count();
+					if options.controls.escape.contains(key) {
+						world_manager.dialogue = None;
+						*mode = Mode::Normal;
+					} else if let Some(moved) = move_selection(key, options, *selection, length) {
+						*selection = moved;
+					} else if options.controls.confirm.contains(key) {
+						// Nodes without choices just continue.
+						let choice = state.choices().nth(*selection).map(|(i, _)| i);
+						state.selection = Some(choice);
+						*selection = 0;
+					} else if let Some(choice) = letter_index(key)
+						.and_then(|i| state.choices().nth(i))
+						.map(|(i, _)| i)
+					{
+						state.selection = Some(Some(choice));
+						*selection = 0;
+					}
+				}
+				Mode::Inventory { selection } => {
+					let length = world_manager.inventory.len();
+					if options.controls.escape.contains(key)
+						|| options.controls.inventory.contains(key)
+					{
+						*mode = Mode::Normal;
+					} else if options.controls.up.contains(key) {
+						*selection = selection.saturating_sub(1);
+					} else if options.controls.down.contains(key) {
+						*selection = (*selection + 1).min(length.saturating_sub(1));
+					} else if *selection < length {
+						if options.controls.confirm.contains(key) {
+							next_character.next_action =
+								Some(character::Action::UseItem(*selection));
+							*mode = Mode::Normal;
+						} else if options.controls.drop_item.contains(key) {
+							next_character.next_action =
+								Some(character::Action::DropItem(*selection));
 							*mode = Mode::Normal;
 						}
-						Mode::Cursor {
-							ref mut x,
-							ref mut y,
-							ref mut submitted,
-							..
-						} => {
-							if *submitted {
-								warn!("entering cursor mode after submission");
-							}
+					}
+				}
+				// Handled before any of the other modes.
+				Mode::Inspect { .. }
+				| Mode::FreeLook(_)
+				| Mode::Orders { .. }
+				| Mode::Options { .. }
+				| Mode::Filter { .. }
+				| Mode::Saves { .. }
+				| Mode::Paused { .. }
+				| Mode::Results { .. }
+				| Mode::Search { .. }
+				| Mode::DeveloperConsole { .. } => {}
+				Mode::Settings {
+					selection,
+					listening,
+				} => {
+					if *listening {
+						*listening = false;
+						if key != Key::Keyboard(Keycode::Escape) {
+							options.controls.bindings_mut()[*selection].1.bind(key);
+						}
+					} else if options.controls.escape.contains(key)
+						|| options.controls.settings.contains(key)
+					{
+						*mode = Mode::Normal;
+						return Ok(Some(Response::SaveOptions));
+					} else if options.controls.up.contains(key) {
+						*selection = selection.saturating_sub(1);
+					} else if options.controls.down.contains(key) {
+						*selection = (*selection + 1).min(options.controls.bindings().len() - 1);
+					} else if options.controls.confirm.contains(key) {
+						*listening = true;
+					} else if matches!(key, Key::Keyboard(Keycode::Backspace | Keycode::Delete)) {
+						options.controls.bindings_mut()[*selection].1.clear();
+					}
+				}
+				Mode::Cursor {
+					ref mut x,
+					ref mut y,
+					origin,
+					range,
+					ref mut submitted,
+					ref mut state,
+				} => {
+					if *submitted {
+						warn!("entering cursor mode after submission");
+					}
 
-							let directions = [
-								(-1, 0, &options.controls.left),
-								(1, 0, &options.controls.right),
-								(0, -1, &options.controls.up),
-								(0, 1, &options.controls.down),
-								(-1, -1, &options.controls.up_left),
-								(1, -1, &options.controls.up_right),
-								(-1, 1, &options.controls.down_left),
-								(1, 1, &options.controls.down_right),
-							];
-							for (x_off, y_off, triggers) in directions {
-								if triggers.contains(keycode) {
-									*x += x_off;
-									*y += y_off;
-								}
-							}
+					let directions = [
+						(-1, 0, &options.controls.left),
+						(1, 0, &options.controls.right),
+						(0, -1, &options.controls.up),
+						(0, 1, &options.controls.down),
+						(-1, -1, &options.controls.up_left),
+						(1, -1, &options.controls.up_right),
+						(-1, 1, &options.controls.down_left),
+						(1, 1, &options.controls.down_right),
+					];
+					for (x_off, y_off, triggers) in directions {
+						if triggers.contains(key) {
+							*x += x_off;
+							*y += y_off;
+						}
+					}
 
-							if options.controls.escape.contains(keycode) {
-								*mode = Mode::Normal;
-							} else if options.controls.confirm.contains(keycode) {
-								*submitted = true;
-							}
+					if options.controls.cycle_target.contains(key) {
+						let targets = world_manager.aiming_targets(*origin, *range);
+						if !targets.is_empty() {
+							let next = state.target.map_or(0, |i| (i + 1) % targets.len());
+							let target = targets[next].borrow();
+							*x = target.x;
+							*y = target.y;
+							state.target = Some(next);
+						}
+					}
+
+					if options.controls.escape.contains(key) {
+						*mode = Mode::Normal;
+					} else if options.controls.confirm.contains(key) {
+						if world_manager
+							.current_floor
+							.within_range(*origin, (*x, *y), *range)
+						{
+							*submitted = true;
+						} else {
+							world_manager
+								.console
+								.print_unimportant("That's out of range.".into());
 						}
 					}
 				}
 			}
-			_ => {}
 		}
 	}
 
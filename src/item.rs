@@ -1,3 +1,5 @@
+use crate::prelude::*;
+
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Piece {
 	pub item: Item,
@@ -9,3 +11,101 @@ pub struct Piece {
 pub struct Item {
 	pub name: String,
 }
+
+/// A possible drop, and how likely it is relative to others in the same table.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Drop {
+	pub item: String,
+	#[serde(default = "default_weight")]
+	pub weight: u32,
+	/// How many copies of the item to drop, as an inclusive range.
+	#[serde(default = "default_quantity")]
+	pub quantity: (u32, u32),
+}
+
+fn default_weight() -> u32 {
+	1
+}
+
+fn default_quantity() -> (u32, u32) {
+	(1, 1)
+}
+
+/// Decides what a character leaves behind when it dies.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DropTable {
+	/// Relative chance of dropping nothing at all.
+	pub nothing: u32,
+	pub entries: Vec<Drop>,
+}
+
+impl DropTable {
+	/// Returns the names of each item dropped (repeated for quantities greater than 1).
+	pub fn roll(&self, rng: &mut dyn rand::RngCore) -> Vec<String> {
+		let total = self.nothing + self.entries.iter().map(|x| x.weight).sum::<u32>();
+		if total == 0 {
+			return Vec::new();
+		}
+		let mut roll = rng.gen_range(0..total);
+		if roll < self.nothing {
+			return Vec::new();
+		}
+		roll -= self.nothing;
+		for entry in &self.entries {
+			if roll < entry.weight {
+				let (min, max) = entry.quantity;
+				let quantity = rng.gen_range(min..=max.max(min));
+				return (0..quantity).map(|_| entry.item.clone()).collect();
+			}
+			roll -= entry.weight;
+		}
+		Vec::new()
+	}
+}
+
+/// Where an item is worn. Each piece may wear one item in each slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Slot {
+	Weapon,
+	Armor,
+	Accessory,
+}
+
+/// What happens when an item in the inventory is used.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Usage {
+	/// The item can only be carried or dropped.
+	#[default]
+	None,
+	/// The item is used up, running `on_use` with `user` set to the piece using it.
+	Consume { on_use: script::MaybeInline },
+	/// The item is worn, adding `bonuses` to the wearer's stats.
+	Equip {
+		slot: Slot,
+		#[serde(default)]
+		bonuses: character::Stats,
+	},
+}
+
+/// Describes an item that may be found, carried, and used.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Definition {
+	pub name: String,
+	pub icon: String,
+	pub description: String,
+	#[serde(default)]
+	pub usage: Usage,
+	/// How many tiles of darkness this item lights up while lying on the floor.
+	#[serde(default)]
+	pub light: u32,
+}
+
+/// An item worn by a piece.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Equipped {
+	pub item: String,
+	pub slot: Slot,
+	pub bonuses: character::Stats,
+}
@@ -0,0 +1,112 @@
+//! Spells and skills that pieces learn as they level up.
+
+use crate::prelude::*;
+use std::rc::Rc;
+use tracing::error;
+
+/// What a sheet learns, and when.
+///
+/// Sheets name their learnset with the `learnset` field.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Learnset {
+	pub spells: Vec<SpellUnlock>,
+	pub skills: Vec<SkillUnlock>,
+	/// Run whenever a piece learns something from this learnset.
+	///
+	/// `piece` is the learner, and either `spell` (the spell's name) or `skill` is set.
+	pub on_learn: Option<script::MaybeInline>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpellUnlock {
+	pub level: u32,
+	pub spell: String,
+}
+
+/// Grants a minor skill, if the sheet doesn't already have one.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SkillUnlock {
+	pub level: u32,
+	pub minor: spell::Skill,
+}
+
+#[derive(Clone, Debug)]
+pub enum Ability {
+	Spell { key: String, spell: Rc<Spell> },
+	Skill(spell::Skill),
+}
+
+impl Ability {
+	/// A message for the console, containing noun tokens for the learner.
+	pub fn announcement(&self) -> String {
+		match self {
+			Ability::Spell { spell, .. } => format!("{{Address}} learned {}!", spell.name),
+			Ability::Skill(skill) => format!("{{Address's}} affinity for {skill} awakened!"),
+		}
+	}
+}
+
+/// An ability waiting to be announced by `world::Manager`,
+/// since pieces don't have access to the console or Lua when they level up.
+#[derive(Clone, Debug)]
+pub struct Lesson {
+	pub ability: Ability,
+	pub on_learn: Option<script::MaybeInline>,
+}
+
+impl Lesson {
+	/// Run the learnset's `on_learn` script, if it has one.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the script fails.
+	pub fn run_hook(&self, lua: &mlua::Lua, piece: &world::CharacterRef) -> mlua::Result<()> {
+		let Some(script) = &self.on_learn else {
+			return Ok(());
+		};
+		let globals = lua.globals();
+		globals.set("piece", piece.clone())?;
+		match &self.ability {
+			Ability::Spell { spell, .. } => {
+				globals.set("spell", spell.name.as_str())?;
+				globals.set("skill", mlua::Value::Nil)?;
+			}
+			Ability::Skill(skill) => {
+				globals.set("spell", mlua::Value::Nil)?;
+				globals.set("skill", skill.to_string())?;
+			}
+		}
+		script.exec(lua, "on_learn")
+	}
+}
+
+impl Learnset {
+	/// Everything learned upon reaching `level`.
+	///
+	/// Spells which can't be found are logged and skipped, so that a typo doesn't stop a level up.
+	pub fn abilities(&self, level: u32, resources: &resource::Catalog) -> Vec<Ability> {
+		let spells =
+			self.spells
+				.iter()
+				.filter(|x| x.level == level)
+				.filter_map(|x| match resources.get_spell(&x.spell) {
+					Ok(spell) => Some(Ability::Spell {
+						key: x.spell.clone(),
+						spell: spell.clone(),
+					}),
+					Err(msg) => {
+						error!("failed to learn {}: {msg}", x.spell);
+						None
+					}
+				});
+		let skills = self
+			.skills
+			.iter()
+			.filter(|x| x.level == level)
+			.map(|x| Ability::Skill(x.minor));
+		spells.chain(skills).collect()
+	}
+}
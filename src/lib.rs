@@ -6,25 +6,90 @@
 	clippy::unwrap_used
 )]
 
+pub mod api;
+
+// Everything else is internal to the game and its frontend; see `api`.
+#[doc(hidden)]
+pub mod ai;
+#[doc(hidden)]
+pub mod animation;
+#[doc(hidden)]
 pub mod attack;
+#[doc(hidden)]
+pub mod audio;
+#[doc(hidden)]
+pub mod biome;
+#[doc(hidden)]
 pub mod character;
+#[doc(hidden)]
 pub mod combat;
+#[doc(hidden)]
+pub mod command;
+#[doc(hidden)]
 pub mod console;
+#[doc(hidden)]
+pub mod creation;
+#[doc(hidden)]
+pub mod cutscene;
+#[doc(hidden)]
+pub mod definitions;
+#[doc(hidden)]
+pub mod dialogue;
+#[doc(hidden)]
 pub mod draw;
+#[doc(hidden)]
 pub mod expression;
+#[doc(hidden)]
 pub mod floor;
+#[doc(hidden)]
 pub mod gui;
+#[doc(hidden)]
 pub mod input;
+#[doc(hidden)]
 pub mod item;
+#[doc(hidden)]
+pub mod learnset;
+#[doc(hidden)]
+pub mod mapgen;
+#[doc(hidden)]
+pub mod morgue;
+#[doc(hidden)]
 pub mod nouns;
+#[doc(hidden)]
 pub mod options;
+#[doc(hidden)]
+pub mod profile;
+#[doc(hidden)]
+pub mod query;
+#[doc(hidden)]
+pub mod quest;
+#[doc(hidden)]
 pub mod resource;
+#[doc(hidden)]
+pub mod save;
+#[doc(hidden)]
+pub mod schedule;
+#[doc(hidden)]
+pub mod score;
+#[doc(hidden)]
 pub mod script;
+#[doc(hidden)]
+pub mod simulation;
+#[doc(hidden)]
 pub mod soul;
+#[doc(hidden)]
+pub mod spatial;
+#[doc(hidden)]
+pub mod spawn;
+#[doc(hidden)]
 pub mod spell;
+#[doc(hidden)]
 pub mod status;
+#[doc(hidden)]
 pub mod typography;
+#[doc(hidden)]
 pub mod vault;
+#[doc(hidden)]
 pub mod world;
 
 #[derive(Debug, thiserror::Error)]
@@ -45,8 +110,16 @@ pub enum Error {
 	Resource(#[from] resource::Error),
 	#[error(transparent)]
 	Expression(#[from] expression::Error),
+	#[error(transparent)]
+	Creation(#[from] creation::Error),
+	#[error(transparent)]
+	Command(#[from] command::Error),
+	#[error(transparent)]
+	ApiVersion(#[from] script::VersionError),
 }
 
+pub use definitions::lua_defs;
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// Arbitrary Unit of Time.
@@ -56,22 +129,25 @@ type Aut = u32;
 /// This is arbitrary, but it effectively makes Auts a fixed-point fraction,
 /// which is useful for dividing by common values like 2, 3, 4, and 6.
 // 12 is divisible by lots of nice numbers!
-#[allow(unused)] // I'm not using this anywhere yet, but it's useful to have written down.
 const TURN: Aut = 12;
 
 type Color = (u8, u8, u8, u8);
 
+#[doc(hidden)]
 pub mod prelude {
 	pub use super::*;
 
 	// Import redundant module::Struct names.
 	pub use attack::Attack;
+	pub use biome::Biome;
 	pub use console::Console;
 	pub use expression::Expression;
 	pub use floor::Floor;
 	pub use item::Item;
+	pub use learnset::Learnset;
 	pub use nouns::Nouns;
 	pub use options::Options;
+	pub use profile::Profile;
 	pub use script::Script;
 	pub use soul::Soul;
 	pub use spell::Spell;
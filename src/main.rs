@@ -1,7 +1,11 @@
 use esprit2::prelude::*;
-use sdl2::pixels::Color;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
+use sdl2::render::TextureCreator;
+use sdl2::surface::Surface;
 use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
 use std::process::exit;
 use tracing::{error, info};
 
@@ -18,14 +22,264 @@ fn update_delta(
 		/ 1000.0
 }
 
+/// Replace the world with a saved one, leaving it untouched if the save can't be loaded.
+fn load(
+	path: &std::path::Path,
+	world_manager: &mut world::Manager,
+	lua: &mlua::Lua,
+	options: &Options,
+) -> bool {
+	match save::open(path) {
+		Ok(mut loaded) => {
+			loaded.audio = world_manager.audio.clone();
+			match loaded.attach(lua, options) {
+				Ok(()) => {
+					info!("loaded {}", path.display());
+					*world_manager = loaded;
+					// Party members keep their accents in saves, so a color scheme has to reapply them.
+					if options.ui.color_scheme.is_some() {
+						for (i, member) in world_manager.party.iter_mut().enumerate() {
+							member.accent_color = options.ui.colors.accent(i);
+						}
+					}
+					true
+				}
+				Err(msg) => {
+					error!("failed to restore {}: {msg}", path.display());
+					false
+				}
+			}
+		}
+		Err(msg) => {
+			error!("failed to open {}: {msg}", path.display());
+			false
+		}
+	}
+}
+
+/// Start a run from the party blueprint.
+fn new_run(
+	party_blueprint: &[world::PartyReferenceBase],
+	resources: &resource::Manager,
+	lua: &mlua::Lua,
+	options: &Options,
+	profile: &mut Profile,
+	mixer: Option<&audio::Mixer>,
+) -> world::Manager {
+	let mut world_manager = world::Manager::new(
+		party_blueprint.iter().cloned(),
+		resources,
+		lua,
+		options,
+		profile,
+	)
+	.unwrap_or_else(|msg| {
+		error!("failed to initialize world manager: {msg}");
+		exit(1);
+	});
+	if let Some(mixer) = mixer {
+		world_manager.audio = mixer.handle();
+		if let Err(msg) = lua.globals().set("Audio", mixer.handle()) {
+			error!("failed to register audio handle: {msg}");
+		}
+	}
+	if let Err(msg) =
+		world_manager.apply_vault(1, 1, resources.get_vault("example").unwrap(), resources)
+	{
+		error!("failed to apply vault \"example\": {msg}");
+	}
+	world_manager
+}
+
+/// Remember what the party earned over the run.
+fn bank(world_manager: &world::Manager, profile: &mut Profile, profile_path: &std::path::Path) {
+	profile.bank(world_manager);
+	if let Err(msg) = profile.save(profile_path) {
+		error!("failed to save profile.toml: {msg}");
+	}
+}
+
+fn write_morgue(world_manager: &world::Manager, outcome: morgue::Outcome) {
+	match morgue::write(
+		options::user_directory().join("morgue"),
+		world_manager,
+		outcome,
+	) {
+		Ok(name) => info!("wrote {name}"),
+		Err(msg) => error!("failed to write morgue file: {msg}"),
+	}
+}
+
+/// How many turns each instance takes when running with `--simulate`.
+const SIMULATION_TICKS: u64 = 1000;
+
+/// Parse `--simulate <instances>` from the command line.
+fn simulate_count() -> Option<usize> {
+	let mut args = std::env::args().skip_while(|arg| arg != "--simulate");
+	args.next()?;
+	let count = args.next().and_then(|count| count.parse().ok());
+	if count.is_none() {
+		error!("--simulate expects a number of instances");
+		exit(1);
+	}
+	count
+}
+
+fn new_lua() -> mlua::Lua {
+	let lua = mlua::Lua::new();
+	lua.globals()
+		.get::<&str, mlua::Table>("package")
+		.unwrap()
+		.set(
+			"path",
+			options::resource_directory()
+				.join("scripts/?.lua")
+				.to_str()
+				.unwrap(),
+		)
+		.unwrap();
+	lua
+}
+
+fn open_resources<T>(texture_creator: &TextureCreator<T>) -> resource::Manager<'_> {
+	match resource::Manager::open(options::resource_directory(), texture_creator) {
+		Ok(resources) => resources,
+		Err(msg) => {
+			error!("failed to open resource directory: {msg}");
+			exit(1);
+		}
+	}
+}
+
+/// Open options.toml, replacing it with the defaults if it can't be read.
+fn open_options(options_path: &Path) -> Options {
+	Options::open(options_path).unwrap_or_else(|msg| {
+		// This is `info` because it's actually very expected for first-time players.
+		info!("failed to open options.toml ({msg})");
+		info!("initializing options.toml instead");
+		// Attempt to save the old file, in case it exists.
+		if let Err(msg) = fs::rename(options_path, options_path.with_extension("toml.old")) {
+			info!("failed to backup existing options.toml: {msg}");
+		} else {
+			info!("exiting options.toml was backed up to options.toml.old");
+		}
+		let options = Options::default();
+		if let Err(msg) = fs::write(options_path, toml::to_string(&options).unwrap()) {
+			error!("failed to initialize options.toml: {msg}");
+		}
+		options
+	})
+}
+
+fn open_profile(profile_path: &Path) -> Profile {
+	Profile::open(profile_path).unwrap_or_else(|msg| {
+		info!("failed to open profile.toml ({msg}); starting a new profile");
+		Profile::default()
+	})
+}
+
+/// The party each run starts with.
+fn party_blueprint(
+	resources: &resource::Manager,
+	options: &Options,
+) -> Vec<world::PartyReferenceBase> {
+	let sheet = |key| {
+		resources.get_sheet(key).cloned().unwrap_or_else(|msg| {
+			error!("failed to load party member {key}: {msg}");
+			exit(1);
+		})
+	};
+	let mut party_blueprint = vec![
+		world::PartyReferenceBase {
+			sheet: sheet("luvui"),
+			accent_color: options.ui.colors.accent(0),
+		},
+		world::PartyReferenceBase {
+			sheet: sheet("aris"),
+			accent_color: options.ui.colors.accent(1),
+		},
+	];
+	// A player-defined character replaces the party's leader.
+	let character_path = options::user_directory().join("character.toml");
+	if character_path.exists() {
+		let rules =
+			creation::Rules::open(options::resource_directory().join("character_creation.toml"))
+				.unwrap_or_else(|msg| {
+					info!("failed to open character_creation.toml ({msg}); using default rules");
+					creation::Rules::default()
+				});
+		match creation::Definition::open(&character_path)
+			.and_then(|definition| definition.build(&rules, resources))
+		{
+			Ok(base) => party_blueprint[0] = base,
+			Err(msg) => error!("failed to create character from character.toml: {msg}"),
+		}
+	}
+	party_blueprint
+}
+
+/// Headless mode: run AI-only copies of the starting world and report how quickly they went.
+///
+/// No window or audio device is opened; resources load through a software renderer instead.
+fn simulate(count: usize) {
+	let canvas = Surface::new(1, 1, PixelFormatEnum::RGBA32)
+		.and_then(Surface::into_canvas)
+		.unwrap_or_else(|msg| {
+			error!("failed to create a software renderer: {msg}");
+			exit(1);
+		});
+	let texture_creator = canvas.texture_creator();
+	let resources = open_resources(&texture_creator);
+	let options = open_options(&options::user_directory().join("options.toml"));
+	let party_blueprint = party_blueprint(&resources, &options);
+	// Simulated runs shouldn't affect the real profile.
+	let profile = open_profile(&options::user_directory().join("profile.toml"));
+	let mut instances = Vec::new();
+	for _ in 0..count {
+		let lua = new_lua();
+		let world = world::Manager::new(
+			party_blueprint.iter().cloned(),
+			&resources,
+			&lua,
+			&options,
+			&mut profile.clone(),
+		)
+		.unwrap_or_else(|msg| {
+			error!("failed to initialize world manager: {msg}");
+			exit(1);
+		});
+		instances.push(simulation::Instance::new(world, lua));
+	}
+	let report = simulation::run(&mut instances, SIMULATION_TICKS);
+	info!("simulation finished: {report}");
+}
+
 pub fn main() {
+	// Logging initialization.
+	tracing_subscriber::fmt::init();
+
+	if let Some(count) = simulate_count() {
+		simulate(count);
+		return;
+	}
+
 	// SDL initialization.
 	let sdl_context = sdl2::init().unwrap();
 	let ttf_context = sdl2::ttf::init().unwrap();
 	let video_subsystem = sdl_context.video().unwrap();
 	let timer_subsystem = sdl_context.timer().unwrap();
+	// Displays are assumed to be 96 DPI; denser ones magnify the interface.
+	let display_scale = video_subsystem
+		.display_dpi(0)
+		.map(|(dpi, _, _)| ((dpi / 96.0).clamp(1.0, 4.0) * 4.0).round() / 4.0)
+		.unwrap_or(1.0);
+	// Don't open a window larger than a small screen can show.
+	let (window_width, window_height) = video_subsystem
+		.display_usable_bounds(0)
+		.map(|bounds| (bounds.width().min(1280), bounds.height().min(720)))
+		.unwrap_or((1280, 720));
 	let window = video_subsystem
-		.window("Esprit 2", 1280, 720)
+		.window("Esprit 2", window_width, window_height)
 		.resizable()
 		.position_centered()
 		.build()
@@ -41,74 +295,62 @@ pub fn main() {
 		.unwrap();
 	let texture_creator = canvas.texture_creator();
 	let mut event_pump = sdl_context.event_pump().unwrap();
+	let mut controllers = input::Controllers::new(sdl_context.game_controller().unwrap());
 
 	let mut current_time = timer_subsystem.performance_counter() as f64;
 	let mut last_time = current_time;
 
-	// Logging initialization.
-	tracing_subscriber::fmt::init();
+	// Audio is optional; the game is still playable without it.
+	let mut mixer = audio::Mixer::open(options::resource_directory())
+		.map_err(|msg| error!("failed to open audio device: {msg}"))
+		.ok();
 
 	// Game initialization.
-	let resources = match resource::Manager::open(options::resource_directory(), &texture_creator) {
-		Ok(resources) => resources,
-		Err(msg) => {
-			error!("failed to open resource directory: {msg}");
-			exit(1);
-		}
-	};
+	let resources = open_resources(&texture_creator);
 	let options_path = options::user_directory().join("options.toml");
-	let options = Options::open(&options_path).unwrap_or_else(|msg| {
-		// This is `info` because it's actually very expected for first-time players.
-		info!("failed to open options.toml ({msg})");
-		info!("initializing options.toml instead");
-		// Attempt to save the old file, in case it exists.
-		if let Err(msg) = fs::rename(&options_path, options_path.with_extension("toml.old")) {
-			info!("failed to backup existing options.toml: {msg}");
-		} else {
-			info!("exiting options.toml was backed up to options.toml.old");
+	let mut options = open_options(&options_path);
+	// A color scheme replaces the configured colors, but shouldn't overwrite them when saving.
+	let user_colors = options.ui.colors.clone();
+	if let Some(scheme) = &options.ui.color_scheme {
+		match resources.get_color_scheme(scheme) {
+			Ok(colors) => options.ui.colors = colors.clone(),
+			Err(msg) => error!("failed to load color scheme {scheme}: {msg}"),
 		}
-		let options = Options::default();
-		if let Err(msg) = fs::write(&options_path, toml::to_string(&options).unwrap()) {
-			error!("failed to initialize options.toml: {msg}");
-		}
-		options
+	}
+	let party_blueprint = party_blueprint(&resources, &options);
+	let lua = new_lua();
+	let leaderboard_path = options::user_directory().join("leaderboard.toml");
+	let mut leaderboard = score::Leaderboard::open(&leaderboard_path).unwrap_or_else(|msg| {
+		info!("failed to open leaderboard.toml ({msg}); starting a new leaderboard");
+		score::Leaderboard::default()
 	});
-	// Create a piece for the player, and register it with the world manager.
-	let party_blueprint = [
-		world::PartyReferenceBase {
-			sheet: "luvui",
-			accent_color: (0xDA, 0x2D, 0x5C, 0xFF),
-		},
-		world::PartyReferenceBase {
-			sheet: "aris",
-			accent_color: (0x0C, 0x94, 0xFF, 0xFF),
-		},
-	];
-	let lua = mlua::Lua::new();
-	lua.globals()
-		.get::<&str, mlua::Table>("package")
-		.unwrap()
-		.set(
-			"path",
-			options::resource_directory()
-				.join("scripts/?.lua")
-				.to_str()
-				.unwrap(),
-		)
-		.unwrap();
-	let mut world_manager =
-		world::Manager::new(party_blueprint.into_iter(), &resources, &lua, &options)
-			.unwrap_or_else(|msg| {
-				error!("failed to initialize world manager: {msg}");
-				exit(1);
-			});
-	if let Err(msg) =
-		world_manager.apply_vault(1, 1, resources.get_vault("example").unwrap(), &resources)
-	{
-		error!("failed to apply vault \"example\": {msg}");
+	let profile_path = options::user_directory().join("profile.toml");
+	let mut profile = open_profile(&profile_path);
+
+	let mut world_manager = new_run(
+		&party_blueprint,
+		&resources,
+		&lua,
+		&options,
+		&mut profile,
+		mixer.as_ref(),
+	);
+
+	let saves_directory = save::directory();
+	// Whether the current run has been saved (or loaded from a save),
+	// in which case quitting doesn't end it and it must not be banked yet.
+	let mut resumable = false;
+	if std::env::args().any(|arg| arg == "--recover") {
+		match save::latest_emergency(&saves_directory) {
+			Some(path) => {
+				resumable = load(&path, &mut world_manager, &lua, &options);
+			}
+			None => info!("--recover was given, but there are no emergency saves"),
+		}
 	}
 
-	let typography = Typography::new(&options.ui.typography, &ttf_context);
+	let mut typography = Typography::new(&options.ui.typography, &ttf_context);
+	let mut typography_options = options.ui.typography.clone();
 
 	let mut soul_jar = gui::widget::SoulJar::new(&resources).unwrap_or_else(|msg| {
 		error!("failed to initialize soul jar: {msg}");
@@ -118,22 +360,80 @@ pub fn main() {
 	// the same effect can be seen if a computer is put to sleep and then woken up.
 	soul_jar.tick(5.0);
 	let mut cloudy_wave = draw::CloudyWave::default();
+	let mut movement = draw::Movement::default();
+	let mut feedback = draw::Feedback::default();
+	let mut animations = draw::Animations::default();
+	let mut effects = draw::Effects::default();
+	let mut lighting = draw::Lighting::new(&texture_creator, &resources).unwrap_or_else(|msg| {
+		error!("failed to initialize lighting: {msg}");
+		exit(1);
+	});
 
 	let mut input_mode = input::Mode::Normal;
 	let mut action_request = None;
 	let mut fps = 60.0;
 	let mut fps_timer = 0.0;
 	let mut debug = false;
-	loop {
+	// The turn the camera last refocused for.
+	let mut focused_turn = None;
+	// The turn the last autosave was written on.
+	let mut last_autosave = world_manager.stats.turns();
+	// If anything goes wrong, try to salvage the run before going down.
+	let result = panic::catch_unwind(AssertUnwindSafe(|| loop {
 		// Input processing
+		let window_size = canvas.window().size();
+		let layout = options.ui.layout(window_size, display_scale);
+		let world_area = Rect::new(
+			0,
+			0,
+			window_size
+				.0
+				.saturating_sub((layout.pamphlet_width as f32 * layout.scale) as u32),
+			window_size
+				.1
+				.saturating_sub((layout.console_height as f32 * layout.scale) as u32),
+		);
 		match input::world(
 			&mut event_pump,
 			&mut world_manager,
 			&resources,
+			&mut controllers,
+			world_area,
 			&mut input_mode,
-			&options,
+			&mut options,
 		) {
-			Ok(Some(input::Response::Exit)) => break,
+			Ok(Some(input::Response::Exit)) => {
+				// A finished run has already been banked, and its morgue file is optional.
+				// A saved run can still be picked back up, so it hasn't ended either.
+				if !resumable && !matches!(input_mode, input::Mode::Results { .. }) {
+					bank(&world_manager, &mut profile, &profile_path);
+					write_morgue(&world_manager, morgue::Outcome::Quit);
+				}
+				break;
+			}
+			Ok(Some(input::Response::WriteMorgue(outcome))) => {
+				write_morgue(&world_manager, outcome);
+				world_manager
+					.console
+					.print_system("Wrote a morgue file.".into());
+			}
+			Ok(Some(input::Response::NewRun)) => {
+				let seed = world_manager.seed;
+				world_manager = new_run(
+					&party_blueprint,
+					&resources,
+					&lua,
+					&options,
+					&mut profile,
+					mixer.as_ref(),
+				);
+				world_manager.seed = seed;
+				input_mode = input::Mode::Normal;
+				action_request = None;
+				focused_turn = None;
+				last_autosave = 0;
+				resumable = false;
+			}
 			Ok(Some(input::Response::Fullscreen)) => {
 				use sdl2::video::FullscreenType;
 				match canvas.window().fullscreen_state() {
@@ -146,6 +446,67 @@ pub fn main() {
 				}
 			}
 			Ok(Some(input::Response::Debug)) => debug ^= true,
+			Ok(Some(input::Response::SaveOptions)) => {
+				let mut saved = options.clone();
+				saved.ui.colors = user_colors.clone();
+				match toml::to_string(&saved) {
+					Ok(contents) => {
+						if let Err(msg) = fs::write(&options_path, contents) {
+							error!("failed to save options.toml: {msg}");
+						}
+					}
+					Err(msg) => error!("failed to serialize options.toml: {msg}"),
+				}
+			}
+			Ok(Some(input::Response::Save)) => {
+				let name = format!(
+					"{} floor {}",
+					world_manager.level.name, world_manager.location.floor
+				);
+				match save::write(&world_manager, &saves_directory, name) {
+					Ok(_) => {
+						resumable = true;
+						world_manager.console.print_system("Saved.".into());
+					}
+					Err(msg) => world_manager
+						.console
+						.print_danger(format!("Failed to save: {msg}")),
+				}
+			}
+			Ok(Some(input::Response::Overwrite(slot))) => {
+				match save::overwrite(&world_manager, &slot) {
+					Ok(()) => {
+						resumable = true;
+						world_manager
+							.console
+							.print_system(format!("Saved over {}.", slot.metadata.name));
+					}
+					Err(msg) => world_manager
+						.console
+						.print_danger(format!("Failed to save: {msg}")),
+				}
+			}
+			Ok(Some(input::Response::Load(path))) => {
+				if load(&path, &mut world_manager, &lua, &options) {
+					action_request = None;
+					focused_turn = None;
+					last_autosave = world_manager.stats.turns();
+					resumable = true;
+				} else {
+					world_manager
+						.console
+						.print_danger("Failed to load that save.".into());
+				}
+			}
+			Ok(Some(input::Response::Command(command))) => {
+				let result = command
+					.parse::<command::Command>()
+					.map_err(esprit2::Error::from)
+					.and_then(|command| command.execute(&mut world_manager, &resources, &lua));
+				if let Err(msg) = result {
+					world_manager.console.print_danger(msg.to_string());
+				}
+			}
 			Ok(None) => (),
 			Err(msg) => {
 				error!("world input processing returned an error: {msg}");
@@ -166,24 +527,110 @@ pub fn main() {
 				i.draw_state.cloud.tick(delta);
 				i.draw_state.cloud_trail.tick(delta / 4.0);
 			}
-			match world_manager.update(action_request, &lua, &mut input_mode) {
-				Ok(result) => action_request = result,
-				Err(msg) => {
-					error!("world manager update returned an error: {msg}");
-					action_request = None;
+			// Pausing and hit-stop both hold the board still, including turns.
+			let paused = input_mode.paused();
+			if !paused {
+				if let Err(msg) = world_manager.tick_cutscene(&lua, delta) {
+					world_manager.report_script_error(&msg);
+				}
+			}
+			let frozen = paused || effects.frozen();
+			let board_delta = if frozen { 0.0 } else { delta };
+			if !frozen {
+				match world_manager.update(action_request, &lua, &mut input_mode) {
+					Ok(result) => action_request = result,
+					Err(msg) => {
+						error!("world manager update returned an error: {msg}");
+						action_request = None;
+					}
+				}
+			}
+			if let Err(msg) = world_manager.remove_dead(&lua) {
+				world_manager.report_script_error(&msg);
+			}
+			// Whenever a new turn starts, switch to the camera of whoever the player is controlling.
+			let turn = world_manager.next_character().borrow().id;
+			if focused_turn != Some(turn) {
+				focused_turn = Some(turn);
+				world_manager.focus_next_character();
+			}
+			let outcome = if world_manager.victorious {
+				Some(morgue::Outcome::Victory)
+			} else if world_manager.party_defeated() {
+				Some(morgue::Outcome::Defeat)
+			} else {
+				None
+			};
+			if let Some(outcome) =
+				outcome.filter(|_| !matches!(input_mode, input::Mode::Results { .. }))
+			{
+				bank(&world_manager, &mut profile, &profile_path);
+				let rank = leaderboard.record(score::Entry::new(&world_manager, outcome));
+				if let Err(msg) = leaderboard.save(&leaderboard_path) {
+					error!("failed to save leaderboard.toml: {msg}");
 				}
+				input_mode = input::Mode::Results {
+					outcome,
+					selection: 0,
+					rank,
+					scope: score::Scope::All,
+				};
 			}
-			world_manager
-				.characters
-				.retain(|character| character.borrow().hp > 0);
 			world_manager.console.update(delta);
+			let biome = resources.get_biome(&world_manager.biome).ok();
+			let autosave = options.autosave;
+			let mut should_autosave =
+				autosave.turns > 0 && world_manager.stats.turns() >= last_autosave + autosave.turns;
+			for event in &world_manager.events {
+				if let world::Event::FloorEntered(floor) = event {
+					should_autosave |= autosave.floors > 0 && floor % autosave.floors == 0;
+				}
+				if let Some(biome) = biome {
+					world_manager.audio.react(event, biome);
+				}
+				feedback.push(event, &movement, &options.ui);
+				animations.push(event);
+				effects.push(event, &options.ui);
+			}
+			world_manager.events.clear();
+			if should_autosave {
+				last_autosave = world_manager.stats.turns();
+				match save::write_autosave(&world_manager, &saves_directory, autosave.slots) {
+					Ok(_) => resumable = true,
+					Err(msg) => error!("failed to autosave: {msg}"),
+				}
+			}
+			if let Some(mixer) = &mut mixer {
+				mixer.update(&options.audio);
+			}
 			soul_jar.tick(delta as f32);
 			cloudy_wave.tick(delta);
-			if let input::Mode::Cursor { state, .. } = &mut input_mode {
+			movement.tick(&world_manager, board_delta, options.ui.movement_duration);
+			for member in &mut world_manager.party {
+				let target = movement.position(&member.piece.borrow());
+				member.draw_state.camera.tick(target, board_delta);
+			}
+			if let input::Mode::FreeLook(free_look) = &mut input_mode {
+				free_look.tick(&world_manager, &movement, board_delta);
+			}
+			feedback.tick(board_delta);
+			animations.tick(&world_manager, &resources, board_delta);
+			if !paused {
+				effects.tick(delta);
+			}
+			if let input::Mode::Cursor { state, .. } | input::Mode::Inspect { state, .. } =
+				&mut input_mode
+			{
 				state.float.increment(delta);
 			}
 		}
 
+		// Fonts have to be reopened to change size.
+		if options.ui.typography != typography_options {
+			typography = Typography::new(&options.ui.typography, &ttf_context);
+			typography_options = options.ui.typography.clone();
+		}
+
 		// Rendering
 		{
 			// Clear the screen.
@@ -192,19 +639,71 @@ pub fn main() {
 
 			// Configure world viewport.
 			let window_size = canvas.window().size();
-			canvas.set_viewport(Rect::new(0, 0, window_size.0, window_size.1));
+			let (shake_x, shake_y) = world_manager
+				.shake
+				.map(|shake| shake.offset())
+				.unwrap_or_default();
+			let (effect_x, effect_y) = effects.offset();
+			let (shake_x, shake_y) = (shake_x + effect_x, shake_y + effect_y);
+			let board_scale = draw::board_scale(&input_mode, options.ui.board_scale);
+			let (camera_x, camera_y) =
+				draw::board_offset(&world_manager, &input_mode, world_area, board_scale);
+			let (board_x, board_y) = (shake_x + camera_x, shake_y + camera_y);
+			let _ = canvas.set_scale(board_scale, board_scale);
+			canvas.set_viewport(Rect::new(
+				board_x,
+				board_y,
+				((window_size.0 as f32 / board_scale) as i32 - board_x).max(1) as u32,
+				((window_size.1 as f32 / board_scale) as i32 - board_y).max(1) as u32,
+			));
 			canvas.set_draw_color(Color::RGB(20, 20, 20));
 
 			canvas
 				.fill_rect(Rect::new(0, 0, window_size.0, window_size.1))
 				.unwrap();
 
-			draw::tilemap(&mut canvas, &world_manager);
-			draw::characters(&world_manager, &mut canvas, &resources);
-			draw::cursor(&input_mode, &resources, &mut canvas);
+			draw::tilemap(&mut canvas, &world_manager, &resources);
+			draw::items(&world_manager, &mut canvas, &resources);
+			draw::corpses(&world_manager, &mut canvas, &resources);
+			draw::move_preview(&input_mode, &world_manager, &options.ui, &mut canvas);
+			draw::characters(
+				&world_manager,
+				&mut canvas,
+				&resources,
+				&movement,
+				&animations,
+				&options.ui,
+			);
+			lighting.draw(
+				&mut canvas,
+				&world_manager,
+				&resources,
+				&movement,
+				&options.ui,
+			);
+			feedback.draw(&mut canvas, &typography, &world_manager, &movement);
+			draw::cursor(
+				&input_mode,
+				&world_manager,
+				&resources,
+				&options.ui,
+				&mut canvas,
+			);
 
 			// Render User Interface
 			canvas.set_viewport(None);
+			let _ = canvas.set_scale(1.0, 1.0);
+			effects.draw(&mut canvas, world_area, &options.ui);
+			if input_mode.paused() {
+				draw::pause_overlay(&mut canvas, world_area);
+			}
+			let ui_scale = layout.scale;
+			let _ = canvas.set_scale(ui_scale, ui_scale);
+			// The interface is laid out as if the window were this size.
+			let window_size = (
+				(window_size.0 as f32 / ui_scale) as u32,
+				(window_size.1 as f32 / ui_scale) as u32,
+			);
 
 			if debug {
 				let mut debug =
@@ -228,21 +727,21 @@ pub fn main() {
 				&typography,
 				Rect::new(
 					0,
-					(window_size.1 - options.ui.console_height) as i32,
-					window_size.0 - options.ui.pamphlet_width,
-					options.ui.console_height,
+					(window_size.1 - layout.console_height) as i32,
+					window_size.0 - layout.pamphlet_width,
+					layout.console_height,
 				),
 			);
-			gui::widget::menu(&mut menu, &options, &input_mode, &world_manager);
+			gui::widget::menu(&mut menu, &options, &input_mode, &world_manager, &resources);
 
 			// Draw pamphlet
 			let mut pamphlet = gui::Context::new(
 				&mut canvas,
 				&typography,
 				Rect::new(
-					(window_size.0 - options.ui.pamphlet_width) as i32,
+					(window_size.0 - layout.pamphlet_width) as i32,
 					0,
-					options.ui.pamphlet_width,
+					layout.pamphlet_width,
 					window_size.1,
 				),
 			);
@@ -256,7 +755,67 @@ pub fn main() {
 
 			gui::widget::pamphlet(&mut pamphlet, &world_manager, &resources, &mut soul_jar);
 
+			if let input::Mode::Results {
+				outcome,
+				rank,
+				scope,
+				..
+			} = input_mode
+			{
+				let mut results = gui::Context::new(
+					&mut canvas,
+					&typography,
+					Rect::new(
+						16,
+						16,
+						(window_size.0 - layout.pamphlet_width).saturating_sub(32),
+						(window_size.1 - layout.console_height).saturating_sub(32),
+					),
+				);
+				gui::widget::results(&mut results, &world_manager, &options, outcome);
+				results.advance(0, 8);
+				gui::widget::leaderboard(
+					&mut results,
+					&leaderboard,
+					&world_manager,
+					outcome,
+					rank,
+					scope,
+				);
+			}
+
+			// Describe whatever the mouse is hovering over.
+			let mouse = event_pump.mouse_state();
+			if world_area.contains_point((mouse.x(), mouse.y())) {
+				let (x, y) = draw::tile_at(
+					mouse.x() - (camera_x as f32 * board_scale) as i32,
+					mouse.y() - (camera_y as f32 * board_scale) as i32,
+					board_scale,
+				);
+				if let Some(piece) = world_manager.get_character_at(x, y) {
+					let piece = piece.borrow();
+					let rect = Rect::new(
+						(mouse.x() as f32 / ui_scale) as i32 + 16,
+						(mouse.y() as f32 / ui_scale) as i32 + 16,
+						200,
+						60,
+					);
+					canvas.set_draw_color(Color::RGB(20, 20, 20));
+					canvas.fill_rect(rect).unwrap();
+					let mut tooltip = gui::Context::new(&mut canvas, &typography, rect);
+					tooltip.label(&piece.sheet.nouns.name);
+					tooltip.label(&format!("HP: {}/{}", piece.hp, piece.stats().heart));
+				}
+			}
+
 			canvas.present();
 		}
+	}));
+	if let Err(panic) = result {
+		match save::write_emergency(&world_manager, &saves_directory) {
+			Ok(path) => error!("wrote emergency save to {}", path.display()),
+			Err(msg) => error!("failed to write emergency save: {msg}"),
+		}
+		panic::resume_unwind(panic);
 	}
 }
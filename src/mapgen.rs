@@ -0,0 +1,334 @@
+use crate::floor::{Floor, Tile};
+use rand::{Rng, RngCore};
+use std::collections::VecDeque;
+use tracing::debug;
+
+/// Fills in the structure of a floor before any vaults are placed.
+pub trait Generate {
+	fn generate(&self, floor: &mut Floor, rng: &mut dyn RngCore);
+}
+
+/// Which algorithm to use when laying out a floor.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Generator {
+	/// An open room; all structure comes from the vaults stitched onto it.
+	#[default]
+	Vaults,
+	/// Rectangular rooms connected by corridors.
+	Bsp(Bsp),
+	/// Organic, winding caverns.
+	Caves(Caves),
+}
+
+impl Generate for Generator {
+	fn generate(&self, floor: &mut Floor, rng: &mut dyn RngCore) {
+		match self {
+			Generator::Vaults => floor.map.fill(Tile::Floor),
+			Generator::Bsp(bsp) => bsp.generate(floor, rng),
+			Generator::Caves(caves) => caves.generate(floor, rng),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Rect {
+	x: usize,
+	y: usize,
+	width: usize,
+	height: usize,
+}
+
+impl Rect {
+	fn center(self) -> (usize, usize) {
+		(self.x + self.width / 2, self.y + self.height / 2)
+	}
+}
+
+/// Binary space partitioning: the floor is recursively split in half,
+/// a room is placed in each partition, and sibling partitions are connected.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Bsp {
+	/// Partitions smaller than this (in either dimension) will not be split further.
+	///
+	/// Treated as 1 if it's 0, since empty partitions could be split forever.
+	pub min_size: usize,
+}
+
+impl Default for Bsp {
+	fn default() -> Self {
+		Self { min_size: 6 }
+	}
+}
+
+impl Bsp {
+	/// Returns the center of a room within the partition, for corridors to connect to.
+	fn split(&self, floor: &mut Floor, area: Rect, rng: &mut dyn RngCore) -> (usize, usize) {
+		let min_size = self.min_size.max(1);
+		let can_split_x = area.width >= min_size * 2;
+		let can_split_y = area.height >= min_size * 2;
+		let split_x = match (can_split_x, can_split_y) {
+			(false, false) => return carve_room(floor, area, rng),
+			(true, false) => true,
+			(false, true) => false,
+			(true, true) => rng.gen(),
+		};
+
+		let (first, second) = if split_x {
+			let at = rng.gen_range(min_size..=(area.width - min_size));
+			(
+				Rect { width: at, ..area },
+				Rect {
+					x: area.x + at,
+					width: area.width - at,
+					..area
+				},
+			)
+		} else {
+			let at = rng.gen_range(min_size..=(area.height - min_size));
+			(
+				Rect { height: at, ..area },
+				Rect {
+					y: area.y + at,
+					height: area.height - at,
+					..area
+				},
+			)
+		};
+
+		let first = self.split(floor, first, rng);
+		let second = self.split(floor, second, rng);
+		carve_corridor(floor, first, second);
+		// Either room works as a connection point for the parent partition.
+		if rng.gen() {
+			first
+		} else {
+			second
+		}
+	}
+}
+
+impl Generate for Bsp {
+	fn generate(&self, floor: &mut Floor, rng: &mut dyn RngCore) {
+		floor.map.fill(Tile::Wall);
+		let area = Rect {
+			x: 0,
+			y: 0,
+			width: floor.map.cols(),
+			height: floor.map.rows(),
+		};
+		self.split(floor, area, rng);
+		place_exit(floor, rng);
+	}
+}
+
+/// Carves a room somewhere within `area`, leaving a wall along its edges.
+fn carve_room(floor: &mut Floor, area: Rect, rng: &mut dyn RngCore) -> (usize, usize) {
+	// Partitions are at least `min_size`, but the room needs room for its walls.
+	if area.width < 3 || area.height < 3 {
+		return area.center();
+	}
+	let width = rng.gen_range(1..=(area.width - 2));
+	let height = rng.gen_range(1..=(area.height - 2));
+	let room = Rect {
+		x: area.x + rng.gen_range(1..=(area.width - 1 - width)),
+		y: area.y + rng.gen_range(1..=(area.height - 1 - height)),
+		width,
+		height,
+	};
+	for y in room.y..(room.y + room.height) {
+		for x in room.x..(room.x + room.width) {
+			if let Some(tile) = floor.map.get_mut(y, x) {
+				*tile = Tile::Floor;
+			}
+		}
+	}
+	room.center()
+}
+
+/// Carves an L-shaped corridor between two points.
+///
+/// Only walls are replaced, so anything special along the way (like exits) is left intact.
+fn carve_corridor(floor: &mut Floor, from: (usize, usize), to: (usize, usize)) {
+	let (x1, y1) = from;
+	let (x2, y2) = to;
+	let mut carve = |x, y| {
+		if let Some(tile @ Tile::Wall) = floor.map.get_mut(y, x) {
+			*tile = Tile::Floor;
+		}
+	};
+	for x in x1.min(x2)..=x1.max(x2) {
+		carve(x, y1);
+	}
+	for y in y1.min(y2)..=y1.max(y2) {
+		carve(x2, y);
+	}
+}
+
+/// Cellular automata: the floor starts as random noise,
+/// and is smoothed by turning each tile into whatever most of its neighbors are.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Caves {
+	/// Percent chance for each tile to start out as a wall.
+	pub fill: u32,
+	/// How many times the smoothing rule should be applied.
+	pub iterations: u32,
+}
+
+impl Default for Caves {
+	fn default() -> Self {
+		Self {
+			fill: 45,
+			iterations: 4,
+		}
+	}
+}
+
+impl Generate for Caves {
+	fn generate(&self, floor: &mut Floor, rng: &mut dyn RngCore) {
+		let rows = floor.map.rows();
+		let cols = floor.map.cols();
+		let is_edge = |y: usize, x: usize| x == 0 || y == 0 || x == cols - 1 || y == rows - 1;
+
+		for ((y, x), tile) in floor.map.indexed_iter_mut() {
+			*tile = if is_edge(y, x) || rng.gen_range(0..100) < self.fill {
+				Tile::Wall
+			} else {
+				Tile::Floor
+			};
+		}
+
+		for _ in 0..self.iterations {
+			let previous = floor.map.clone();
+			for ((y, x), tile) in floor.map.indexed_iter_mut() {
+				let mut walls = 0;
+				for yoff in -1..=1 {
+					for xoff in -1..=1 {
+						// Anything outside of the map counts as a wall.
+						let neighbor = previous.get(y as i32 + yoff, x as i32 + xoff);
+						if !matches!(neighbor, Some(Tile::Floor)) {
+							walls += 1;
+						}
+					}
+				}
+				*tile = if is_edge(y, x) || walls >= 5 {
+					Tile::Wall
+				} else {
+					Tile::Floor
+				};
+			}
+		}
+
+		place_exit(floor, rng);
+	}
+}
+
+fn place_exit(floor: &mut Floor, rng: &mut dyn RngCore) {
+	if let Some((x, y)) = floor.random_floor_tile(rng) {
+		if let Some(tile) = floor.map.get_mut(y, x) {
+			*tile = Tile::Exit;
+		}
+	}
+}
+
+/// What to do when parts of a floor can't be reached from the entrance.
+#[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub enum Connectivity {
+	/// Dig corridors from each unreachable region to the rest of the floor.
+	#[default]
+	Carve,
+	/// Throw the floor away and try again.
+	/// If this keeps failing, corridors are carved anyway.
+	Regenerate,
+}
+
+/// A record of the decisions made while generating a floor.
+///
+/// Generation is deterministic for a given seed,
+/// so this is mostly useful for tracking down why a seed produced a strange floor.
+#[derive(Clone, Debug, Default)]
+pub struct Log {
+	pub seed: u64,
+	pub entries: Vec<String>,
+}
+
+impl Log {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			entries: Vec::new(),
+		}
+	}
+
+	pub fn push(&mut self, entry: String) {
+		debug!("floor generation (seed {}): {entry}", self.seed);
+		self.entries.push(entry);
+	}
+}
+
+fn passable(tile: Option<&Tile>) -> bool {
+	tile.is_some_and(|tile| tile.is_passable())
+}
+
+/// Returns a grid marking every tile reachable from `from`.
+pub fn reachable(floor: &Floor, from: (i32, i32)) -> grid::Grid<bool> {
+	let mut reached = grid::Grid::init(floor.map.rows(), floor.map.cols(), false);
+	let mut queue = VecDeque::from([from]);
+	while let Some((x, y)) = queue.pop_front() {
+		if !passable(floor.map.get(y, x)) {
+			continue;
+		}
+		let Some(visited) = reached.get_mut(y, x) else {
+			continue;
+		};
+		if *visited {
+			continue;
+		}
+		*visited = true;
+		for yoff in -1..=1 {
+			for xoff in -1..=1 {
+				queue.push_back((x + xoff, y + yoff));
+			}
+		}
+	}
+	reached
+}
+
+/// Returns every passable tile that can't be reached from `from`.
+pub fn unreachable_tiles(floor: &Floor, from: (i32, i32)) -> Vec<(usize, usize)> {
+	let reached = reachable(floor, from);
+	floor
+		.map
+		.indexed_iter()
+		.filter(|&((y, x), tile)| passable(Some(tile)) && reached.get(y, x) == Some(&false))
+		.map(|((y, x), _)| (x, y))
+		.collect()
+}
+
+/// Carve corridors until every passable tile is reachable from `from`.
+pub fn connect(floor: &mut Floor, from: (i32, i32), log: &mut Log) {
+	loop {
+		let reached = reachable(floor, from);
+		let Some(stranded) = floor
+			.map
+			.indexed_iter()
+			.find(|&((y, x), tile)| passable(Some(tile)) && reached.get(y, x) == Some(&false))
+			.map(|((y, x), _)| (x, y))
+		else {
+			break;
+		};
+		let Some(target) = reached
+			.indexed_iter()
+			.filter(|(_, reached)| **reached)
+			.map(|((y, x), _)| (x, y))
+			.min_by_key(|&(x, y)| x.abs_diff(stranded.0) + y.abs_diff(stranded.1))
+		else {
+			log.push(String::from("the entrance is not on a passable tile"));
+			break;
+		};
+		log.push(format!("carved a corridor from {stranded:?} to {target:?}"));
+		carve_corridor(floor, stranded, target);
+	}
+}
@@ -0,0 +1,124 @@
+//! Statistics gathered over the course of a run, and the "morgue" summaries written from them.
+
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Statistics {
+	/// Total time passed over the course of the run.
+	pub time: Aut,
+	/// Keyed by character name.
+	pub damage_dealt: HashMap<String, u32>,
+	/// Keyed by character name.
+	pub damage_received: HashMap<String, u32>,
+	/// How many of each kind of character were defeated, keyed by name.
+	pub kills: HashMap<String, u32>,
+	pub deepest_floor: usize,
+	/// How the most recently fallen party member was defeated.
+	pub cause_of_death: Option<String>,
+}
+
+/// How a run came to an end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+	Defeat,
+	Victory,
+	/// The run was abandoned (or saved and exited).
+	Quit,
+}
+
+impl Statistics {
+	pub fn turns(&self) -> Aut {
+		self.time / TURN
+	}
+
+	/// Record `damage` dealt by `attacker` (if any) to `target`.
+	pub fn record_damage(&mut self, attacker: Option<&str>, target: &str, damage: u32) {
+		if let Some(attacker) = attacker {
+			*self.damage_dealt.entry(attacker.into()).or_default() += damage;
+		}
+		*self.damage_received.entry(target.into()).or_default() += damage;
+	}
+
+	pub fn record_kill(&mut self, name: &str) {
+		*self.kills.entry(name.into()).or_default() += 1;
+	}
+}
+
+/// Produce a human-readable summary of a run.
+pub fn summary(world: &world::Manager, outcome: Outcome) -> String {
+	let stats = &world.stats;
+	let mut out = String::new();
+	let description = match outcome {
+		Outcome::Defeat => "The party was defeated",
+		Outcome::Victory => "The party was victorious",
+		Outcome::Quit => "The party gave up",
+	};
+	let _ = writeln!(
+		out,
+		"{description} on floor {} of {}.",
+		world.location.floor, world.level.name
+	);
+	let _ = writeln!(out, "Deepest floor: {}", stats.deepest_floor);
+	let _ = writeln!(out, "Turns taken: {}", stats.turns());
+	let _ = writeln!(out, "Seed: {}", world.seed);
+	if outcome == Outcome::Defeat {
+		if let Some(cause) = &stats.cause_of_death {
+			let _ = writeln!(out, "{cause}.");
+		}
+		if let Some(blow) = world.console.last_combat_message() {
+			let _ = writeln!(out, "Killing blow: {blow}");
+		}
+	}
+
+	let _ = writeln!(out, "\nParty:");
+	for member in &world.party {
+		let piece = member.piece.borrow();
+		let name = &*piece.sheet.nouns.name;
+		let _ = writeln!(
+			out,
+			"  {name} (level {}): {} damage dealt, {} damage received{}",
+			piece.sheet.level,
+			stats.damage_dealt.get(name).copied().unwrap_or(0),
+			stats.damage_received.get(name).copied().unwrap_or(0),
+			if piece.hp <= 0 { ", fallen" } else { "" },
+		);
+	}
+
+	let _ = writeln!(out, "\nKills:");
+	let mut kills: Vec<_> = stats.kills.iter().collect();
+	kills.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+	if kills.is_empty() {
+		let _ = writeln!(out, "  None");
+	}
+	for (name, count) in kills {
+		let _ = writeln!(out, "  {count} {name}");
+	}
+
+	out
+}
+
+/// Write a morgue file into `directory`, returning the name of the new file.
+///
+/// # Errors
+///
+/// Fails if the directory or file could not be written to.
+pub fn write(
+	directory: impl AsRef<Path>,
+	world: &world::Manager,
+	outcome: Outcome,
+) -> Result<String> {
+	let directory = directory.as_ref();
+	fs::create_dir_all(directory)?;
+	let timestamp = std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map(|x| x.as_secs())
+		.unwrap_or_default();
+	let name = format!("morgue-{timestamp}.txt");
+	fs::write(directory.join(&name), summary(world, outcome))?;
+	Ok(name)
+}
@@ -1,5 +1,4 @@
-use aho_corasick::AhoCorasick;
-use std::sync::{Arc, LazyLock};
+use std::sync::Arc;
 
 /// For dynamically addressing a character.
 /// This should encompass almost every (dynamic) way of addressing someone or something.
@@ -13,6 +12,13 @@ pub struct Nouns {
 	#[alua(get)]
 	pub proper_name: bool,
 	pub pronouns: Pronouns,
+	/// If true, the name refers to a group ("Bats"), which takes plural verbs.
+	#[serde(default)]
+	pub plural: bool,
+	/// Used in place of the name when referring to several of this character.
+	/// If absent, this is guessed from the name.
+	#[serde(default)]
+	pub plural_name: Option<Arc<str>>,
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -37,68 +43,140 @@ impl<T: AsRef<str>> StrExt for T {
 	}
 
 	fn replace_nouns(&self, nouns: &Nouns) -> String {
-		static PRONOUN_TABLE: LazyLock<AhoCorasick> = LazyLock::new(|| {
-			AhoCorasick::new([
-				"{they}", "{them}", "{their}", "{theirs}", "{are}", "{They}", "{Them}", "{Their}",
-				"{Theirs}", "{Are}",
-			])
-			.expect("aho corasick table must be valid")
-		});
-		let replacements = match nouns.pronouns {
-			Pronouns::Female => &[
-				"she", "her", "her", "hers", "is", "She", "Her", "Her", "Hers", "Is",
-			],
-			Pronouns::Male => &[
-				"he", "him", "his", "his", "is", "He", "Him", "His", "His", "Is",
-			],
-			Pronouns::Neutral => &[
-				"they", "them", "their", "theirs", "are", "They", "Them", "Their", "Theirs", "Are",
-			],
-			Pronouns::Object => &[
-				"it", "its", "its", "its", "is", "It", "Its", "Its", "Its", "Is",
-			],
+		let (they, them, their, theirs) = match nouns.pronouns {
+			Pronouns::Female => ("she", "her", "her", "hers"),
+			Pronouns::Male => ("he", "him", "his", "his"),
+			Pronouns::Neutral => ("they", "them", "their", "theirs"),
+			Pronouns::Object => ("it", "it", "its", "its"),
 		};
-		let name = &[&*nouns.name];
-		let capital_indirect_name = &["A ", &nouns.name];
-		let indirect_name = &["a ", &nouns.name];
-		let capital_address_name = &["The ", &nouns.name];
-		let address_name = &["the ", &nouns.name];
+		// Verbs agree with whichever subject came last:
+		// "{Address} {verb:strike}" is singular even for characters addressed as "they".
+		let pronoun_plural = matches!(nouns.pronouns, Pronouns::Neutral);
+		let mut plural = nouns.plural;
 
-		let mut s = PRONOUN_TABLE.replace_all(self.as_ref(), replacements);
-		let mut i = 0;
-		while let Some(start) = s[i..].find('{') {
-			let start = i + start;
-			let Some(end) = s[start..].find('}') else {
+		let mut s = String::new();
+		let mut rest = self.as_ref();
+		while let Some(start) = rest.find('{') {
+			let Some(end) = rest[start..].find('}') else {
 				break;
 			};
 			let end = start + end;
-			let source = &s[(start + 1)..end];
+			let source = &rest[(start + 1)..end];
+			s += &rest[..start];
+			rest = &rest[(end + 1)..];
 
-			let replacement: &[&str] = match (nouns.proper_name, source) {
-				(true, "Address" | "address" | "Indirect" | "indirect") => name,
-				(false, "Address") => capital_address_name,
-				(false, "address") => address_name,
-				(false, "Indirect") => capital_indirect_name,
-				(false, "indirect") => indirect_name,
+			let (capitalize, lower) = match source.chars().next() {
+				Some(first) if first.is_uppercase() => (true, source.to_lowercase()),
+				_ => (false, source.to_string()),
+			};
+			let replacement = match lower.as_str() {
+				"they" => {
+					plural = pronoun_plural;
+					they.to_string()
+				}
+				"them" => them.to_string(),
+				"their" => their.to_string(),
+				"theirs" => theirs.to_string(),
+				"are" => if plural { "are" } else { "is" }.to_string(),
+				"address" | "indirect" => {
+					plural = nouns.plural;
+					nouns.article(&lower) + &nouns.name
+				}
+				"address's" | "indirect's" => {
+					plural = nouns.plural;
+					let article = nouns.article(lower.trim_end_matches("'s"));
+					article + &possessive(&nouns.name)
+				}
+				"plural" => nouns.plural_name().to_string(),
 				_ => {
-					i = end;
-					continue;
+					if let Some(verb) = lower.strip_prefix("verb:") {
+						conjugate(verb, plural)
+					} else {
+						s.push('{');
+						s += source;
+						s.push('}');
+						continue;
+					}
 				}
 			};
+			if capitalize {
+				s += &capitalized(&replacement);
+			} else {
+				s += &replacement;
+			}
+		}
+		s + rest
+	}
+}
 
-			let mut replacements = replacement.iter().rev();
-			let first = replacements.next().expect("replacements must nt be empty");
-			s.replace_range(start..end, first);
-			// Convieniently, the name (longest entry) is first, meaning less copies when we need to fallback.
-			for i in replacements {
-				s.insert_str(start, i);
+impl Nouns {
+	fn article(&self, form: &str) -> String {
+		match (self.proper_name, form) {
+			(true, _) => String::new(),
+			(false, "address") => String::from("the "),
+			// "Bats", rather than "a bats".
+			(false, _) if self.plural => String::new(),
+			(false, _) => {
+				let vowel = self
+					.name
+					.chars()
+					.next()
+					.is_some_and(|c| "aeiouAEIOU".contains(c));
+				String::from(if vowel { "an " } else { "a " })
 			}
-			let new_end = s[start..]
-				.find('}')
-				.expect("replacements mus contain closing braces");
-			s.remove(start + new_end);
-			i = new_end;
 		}
-		s
+	}
+
+	/// The name used when referring to more than one of this character.
+	pub fn plural_name(&self) -> String {
+		match &self.plural_name {
+			Some(plural_name) => plural_name.to_string(),
+			None if self.plural => self.name.to_string(),
+			None => pluralize(&self.name),
+		}
+	}
+}
+
+fn capitalized(s: &str) -> String {
+	let mut chars = s.chars();
+	match chars.next() {
+		Some(first) => first.to_uppercase().chain(chars).collect(),
+		None => String::new(),
+	}
+}
+
+fn possessive(name: &str) -> String {
+	if name.ends_with('s') {
+		format!("{name}'")
+	} else {
+		format!("{name}'s")
+	}
+}
+
+/// Add the suffix that both regular plural nouns and singular verbs use ("-s" or "-es").
+fn pluralize(word: &str) -> String {
+	let consonant_y = word
+		.strip_suffix('y')
+		.filter(|stem| stem.chars().last().is_some_and(|c| !"aeiou".contains(c)));
+	if let Some(stem) = consonant_y {
+		format!("{stem}ies")
+	} else if ["s", "sh", "ch", "x", "z", "o"]
+		.iter()
+		.any(|suffix| word.ends_with(suffix))
+	{
+		format!("{word}es")
+	} else {
+		format!("{word}s")
+	}
+}
+
+/// Conjugate a verb's plain form ("strike") to agree with its subject.
+fn conjugate(verb: &str, plural: bool) -> String {
+	match (verb, plural) {
+		("be", true) => String::from("are"),
+		("be", false) => String::from("is"),
+		("have", false) => String::from("has"),
+		(verb, true) => verb.to_string(),
+		(verb, false) => pluralize(verb),
 	}
 }
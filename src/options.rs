@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use sdl2::controller::Button;
 use sdl2::keyboard::Keycode;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
@@ -33,6 +34,54 @@ fn find_resource_directory() -> PathBuf {
 pub struct Options {
 	pub ui: UserInterface,
 	pub controls: Controls,
+	pub audio: audio::Volume,
+	/// What stops auto-explore.
+	pub explore: Interruptions,
+	pub autosave: Autosave,
+	/// Difficulty settings given to new runs.
+	pub rules: world::Rules,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Autosave {
+	/// Save after entering every this many floors; 0 disables this.
+	pub floors: usize,
+	/// Save after this many turns have passed; 0 disables this.
+	pub turns: u32,
+	/// How many autosaves to keep before the oldest is overwritten.
+	pub slots: usize,
+}
+
+impl Default for Autosave {
+	fn default() -> Self {
+		Self {
+			floors: 1,
+			turns: 0,
+			slots: 3,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Interruptions {
+	/// Stop when a new hostile piece comes into view.
+	pub enemies: bool,
+	/// Stop when a new item comes into view.
+	pub items: bool,
+	/// Stop when the exploring piece loses HP.
+	pub damage: bool,
+}
+
+impl Default for Interruptions {
+	fn default() -> Self {
+		Self {
+			enemies: true,
+			items: true,
+			damage: true,
+		}
+	}
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -50,7 +99,25 @@ impl Options {
 	///
 	/// Fails if the file could not be opened or parsed.
 	pub fn open(path: impl AsRef<Path>) -> Result<Self, OpenOptionsError> {
-		Ok(toml::from_str(&fs::read_to_string(path)?)?)
+		let mut options: Self = toml::from_str(&fs::read_to_string(path)?)?;
+		options.ui.ui_scale = clamp_scale(options.ui.ui_scale);
+		options.ui.board_scale = clamp_scale(options.ui.board_scale);
+		Ok(options)
+	}
+}
+
+/// The smallest and largest `ui_scale` and `board_scale` allowed.
+///
+/// Tiny scales would shrink tiles to nothing (and break mouse picking),
+/// so hand-edited options are pulled back into this range.
+const MIN_SCALE: f32 = 0.5;
+const MAX_SCALE: f32 = 3.0;
+
+fn clamp_scale(scale: f32) -> f32 {
+	if scale.is_nan() {
+		1.0
+	} else {
+		scale.clamp(MIN_SCALE, MAX_SCALE)
 	}
 }
 
@@ -58,20 +125,198 @@ impl Options {
 #[serde(default, deny_unknown_fields)]
 pub struct UserInterface {
 	pub colors: Colors,
+	/// A color scheme from `res/color_schemes` to use in place of `colors`,
+	/// such as `colorblind` or `high_contrast`.
+	pub color_scheme: Option<String>,
 	pub typography: typography::Options,
 
+	/// Magnification of the pamphlet, console, and menus.
+	pub ui_scale: f32,
+	/// Multiply `ui_scale` by the display's density, so high-DPI screens aren't unreadably small.
+	pub dpi_scaling: bool,
+	/// Magnification of the world.
+	pub board_scale: f32,
+	pub pamphlet_width: u32,
+	pub console_height: u32,
+	/// How many console messages are remembered.
+	pub console_history: usize,
+	pub console_filter: console::Filter,
+	/// How long pieces take to slide between tiles, in seconds.
+	/// 0 disables movement animation.
+	pub movement_duration: f64,
+	/// Show floating numbers when pieces are damaged or healed.
+	pub damage_numbers: bool,
+	/// Briefly flash pieces when they take damage.
+	pub hit_flash: bool,
+	/// When to draw HP bars and status icons above pieces.
+	pub health_bars: HealthBars,
+	/// Allow the drop-down developer console to be opened.
+	pub developer_console: bool,
+	/// Shade the tiles the acting piece can reach before enemies act.
+	pub move_preview: bool,
+	/// Shake, flash, and briefly freeze the screen in response to big moments.
+	pub effects: bool,
+	/// Scales how strong screen effects are.
+	pub effect_intensity: f32,
+	/// Darken floors according to their biome's ambient color.
+	/// Disabling this lights everything fully.
+	pub darkness: bool,
+}
+
+/// Where the interface sits within the window, in scaled pixels.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+	/// Magnification of the interface, including the display's density.
+	pub scale: f32,
 	pub pamphlet_width: u32,
 	pub console_height: u32,
 }
 
+impl UserInterface {
+	/// Lay out the interface for a window of `window_size` physical pixels.
+	///
+	/// `display_scale` is the display's density relative to 96 DPI.
+	/// The pamphlet and console shrink on small windows to leave room for the world.
+	pub fn layout(&self, window_size: (u32, u32), display_scale: f32) -> Layout {
+		let scale = if self.dpi_scaling {
+			self.ui_scale * display_scale
+		} else {
+			self.ui_scale
+		};
+		let width = (window_size.0 as f32 / scale) as u32;
+		let height = (window_size.1 as f32 / scale) as u32;
+		Layout {
+			scale,
+			pamphlet_width: self.pamphlet_width.min(width * 2 / 5),
+			console_height: self.console_height.min(height * 2 / 5),
+		}
+	}
+}
+
+/// Options which can be adjusted from the in-game options menu.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Setting {
+	UiScale,
+	FontSize,
+	PamphletWidth,
+	ConsoleHeight,
+	BoardScale,
+	MasterVolume,
+	SoundVolume,
+	MusicVolume,
+	MovementDuration,
+}
+
+impl Setting {
+	pub const ALL: [Setting; 9] = [
+		Setting::UiScale,
+		Setting::FontSize,
+		Setting::PamphletWidth,
+		Setting::ConsoleHeight,
+		Setting::BoardScale,
+		Setting::MasterVolume,
+		Setting::SoundVolume,
+		Setting::MusicVolume,
+		Setting::MovementDuration,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Setting::UiScale => "UI scale",
+			Setting::FontSize => "Font size",
+			Setting::PamphletWidth => "Pamphlet width",
+			Setting::ConsoleHeight => "Console height",
+			Setting::BoardScale => "Board scale",
+			Setting::MasterVolume => "Master volume",
+			Setting::SoundVolume => "Sound volume",
+			Setting::MusicVolume => "Music volume",
+			Setting::MovementDuration => "Animation duration",
+		}
+	}
+
+	pub fn value(self, options: &Options) -> String {
+		match self {
+			Setting::UiScale => format!("{:.2}x", options.ui.ui_scale),
+			Setting::FontSize => format!("{}pt", options.ui.typography.font_size),
+			Setting::PamphletWidth => format!("{}px", options.ui.pamphlet_width),
+			Setting::ConsoleHeight => format!("{}px", options.ui.console_height),
+			Setting::BoardScale => format!("{:.2}x", options.ui.board_scale),
+			Setting::MasterVolume => format!("{}%", options.audio.master),
+			Setting::SoundVolume => format!("{}%", options.audio.sound),
+			Setting::MusicVolume => format!("{}%", options.audio.music),
+			Setting::MovementDuration => format!("{:.2}s", options.ui.movement_duration),
+		}
+	}
+
+	/// Nudge the setting up or down by `steps` increments, staying within a sensible range.
+	pub fn adjust(self, options: &mut Options, steps: i32) {
+		fn step_u32(value: &mut u32, steps: i32, increment: i32, min: u32, max: u32) {
+			*value = (*value as i32 + steps * increment).clamp(min as i32, max as i32) as u32;
+		}
+		fn step_u8(value: &mut u8, steps: i32) {
+			*value = (*value as i32 + steps * 5).clamp(0, 100) as u8;
+		}
+		fn step_f32(value: &mut f32, steps: i32) {
+			*value = clamp_scale(((*value + steps as f32 * 0.25) * 4.0).round() / 4.0);
+		}
+
+		match self {
+			Setting::UiScale => step_f32(&mut options.ui.ui_scale, steps),
+			Setting::FontSize => {
+				let size = &mut options.ui.typography.font_size;
+				*size = (i32::from(*size) + steps).clamp(8, 48) as u16;
+			}
+			Setting::PamphletWidth => {
+				step_u32(&mut options.ui.pamphlet_width, steps, 20, 200, 1000)
+			}
+			Setting::ConsoleHeight => step_u32(&mut options.ui.console_height, steps, 20, 100, 800),
+			Setting::BoardScale => step_f32(&mut options.ui.board_scale, steps),
+			Setting::MasterVolume => step_u8(&mut options.audio.master, steps),
+			Setting::SoundVolume => step_u8(&mut options.audio.sound, steps),
+			Setting::MusicVolume => step_u8(&mut options.audio.music, steps),
+			Setting::MovementDuration => {
+				let duration = &mut options.ui.movement_duration;
+				*duration = ((*duration + f64::from(steps) * 0.05) * 20.0)
+					.round()
+					.clamp(0.0, 10.0)
+					/ 20.0;
+			}
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum HealthBars {
+	Always,
+	/// Only for pieces that are hurt or afflicted by a status.
+	#[default]
+	OnDamage,
+	Never,
+}
+
 impl Default for UserInterface {
 	fn default() -> Self {
 		Self {
 			colors: Colors::default(),
+			color_scheme: None,
 			typography: typography::Options::default(),
 
+			ui_scale: 1.0,
+			dpi_scaling: true,
+			board_scale: 1.0,
 			pamphlet_width: 400,
 			console_height: 200,
+			console_history: 1000,
+			console_filter: console::Filter::default(),
+			movement_duration: 0.1,
+			damage_numbers: true,
+			hit_flash: true,
+			health_bars: HealthBars::default(),
+			developer_console: false,
+			move_preview: true,
+			effects: true,
+			effect_intensity: 1.0,
+			darkness: true,
 		}
 	}
 }
@@ -82,8 +327,59 @@ impl Default for UserInterface {
 pub struct Colors {
 	pub normal_mode: Color,
 	pub cast_mode: Color,
+	pub delay_mode: Color,
+	pub dialogue_mode: Color,
+	pub inventory_mode: Color,
+	pub settings_mode: Color,
+	pub search_mode: Color,
 	pub cursor_mode: Color,
+	/// Marks the part of a cursor's line which can't be selected.
+	pub out_of_range: Color,
+	pub damage: Color,
+	pub healing: Color,
+	pub friendly_health: Color,
+	pub enemy_health: Color,
+	pub reachable: Color,
+	pub attackable: Color,
 	pub console: console::Colors,
+	pub stats: StatColors,
+	/// Given to party members in order, repeating if the party outgrows them.
+	pub accents: Vec<Color>,
+}
+
+impl Colors {
+	/// The accent color of the party member at `index`.
+	pub fn accent(&self, index: usize) -> Color {
+		self.accents
+			.get(index % self.accents.len().max(1))
+			.copied()
+			.unwrap_or((0xFF, 0xFF, 0xFF, 0xFF))
+	}
+}
+
+/// Used when highlighting stats, such as within expressions.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatColors {
+	pub heart: Color,
+	pub soul: Color,
+	pub power: Color,
+	pub defense: Color,
+	pub magic: Color,
+	pub resistance: Color,
+}
+
+impl Default for StatColors {
+	fn default() -> Self {
+		Self {
+			heart: (96, 67, 18, 255),
+			soul: (128, 128, 128, 255),
+			power: (255, 11, 64, 255),
+			defense: (222, 120, 64, 255),
+			magic: (59, 115, 255, 255),
+			resistance: (222, 64, 255, 255),
+		}
+	}
 }
 
 impl Default for Colors {
@@ -91,21 +387,85 @@ impl Default for Colors {
 		Self {
 			normal_mode: (0x77, 0xE7, 0xA2, 0xFF),
 			cast_mode: (0xA2, 0x77, 0xE7, 0xFF),
+			delay_mode: (0x77, 0xA2, 0xE7, 0xFF),
+			dialogue_mode: (0xE7, 0x77, 0xC0, 0xFF),
+			inventory_mode: (0xC0, 0xE7, 0x77, 0xFF),
+			settings_mode: (0xE7, 0xE7, 0x77, 0xFF),
+			search_mode: (0x77, 0xE7, 0xE7, 0xFF),
 			cursor_mode: (0xE7, 0xA2, 0x77, 0xFF),
+			out_of_range: (0xE7, 0x4C, 0x4C, 0xFF),
+			damage: (0xE7, 0x4C, 0x4C, 0xFF),
+			healing: (0x77, 0xE7, 0x8C, 0xFF),
+			friendly_health: (0x77, 0xE7, 0x8C, 0xFF),
+			enemy_health: (0xE7, 0x4C, 0x4C, 0xFF),
+			reachable: (0x77, 0xA2, 0xE7, 0x30),
+			attackable: (0xE7, 0x4C, 0x4C, 0xFF),
 			console: console::Colors::default(),
+			stats: StatColors::default(),
+			accents: vec![
+				(0xDA, 0x2D, 0x5C, 0xFF),
+				(0x0C, 0x94, 0xFF, 0xFF),
+				(0x5C, 0xDA, 0x2D, 0xFF),
+				(0xFF, 0xB0, 0x0C, 0xFF),
+				(0xB0, 0x5C, 0xDA, 0xFF),
+				(0x2D, 0xDA, 0xC0, 0xFF),
+			],
 		}
 	}
 }
 
+/// Anything that can trigger a control.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub struct Key(Keycode);
+pub enum Key {
+	Keyboard(Keycode),
+	Button(Button),
+	/// Tilting a game controller's left stick.
+	Stick(character::OrdDir),
+}
+
+impl Key {
+	pub fn keycode(self) -> Option<Keycode> {
+		match self {
+			Key::Keyboard(keycode) => Some(keycode),
+			Key::Button(_) | Key::Stick(_) => None,
+		}
+	}
+}
+
+impl std::fmt::Display for Key {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Key::Keyboard(keycode) => write!(f, "{}", keycode.name()),
+			Key::Button(button) => write!(f, "Controller {}", button.string()),
+			Key::Stick(dir) => write!(f, "Stick {dir:?}"),
+		}
+	}
+}
+
+impl std::str::FromStr for Key {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, ()> {
+		if let Some(button) = s.strip_prefix("Controller ") {
+			Button::from_string(button).map(Key::Button).ok_or(())
+		} else if let Some(dir) = s.strip_prefix("Stick ") {
+			character::OrdDir::ALL
+				.into_iter()
+				.find(|x| format!("{x:?}") == dir)
+				.map(Key::Stick)
+				.ok_or(())
+		} else {
+			Keycode::from_name(s).map(Key::Keyboard).ok_or(())
+		}
+	}
+}
 
 impl serde::Serialize for Key {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	where
 		S: serde::Serializer,
 	{
-		serializer.serialize_str(&self.0.name())
+		serializer.serialize_str(&self.to_string())
 	}
 }
 
@@ -115,7 +475,7 @@ impl<'de> serde::de::Visitor<'de> for KeyVisitor {
 	type Value = String;
 
 	fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-		formatter.write_str("a string containing an expression")
+		formatter.write_str("a string containing a key name")
 	}
 
 	fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
@@ -139,10 +499,10 @@ impl<'de> serde::Deserialize<'de> for Key {
 		D: serde::Deserializer<'de>,
 	{
 		use serde::de::Error;
-		Ok(Key(Keycode::from_name(
-			&deserializer.deserialize_string(KeyVisitor)?,
-		)
-		.ok_or(D::Error::custom("unknown key name"))?))
+		deserializer
+			.deserialize_string(KeyVisitor)?
+			.parse()
+			.map_err(|()| D::Error::custom("unknown key name"))
 	}
 }
 
@@ -150,11 +510,103 @@ impl<'de> serde::Deserialize<'de> for Key {
 pub struct Triggers(Vec<Key>);
 
 impl Triggers {
-	pub fn contains(&self, keycode: Keycode) -> bool {
-		self.0.iter().any(|x| x.0 == keycode)
+	pub fn contains(&self, key: Key) -> bool {
+		self.0.contains(&key)
+	}
+
+	pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+		self.0.iter().copied()
+	}
+
+	/// Whether `text` is the character one of these keys types (without any modifiers).
+	pub fn types(&self, text: &str) -> bool {
+		self.keys()
+			.filter_map(Key::keycode)
+			// Keycodes for printable keys are the characters they type.
+			.filter_map(|keycode| char::from_u32(keycode.into_i32() as u32))
+			.any(|character| text.chars().eq([character]))
+	}
+
+	/// Add a key, unless it's already bound.
+	pub fn bind(&mut self, key: Key) {
+		if !self.contains(key) {
+			self.0.push(key);
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.0.clear();
 	}
 }
 
+impl std::fmt::Display for Triggers {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.0.is_empty() {
+			return write!(f, "(unbound)");
+		}
+		for (i, key) in self.0.iter().enumerate() {
+			if i > 0 {
+				write!(f, ", ")?;
+			}
+			write!(f, "{key}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Lists every binding in `Controls` alongside its name.
+macro_rules! bindings {
+	($self:ident, $($ref:tt)+) => {
+		[
+			("left", $($ref)+ $self.left),
+			("right", $($ref)+ $self.right),
+			("up", $($ref)+ $self.up),
+			("down", $($ref)+ $self.down),
+			("up_left", $($ref)+ $self.up_left),
+			("up_right", $($ref)+ $self.up_right),
+			("down_left", $($ref)+ $self.down_left),
+			("down_right", $($ref)+ $self.down_right),
+			("talk", $($ref)+ $self.talk),
+			("cast", $($ref)+ $self.cast),
+			("underfoot", $($ref)+ $self.underfoot),
+			("wait", $($ref)+ $self.wait),
+			("delay", $($ref)+ $self.delay),
+			("hold", $($ref)+ $self.hold),
+			("rest", $($ref)+ $self.rest),
+			("recruit", $($ref)+ $self.recruit),
+			("inventory", $($ref)+ $self.inventory),
+			("drop_item", $($ref)+ $self.drop_item),
+			("settings", $($ref)+ $self.settings),
+			("options", $($ref)+ $self.options),
+			("saves", $($ref)+ $self.saves),
+			("overwrite_save", $($ref)+ $self.overwrite_save),
+			("rename_save", $($ref)+ $self.rename_save),
+			("delete_save", $($ref)+ $self.delete_save),
+			("inspect", $($ref)+ $self.inspect),
+			("free_look", $($ref)+ $self.free_look),
+			("zoom_in", $($ref)+ $self.zoom_in),
+			("zoom_out", $($ref)+ $self.zoom_out),
+			("orders", $($ref)+ $self.orders),
+			("take_control", $($ref)+ $self.take_control),
+			("explore", $($ref)+ $self.explore),
+			("auto_fight", $($ref)+ $self.auto_fight),
+			("repeat_cast", $($ref)+ $self.repeat_cast),
+			("cycle_target", $($ref)+ $self.cycle_target),
+			("page_up", $($ref)+ $self.page_up),
+			("page_down", $($ref)+ $self.page_down),
+			("scroll_top", $($ref)+ $self.scroll_top),
+			("scroll_bottom", $($ref)+ $self.scroll_bottom),
+			("search", $($ref)+ $self.search),
+			("console_filter", $($ref)+ $self.console_filter),
+			("developer_console", $($ref)+ $self.developer_console),
+			("confirm", $($ref)+ $self.confirm),
+			("escape", $($ref)+ $self.escape),
+			("fullscreen", $($ref)+ $self.fullscreen),
+			("debug", $($ref)+ $self.debug),
+		]
+	};
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Controls {
@@ -170,6 +622,37 @@ pub struct Controls {
 	pub talk: Triggers,
 	pub cast: Triggers,
 	pub underfoot: Triggers,
+	pub wait: Triggers,
+	pub delay: Triggers,
+	pub hold: Triggers,
+	pub rest: Triggers,
+	pub recruit: Triggers,
+	pub inventory: Triggers,
+	pub drop_item: Triggers,
+	pub settings: Triggers,
+	pub options: Triggers,
+	pub saves: Triggers,
+	/// In the saves menu, replace the selected save with the current run.
+	pub overwrite_save: Triggers,
+	pub rename_save: Triggers,
+	pub delete_save: Triggers,
+	pub inspect: Triggers,
+	pub free_look: Triggers,
+	pub zoom_in: Triggers,
+	pub zoom_out: Triggers,
+	pub orders: Triggers,
+	pub take_control: Triggers,
+	pub explore: Triggers,
+	pub auto_fight: Triggers,
+	pub repeat_cast: Triggers,
+	pub cycle_target: Triggers,
+	pub page_up: Triggers,
+	pub page_down: Triggers,
+	pub scroll_top: Triggers,
+	pub scroll_bottom: Triggers,
+	pub search: Triggers,
+	pub console_filter: Triggers,
+	pub developer_console: Triggers,
 
 	pub confirm: Triggers,
 	pub escape: Triggers,
@@ -179,30 +662,112 @@ pub struct Controls {
 
 impl Default for Controls {
 	fn default() -> Self {
+		use character::OrdDir as D;
+		use Button as B;
+		use Key::{Button as Pad, Keyboard as Kb, Stick};
 		use Keycode as K;
 
 		Self {
-			left: Triggers(vec![Key(K::H), Key(K::Left), Key(K::Kp4)]),
-			right: Triggers(vec![Key(K::L), Key(K::Right), Key(K::Kp6)]),
-			up: Triggers(vec![Key(K::K), Key(K::Up), Key(K::Kp8)]),
-			down: Triggers(vec![Key(K::J), Key(K::Down), Key(K::Kp2)]),
-			up_left: Triggers(vec![Key(K::Y), Key(K::Kp7)]),
-			up_right: Triggers(vec![Key(K::U), Key(K::Kp9)]),
-			down_left: Triggers(vec![Key(K::B), Key(K::Kp1)]),
-			down_right: Triggers(vec![Key(K::N), Key(K::Kp3)]),
-
-			talk: Triggers(vec![Key(K::T)]),
-			cast: Triggers(vec![Key(K::Z)]),
-			underfoot: Triggers(vec![Key(K::Period)]),
-
-			confirm: Triggers(vec![Key(K::Return)]),
-			escape: Triggers(vec![Key(K::Escape)]),
-			fullscreen: Triggers(vec![Key(K::F11)]),
-			debug: Triggers(vec![Key(K::F1)]),
+			left: Triggers(vec![
+				Kb(K::H),
+				Kb(K::Left),
+				Kb(K::Kp4),
+				Pad(B::DPadLeft),
+				Stick(D::Left),
+			]),
+			right: Triggers(vec![
+				Kb(K::L),
+				Kb(K::Right),
+				Kb(K::Kp6),
+				Pad(B::DPadRight),
+				Stick(D::Right),
+			]),
+			up: Triggers(vec![
+				Kb(K::K),
+				Kb(K::Up),
+				Kb(K::Kp8),
+				Pad(B::DPadUp),
+				Stick(D::Up),
+			]),
+			down: Triggers(vec![
+				Kb(K::J),
+				Kb(K::Down),
+				Kb(K::Kp2),
+				Pad(B::DPadDown),
+				Stick(D::Down),
+			]),
+			up_left: Triggers(vec![Kb(K::Y), Kb(K::Kp7), Stick(D::UpLeft)]),
+			up_right: Triggers(vec![Kb(K::U), Kb(K::Kp9), Stick(D::UpRight)]),
+			down_left: Triggers(vec![Kb(K::B), Kb(K::Kp1), Stick(D::DownLeft)]),
+			down_right: Triggers(vec![Kb(K::N), Kb(K::Kp3), Stick(D::DownRight)]),
+
+			talk: Triggers(vec![Kb(K::T), Pad(B::RightShoulder)]),
+			cast: Triggers(vec![Kb(K::Z), Pad(B::X)]),
+			underfoot: Triggers(vec![Kb(K::Period), Pad(B::Y)]),
+			wait: Triggers(vec![Kb(K::S), Kb(K::Kp5), Pad(B::LeftShoulder)]),
+			delay: Triggers(vec![Kb(K::D)]),
+			hold: Triggers(vec![Kb(K::R)]),
+			rest: Triggers(vec![Kb(K::C)]),
+			recruit: Triggers(vec![Kb(K::P)]),
+			inventory: Triggers(vec![Kb(K::I), Pad(B::Back)]),
+			drop_item: Triggers(vec![Kb(K::X)]),
+			settings: Triggers(vec![Kb(K::F2), Pad(B::Start)]),
+			options: Triggers(vec![Kb(K::F5)]),
+			saves: Triggers(vec![Kb(K::F6)]),
+			overwrite_save: Triggers(vec![Kb(K::Insert), Pad(B::Y)]),
+			rename_save: Triggers(vec![Kb(K::F7), Pad(B::LeftStick)]),
+			delete_save: Triggers(vec![Kb(K::Delete), Pad(B::X)]),
+			inspect: Triggers(vec![Kb(K::Semicolon)]),
+			free_look: Triggers(vec![Kb(K::V)]),
+			zoom_in: Triggers(vec![Kb(K::Equals), Kb(K::KpPlus)]),
+			zoom_out: Triggers(vec![Kb(K::Minus), Kb(K::KpMinus)]),
+			orders: Triggers(vec![Kb(K::O)]),
+			take_control: Triggers(vec![Kb(K::G)]),
+			explore: Triggers(vec![Kb(K::E)]),
+			auto_fight: Triggers(vec![Kb(K::F)]),
+			repeat_cast: Triggers(vec![Kb(K::Q)]),
+			cycle_target: Triggers(vec![Kb(K::Tab), Pad(B::RightStick)]),
+			page_up: Triggers(vec![Kb(K::PageUp)]),
+			page_down: Triggers(vec![Kb(K::PageDown)]),
+			scroll_top: Triggers(vec![Kb(K::Home)]),
+			scroll_bottom: Triggers(vec![Kb(K::End)]),
+			search: Triggers(vec![Kb(K::F3)]),
+			console_filter: Triggers(vec![Kb(K::F4)]),
+			developer_console: Triggers(vec![Kb(K::Backquote)]),
+
+			confirm: Triggers(vec![Kb(K::Return), Pad(B::A)]),
+			escape: Triggers(vec![Kb(K::Escape), Pad(B::B)]),
+			fullscreen: Triggers(vec![Kb(K::F11)]),
+			debug: Triggers(vec![Kb(K::F1)]),
 		}
 	}
 }
 
+impl Controls {
+	pub fn bindings(&self) -> [(&'static str, &Triggers); 45] {
+		bindings!(self, &)
+	}
+
+	pub fn bindings_mut(&mut self) -> [(&'static str, &mut Triggers); 45] {
+		bindings!(self, &mut)
+	}
+
+	/// Returns the names of every other binding sharing a key with the named binding.
+	pub fn conflicts(&self, name: &str) -> Vec<&'static str> {
+		let bindings = self.bindings();
+		let Some((_, triggers)) = bindings.iter().find(|(x, _)| *x == name) else {
+			return Vec::new();
+		};
+		bindings
+			.iter()
+			.filter(|(other, other_triggers)| {
+				*other != name && triggers.keys().any(|key| other_triggers.contains(key))
+			})
+			.map(|(other, _)| *other)
+			.collect()
+	}
+}
+
 /// Potentially useful information for assinging lettered shortcuts for a list.
 ///
 /// Does not (currently) support shifted letters; they're probably necessary but I don't know how I feel about it yet.
@@ -0,0 +1,132 @@
+//! Progress that persists between runs, stored separately from any one run.
+
+use crate::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::{fs, io};
+use uuid::Uuid;
+
+/// The portion of a run's experience that gets banked, as a fraction of 1.
+const BANK_RATE: (u32, u32) = (1, 4);
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Profile {
+	/// Experience saved up from previous runs.
+	pub banked_experience: u32,
+	/// Spells that each character will start future runs with, keyed by name.
+	pub unlocked_spells: HashMap<String, Vec<String>>,
+	/// The deepest floor any run has reached.
+	pub deepest_floor: usize,
+	pub runs: u32,
+	/// Every run that has been banked, so that reloading one can't bank it again.
+	pub banked_runs: HashSet<Uuid>,
+}
+
+/// Every format a profile has ever been saved in.
+///
+/// New versions should be added here, along with a conversion in `Versioned::upgrade`,
+/// rather than changing the layout of an existing version.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "version")]
+enum Versioned {
+	#[serde(rename = "1")]
+	V1(Profile),
+}
+
+impl Versioned {
+	fn upgrade(self) -> Profile {
+		match self {
+			Versioned::V1(profile) => profile,
+		}
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OpenProfileError {
+	#[error("{0}")]
+	Io(#[from] io::Error),
+	#[error("{0}")]
+	Toml(#[from] toml::de::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SaveProfileError {
+	#[error("{0}")]
+	Io(#[from] io::Error),
+	#[error("{0}")]
+	Toml(#[from] toml::ser::Error),
+}
+
+impl Profile {
+	/// Open a profile, upgrading it from older versions if necessary.
+	///
+	/// # Errors
+	///
+	/// Fails if the file could not be opened or parsed.
+	pub fn open(path: impl AsRef<Path>) -> Result<Self, OpenProfileError> {
+		let versioned: Versioned = toml::from_str(&fs::read_to_string(path)?)?;
+		Ok(versioned.upgrade())
+	}
+
+	/// Save the profile in the latest format.
+	///
+	/// # Errors
+	///
+	/// Fails if the file could not be serialized or written.
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveProfileError> {
+		let versioned = Versioned::V1(self.clone());
+		fs::write(path, toml::to_string(&versioned)?)?;
+		Ok(())
+	}
+
+	/// Record the results of a finished run.
+	///
+	/// Runs that have already been banked are ignored.
+	pub fn bank(&mut self, world: &world::Manager) {
+		if !self.banked_runs.insert(world.run) {
+			return;
+		}
+		self.runs += 1;
+		self.deepest_floor = self.deepest_floor.max(world.location.floor);
+		for member in &world.party {
+			let piece = member.piece.borrow();
+			// Experience carried in from earlier runs (including banked experience) isn't earned twice.
+			let experience = piece
+				.total_experience()
+				.saturating_sub(member.starting_experience);
+			self.banked_experience += experience * BANK_RATE.0 / BANK_RATE.1;
+			// Anything a sheet learned during the run is remembered for next time.
+			// Transformed pieces report their original sheet's spells.
+			let sheet = piece
+				.transformations
+				.first()
+				.map_or(&piece.sheet, |transformation| &transformation.sheet);
+			let unlocked = self
+				.unlocked_spells
+				.entry(sheet.nouns.name.to_string())
+				.or_default();
+			for spell in &sheet.spells {
+				if !unlocked.contains(spell) {
+					unlocked.push(spell.clone());
+				}
+			}
+		}
+	}
+
+	/// Apply unlocks to a sheet at the start of a run.
+	///
+	/// Banked experience is spent here, up to just shy of a level.
+	pub fn prepare(&mut self, sheet: &mut character::Sheet) {
+		if let Some(spells) = self.unlocked_spells.get(&*sheet.nouns.name) {
+			for spell in spells {
+				if !sheet.spells.contains(spell) {
+					sheet.spells.push(spell.clone());
+				}
+			}
+		}
+		let experience = self.banked_experience.min(99 - sheet.experience.min(99));
+		sheet.experience += experience;
+		self.banked_experience -= experience;
+	}
+}
@@ -0,0 +1,111 @@
+//! A read-only view of the world for scripts, registered as the `World` global.
+//!
+//! Scripts can't borrow `world::Manager` directly,
+//! so this shares the piece index and keeps a copy of the current floor and the level's rules,
+//! which the manager refreshes whenever the floor changes.
+
+use crate::prelude::*;
+use mlua::LuaSerdeExt;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Cloning a query shares its view of the world.
+#[derive(Clone, Debug, Default, mlua::FromLua)]
+pub struct Query {
+	pub pieces: spatial::Handle,
+	floor: Rc<RefCell<Floor>>,
+	economy: Rc<Cell<world::Economy>>,
+}
+
+impl Query {
+	pub fn new(pieces: spatial::Handle) -> Self {
+		Self {
+			pieces,
+			floor: Rc::default(),
+			economy: Rc::default(),
+		}
+	}
+
+	pub fn set_floor(&self, floor: &Floor) {
+		*self.floor.borrow_mut() = floor.clone();
+	}
+
+	pub fn set_economy(&self, economy: world::Economy) {
+		self.economy.set(economy);
+	}
+
+	pub fn economy(&self) -> world::Economy {
+		self.economy.get()
+	}
+
+	/// How many steps it takes to walk from `from` to `to`, ignoring other pieces.
+	pub fn path_length(&self, from: (i32, i32), to: (i32, i32)) -> Option<u32> {
+		let distances = ai::distance_map(&self.floor.borrow(), [to].into_iter());
+		distances.get(from.1, from.0).copied().flatten()
+	}
+
+	pub fn nearest_enemy(&self, character: &world::CharacterRef) -> Option<world::CharacterRef> {
+		let floor = self.floor.borrow();
+		let radius = floor.map.rows().max(floor.map.cols()) as u32;
+		self.pieces.borrow().nearest_hostile(character, radius)
+	}
+}
+
+fn position(character: &world::CharacterRef) -> (i32, i32) {
+	let piece = character.borrow();
+	(piece.x, piece.y)
+}
+
+impl mlua::UserData for Query {
+	fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+		methods.add_method(
+			"characters_within",
+			|_, this, (x, y, radius): (i32, i32, u32)| {
+				Ok(this.pieces.borrow().pieces_in_radius(x, y, radius))
+			},
+		);
+		methods.add_method("pieces_at", |_, this, (x, y): (i32, i32)| {
+			Ok(this
+				.pieces
+				.borrow()
+				.pieces_at(x, y)
+				.cloned()
+				.collect::<Vec<_>>())
+		});
+		methods.add_method("tile_at", |lua, this, (x, y): (i32, i32)| {
+			match this.floor.borrow().map.get(y, x) {
+				Some(tile) => lua.to_value(tile),
+				None => Ok(mlua::Value::Nil),
+			}
+		});
+		methods.add_method(
+			"line_of_sight",
+			|_,
+			 this,
+			 (a, b): (
+				mlua::UserDataRef<world::CharacterRef>,
+				mlua::UserDataRef<world::CharacterRef>,
+			)| {
+				Ok(this
+					.floor
+					.borrow()
+					.line_of_sight(position(&a), position(&b)))
+			},
+		);
+		methods.add_method(
+			"path_length",
+			|_,
+			 this,
+			 (a, b): (
+				mlua::UserDataRef<world::CharacterRef>,
+				mlua::UserDataRef<world::CharacterRef>,
+			)| { Ok(this.path_length(position(&a), position(&b))) },
+		);
+		methods.add_method(
+			"nearest_enemy",
+			|_, this, character: mlua::UserDataRef<world::CharacterRef>| {
+				Ok(this.nearest_enemy(&character))
+			},
+		);
+	}
+}
@@ -0,0 +1,93 @@
+//! Multi-stage objectives, advanced by `world::Event`s.
+
+use crate::prelude::*;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Quest {
+	pub name: String,
+	pub stages: Vec<Stage>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Stage {
+	/// Shown on the pamphlet while this stage is active.
+	pub description: String,
+	pub condition: Condition,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum Condition {
+	/// Defeat some number of characters with the given name.
+	Kill { name: String, count: u32 },
+	/// Reach the given floor (or deeper).
+	ReachFloor { floor: usize },
+	/// Talk to a character with the given name.
+	TalkTo { name: String },
+}
+
+impl Condition {
+	/// Returns how much progress `event` makes towards this condition.
+	fn progress(&self, event: &world::Event) -> u32 {
+		match (self, event) {
+			(Condition::Kill { name, .. }, world::Event::Killed(killed)) if name == killed => 1,
+			(Condition::ReachFloor { floor }, world::Event::FloorEntered(entered))
+				if entered >= floor =>
+			{
+				1
+			}
+			(Condition::TalkTo { name }, world::Event::Talked(talked)) if name == talked => 1,
+			_ => 0,
+		}
+	}
+
+	fn required(&self) -> u32 {
+		match self {
+			Condition::Kill { count, .. } => *count,
+			Condition::ReachFloor { .. } | Condition::TalkTo { .. } => 1,
+		}
+	}
+}
+
+/// A quest which has been started, and how far along it is.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Active {
+	pub quest: Quest,
+	pub stage: usize,
+	/// Progress towards the current stage's condition.
+	pub progress: u32,
+}
+
+impl Active {
+	pub fn new(quest: Quest) -> Self {
+		Self {
+			quest,
+			stage: 0,
+			progress: 0,
+		}
+	}
+
+	pub fn current(&self) -> Option<&Stage> {
+		self.quest.stages.get(self.stage)
+	}
+
+	pub fn is_complete(&self) -> bool {
+		self.stage >= self.quest.stages.len()
+	}
+
+	/// Returns true if the event advanced the quest to a new stage.
+	pub fn update(&mut self, event: &world::Event) -> bool {
+		let Some(stage) = self.current() else {
+			return false;
+		};
+		let required = stage.condition.required();
+		self.progress += stage.condition.progress(event);
+		if self.progress >= required {
+			self.stage += 1;
+			self.progress = 0;
+			true
+		} else {
+			false
+		}
+	}
+}
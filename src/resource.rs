@@ -1,7 +1,6 @@
 use crate::prelude::*;
 use sdl2::image::LoadTexture;
 use sdl2::render::{Texture, TextureCreator};
-use sdl2::video::WindowContext;
 use std::cell::{Cell, OnceCell};
 use std::collections::HashMap;
 use std::fs;
@@ -30,6 +29,82 @@ impl<T: Clone + mlua::UserData + 'static> mlua::UserData for Handle<T> {
 	}
 }
 
+/// A `'static` view of the resources that scripts may need,
+/// since `Manager` itself is bound to the lifetime of its texture creator.
+#[derive(Clone, mlua::FromLua)]
+pub struct Catalog {
+	pub sheets: Rc<Resource<character::Sheet>>,
+	pub attacks: Rc<Resource<Rc<Attack>>>,
+	pub spells: Rc<Resource<Rc<Spell>>>,
+	pub ai: Rc<Resource<ai::Profile>>,
+	pub items: Rc<Resource<item::Definition>>,
+	pub learnsets: Rc<Resource<Learnset>>,
+}
+
+impl mlua::UserData for Catalog {}
+
+impl Catalog {
+	/// # Errors
+	///
+	/// Returns an error if the sheet could not be found.
+	pub fn get_sheet(&self, key: &str) -> Result<&character::Sheet> {
+		Ok(self
+			.sheets
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// # Errors
+	///
+	/// Returns an error if the attack could not be found.
+	pub fn get_attack(&self, key: &str) -> Result<&Rc<Attack>> {
+		Ok(self
+			.attacks
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// # Errors
+	///
+	/// Returns an error if the spell could not be found.
+	pub fn get_spell(&self, key: &str) -> Result<&Rc<Spell>> {
+		Ok(self
+			.spells
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// # Errors
+	///
+	/// Returns an error if the AI profile could not be found.
+	pub fn get_ai(&self, key: &str) -> Result<&ai::Profile> {
+		Ok(self
+			.ai
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// # Errors
+	///
+	/// Returns an error if the item could not be found.
+	pub fn get_item(&self, key: &str) -> Result<&item::Definition> {
+		Ok(self
+			.items
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// # Errors
+	///
+	/// Returns an error if the learnset could not be found.
+	pub fn get_learnset(&self, key: &str) -> Result<&Learnset> {
+		Ok(self
+			.learnsets
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+}
+
 /// Handles lazy loading of textures into memory and video memory.
 #[derive(Default)]
 struct TextureInfo<'texture> {
@@ -48,6 +123,23 @@ struct TextureInfo<'texture> {
 	had_error: Cell<bool>,
 }
 
+/// Loads textures for any kind of renderer,
+/// so that resources can be opened without a window (such as when simulating).
+trait TextureLoader<'texture> {
+	fn load(&self, path: &Path) -> Result<Texture<'texture>, String>;
+	fn load_bytes(&self, bytes: &[u8]) -> Result<Texture<'texture>, String>;
+}
+
+impl<'texture, T> TextureLoader<'texture> for &'texture TextureCreator<T> {
+	fn load(&self, path: &Path) -> Result<Texture<'texture>, String> {
+		self.load_texture(path)
+	}
+
+	fn load_bytes(&self, bytes: &[u8]) -> Result<Texture<'texture>, String> {
+		self.load_texture_bytes(bytes)
+	}
+}
+
 /// Manages all resource loading in a central, abstracted structure.
 ///
 /// The primary benefit of using this structure is that it abstracts
@@ -55,23 +147,43 @@ struct TextureInfo<'texture> {
 /// `resource::Manager` can also cache certain resources to avoid repeated disk reads,
 /// meaning outside code doesn't need to store permanent references to resources.
 pub struct Manager<'texture> {
-	texture_creator: &'texture TextureCreator<WindowContext>,
+	texture_creator: Box<dyn TextureLoader<'texture> + 'texture>,
 
 	/// `Attack`s need to be owned by many pieces, but rarely need to be mutated, so it's more convenient to provide an `Rc`.
-	attacks: Resource<Rc<Attack>>,
+	attacks: Rc<Resource<Rc<Attack>>>,
 	/// `Spells`s need to be owned by many pieces, but rarely need to be mutated, so it's more convenient to provide an `Rc`.
-	spells: Resource<Rc<Spell>>,
+	spells: Rc<Resource<Rc<Spell>>>,
 	/// Unlike `Attack`s and `Spell`s, `character::Sheet`s are likely to be modified.
-	sheets: Resource<character::Sheet>,
+	sheets: Rc<Resource<character::Sheet>>,
 	statuses: Rc<Resource<Status>>,
 	textures: Resource<TextureInfo<'texture>>,
 	vaults: Resource<Vault>,
+	vault_sets: Resource<vault::Set>,
+	spawn_tables: Resource<spawn::Table>,
+	biomes: Resource<Biome>,
+	palettes: Resource<biome::Palette>,
+	tilesets: Resource<biome::Tileset>,
+	color_schemes: Resource<options::Colors>,
+	dialogues: Resource<dialogue::Dialogue>,
+	quests: Resource<quest::Quest>,
+	ai: Rc<Resource<ai::Profile>>,
+	items: Rc<Resource<item::Definition>>,
+	learnsets: Rc<Resource<Learnset>>,
+	/// Keyed by the texture they describe.
+	animations: Resource<animation::Sheet>,
 
 	missing_texture: Texture<'texture>,
 }
 
 impl mlua::UserData for Manager<'_> {}
 
+/// Load a resource which may contain scripts, and so may declare an `api_version`.
+fn load_scripted<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+	let mut table: toml::Table = toml::from_str(&fs::read_to_string(path)?)?;
+	script::check_api_version(&mut table, &path.display().to_string())?;
+	Ok(table.try_into()?)
+}
+
 fn register<T>(directory: &Path, loader: &dyn Fn(&Path) -> Result<T>) -> Result<Resource<T>> {
 	let mut container = Resource::new();
 	recurse(&mut container, directory, directory, loader)?;
@@ -132,37 +244,73 @@ impl<'texture> Manager<'texture> {
 	///
 	/// Returns an error if ANYTHING fails to be read/parsed.
 	/// This is probably undesirable and should be moved to logging/diagnostics.
-	pub fn open(
+	pub fn open<T>(
 		path: impl AsRef<Path>,
-		texture_creator: &'texture TextureCreator<WindowContext>,
+		texture_creator: &'texture TextureCreator<T>,
 	) -> Result<Manager<'texture>> {
 		let path = path.as_ref();
 
-		let sheets = register(&path.join("sheets"), &|path| {
+		let sheets = register(&path.join("sheets"), &load_scripted)?.into();
+
+		let statuses = register(&path.join("statuses"), &load_scripted)?.into();
+
+		let attacks = register(&path.join("attacks"), &load_scripted)?.into();
+
+		let spells = register(&path.join("spells"), &load_scripted)?.into();
+
+		let textures = register(&path.join("textures"), &|path| {
+			Ok(TextureInfo {
+				path: path.to_path_buf(),
+				..Default::default()
+			})
+		})?;
+
+		let vaults = register(&path.join("vaults"), &|path| Vault::open(path))?;
+
+		let vault_sets = register(&path.join("vault_sets"), &|path| {
 			Ok(toml::from_str(&fs::read_to_string(path)?)?)
 		})?;
 
-		let statuses = register(&path.join("statuses"), &|path| {
+		let spawn_tables = register(&path.join("spawn_tables"), &|path| {
 			Ok(toml::from_str(&fs::read_to_string(path)?)?)
-		})?
-		.into();
+		})?;
 
-		let attacks = register(&path.join("attacks"), &|path| {
+		let biomes = register(&path.join("biomes"), &|path| {
 			Ok(toml::from_str(&fs::read_to_string(path)?)?)
 		})?;
 
-		let spells = register(&path.join("spells"), &|path| {
+		let palettes = register(&path.join("palettes"), &|path| {
 			Ok(toml::from_str(&fs::read_to_string(path)?)?)
 		})?;
 
-		let textures = register(&path.join("textures"), &|path| {
-			Ok(TextureInfo {
-				path: path.to_path_buf(),
-				..Default::default()
-			})
+		let tilesets = register(&path.join("tilesets"), &|path| {
+			Ok(toml::from_str(&fs::read_to_string(path)?)?)
 		})?;
 
-		let vaults = register(&path.join("vaults"), &|path| Vault::open(path))?;
+		let color_schemes = register(&path.join("color_schemes"), &|path| {
+			Ok(toml::from_str(&fs::read_to_string(path)?)?)
+		})?;
+
+		let dialogues = register(&path.join("dialogues"), &|path| {
+			Ok(toml::from_str(&fs::read_to_string(path)?)?)
+		})?;
+
+		let quests = register(&path.join("quests"), &|path| {
+			Ok(toml::from_str(&fs::read_to_string(path)?)?)
+		})?;
+
+		let ai = register(&path.join("ai"), &|path| {
+			Ok(toml::from_str(&fs::read_to_string(path)?)?)
+		})?
+		.into();
+
+		let items = register(&path.join("items"), &load_scripted)?.into();
+
+		let learnsets = register(&path.join("learnsets"), &load_scripted)?.into();
+
+		let animations = register(&path.join("animations"), &|path| {
+			Ok(toml::from_str(&fs::read_to_string(path)?)?)
+		})?;
 
 		// Include a missing texture placeholder, rather than returning an Option.
 		let missing_texture = texture_creator
@@ -170,7 +318,7 @@ impl<'texture> Manager<'texture> {
 			.map_err(crate::Error::Sdl)?;
 
 		Ok(Self {
-			texture_creator,
+			texture_creator: Box::new(texture_creator),
 
 			attacks,
 			spells,
@@ -178,6 +326,18 @@ impl<'texture> Manager<'texture> {
 			statuses,
 			textures,
 			vaults,
+			vault_sets,
+			spawn_tables,
+			biomes,
+			palettes,
+			tilesets,
+			color_schemes,
+			dialogues,
+			quests,
+			ai,
+			items,
+			learnsets,
+			animations,
 
 			missing_texture,
 		})
@@ -187,6 +347,17 @@ impl<'texture> Manager<'texture> {
 		Handle(self.statuses.clone())
 	}
 
+	pub fn catalog(&self) -> Catalog {
+		Catalog {
+			sheets: self.sheets.clone(),
+			attacks: self.attacks.clone(),
+			spells: self.spells.clone(),
+			ai: self.ai.clone(),
+			items: self.items.clone(),
+			learnsets: self.learnsets.clone(),
+		}
+	}
+
 	/// Return the given sheet.
 	///
 	/// # Errors
@@ -235,6 +406,29 @@ impl<'texture> Manager<'texture> {
 			.ok_or_else(|| Error::NotFound(key.into()))?)
 	}
 
+	/// Return the given item.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the item could not be found.
+	pub fn get_item(&self, key: &str) -> Result<&item::Definition> {
+		Ok(self
+			.items
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the animation metadata for the given texture, if it has any.
+	pub fn get_animation(&self, texture: &str) -> Option<&animation::Sheet> {
+		self.animations.get(texture)
+	}
+
+	/// Return the texture used to draw the given item.
+	/// Items without a definition are assumed to share a name with their texture.
+	pub fn get_item_texture(&self, key: &str) -> &Texture<'_> {
+		self.get_texture(self.get_item(key).map_or(key, |item| item.icon.as_str()))
+	}
+
 	/// Return the given texture.
 	/// If the texture cannot be found, returns the missing texture placeholder.
 	pub fn get_texture(&self, key: &str) -> &Texture {
@@ -243,7 +437,7 @@ impl<'texture> Manager<'texture> {
 		};
 		texture_info
 			.texture
-			.get_or_try_init(|| self.texture_creator.load_texture(&texture_info.path))
+			.get_or_try_init(|| self.texture_creator.load(&texture_info.path))
 			.unwrap_or_else(|msg| {
 				if !texture_info.had_error.get() {
 					eprintln!(
@@ -279,7 +473,7 @@ impl<'texture> Manager<'texture> {
 			.image
 			.get_or_try_init(|| fs::read(&texture_info.path))?;
 		self.texture_creator
-			.load_texture_bytes(image)
+			.load_bytes(image)
 			.map_err(crate::Error::Sdl)
 	}
 
@@ -294,4 +488,100 @@ impl<'texture> Manager<'texture> {
 			.get(key)
 			.ok_or_else(|| Error::NotFound(key.into()))?)
 	}
+
+	/// Return the given vault set.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the vault set could not be found.
+	pub fn get_vault_set(&self, key: &str) -> Result<&vault::Set> {
+		Ok(self
+			.vault_sets
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the given spawn table.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the spawn table could not be found.
+	pub fn get_spawn_table(&self, key: &str) -> Result<&spawn::Table> {
+		Ok(self
+			.spawn_tables
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the given biome.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the biome could not be found.
+	pub fn get_biome(&self, key: &str) -> Result<&Biome> {
+		Ok(self
+			.biomes
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the given palette.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the palette could not be found.
+	pub fn get_palette(&self, key: &str) -> Result<&biome::Palette> {
+		Ok(self
+			.palettes
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the given tileset.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the tileset could not be found.
+	pub fn get_tileset(&self, key: &str) -> Result<&biome::Tileset> {
+		Ok(self
+			.tilesets
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the given color scheme.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the color scheme could not be found.
+	pub fn get_color_scheme(&self, key: &str) -> Result<&options::Colors> {
+		Ok(self
+			.color_schemes
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the given dialogue.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the dialogue could not be found.
+	pub fn get_dialogue(&self, key: &str) -> Result<&dialogue::Dialogue> {
+		Ok(self
+			.dialogues
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
+
+	/// Return the given quest.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the quest could not be found.
+	pub fn get_quest(&self, key: &str) -> Result<&quest::Quest> {
+		Ok(self
+			.quests
+			.get(key)
+			.ok_or_else(|| Error::NotFound(key.into()))?)
+	}
 }
@@ -0,0 +1,235 @@
+//! Snapshots of a run.
+//!
+//! Saves are written to slots chosen by the player, to a rotating set of autosaves,
+//! and as a best effort when the game is about to go down unexpectedly.
+//! Each save has a small metadata file beside it, so that saves can be listed without loading them.
+
+use crate::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+const EMERGENCY_PREFIX: &str = "emergency-";
+const AUTOSAVE_PREFIX: &str = "autosave-";
+const SAVE_PREFIX: &str = "save-";
+const EXTENSION: &str = "sav";
+const METADATA_EXTENSION: &str = "toml";
+
+#[derive(Debug, thiserror::Error)]
+pub enum OpenSaveError {
+	#[error("{0}")]
+	Io(#[from] io::Error),
+	#[error("{0}")]
+	Toml(#[from] toml::de::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WriteSaveError {
+	#[error("{0}")]
+	Io(#[from] io::Error),
+	#[error("{0}")]
+	Toml(#[from] toml::ser::Error),
+}
+
+/// A summary of a save, for choosing between them.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Metadata {
+	pub name: String,
+	/// The icon of each party member, in order.
+	pub party: Vec<String>,
+	pub level: String,
+	pub floor: usize,
+	/// When the save was written, in seconds since the Unix epoch.
+	pub timestamp: u64,
+}
+
+impl Metadata {
+	pub fn new(name: String, world: &world::Manager) -> Self {
+		Self {
+			name,
+			party: world
+				.party
+				.iter()
+				.map(|member| member.piece.borrow().sheet.icon.clone())
+				.collect(),
+			level: world.level.name.clone(),
+			floor: world.location.floor,
+			timestamp: now(),
+		}
+	}
+}
+
+/// A save which has been found in a saves directory.
+#[derive(Clone, Debug)]
+pub struct Slot {
+	pub path: PathBuf,
+	pub metadata: Metadata,
+}
+
+/// Where saves are kept.
+pub fn directory() -> PathBuf {
+	options::user_directory().join("saves")
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs()
+}
+
+fn write_to(world: &world::Manager, path: &Path, name: String) -> Result<(), WriteSaveError> {
+	fs::write(path, toml::to_string(world)?)?;
+	fs::write(
+		path.with_extension(METADATA_EXTENSION),
+		toml::to_string(&Metadata::new(name, world))?,
+	)?;
+	Ok(())
+}
+
+/// Write the world to a new save in the given directory.
+///
+/// # Errors
+///
+/// Fails if the world could not be serialized or written.
+pub fn write(
+	world: &world::Manager,
+	directory: impl AsRef<Path>,
+	name: String,
+) -> Result<PathBuf, WriteSaveError> {
+	let directory = directory.as_ref();
+	fs::create_dir_all(directory)?;
+	let timestamp = now();
+	let mut path = directory.join(format!("{SAVE_PREFIX}{timestamp}.{EXTENSION}"));
+	let mut copy = 1;
+	while path.exists() {
+		path = directory.join(format!("{SAVE_PREFIX}{timestamp}-{copy}.{EXTENSION}"));
+		copy += 1;
+	}
+	write_to(world, &path, name)?;
+	Ok(path)
+}
+
+/// Write the world over an existing save, keeping its name.
+///
+/// # Errors
+///
+/// Fails if the world could not be serialized or written.
+pub fn overwrite(world: &world::Manager, slot: &Slot) -> Result<(), WriteSaveError> {
+	write_to(world, &slot.path, slot.metadata.name.clone())
+}
+
+/// Write the world to the oldest of `slots` rotating autosaves in the given directory.
+///
+/// # Errors
+///
+/// Fails if the world could not be serialized or written.
+pub fn write_autosave(
+	world: &world::Manager,
+	directory: impl AsRef<Path>,
+	slots: usize,
+) -> Result<PathBuf, WriteSaveError> {
+	let directory = directory.as_ref();
+	fs::create_dir_all(directory)?;
+	// Unused slots have no modification time, so they're filled first.
+	let path = (0..slots.max(1))
+		.map(|i| directory.join(format!("{AUTOSAVE_PREFIX}{i}.{EXTENSION}")))
+		.min_by_key(|path| fs::metadata(path).and_then(|x| x.modified()).ok())
+		.unwrap_or_else(|| directory.join(format!("{AUTOSAVE_PREFIX}0.{EXTENSION}")));
+	write_to(world, &path, "Autosave".into())?;
+	Ok(path)
+}
+
+/// Write the world to `emergency-<timestamp>.sav` in the given directory.
+///
+/// # Errors
+///
+/// Fails if the world could not be serialized or written.
+pub fn write_emergency(
+	world: &world::Manager,
+	directory: impl AsRef<Path>,
+) -> Result<PathBuf, WriteSaveError> {
+	let directory = directory.as_ref();
+	fs::create_dir_all(directory)?;
+	let timestamp = now();
+	let path = directory.join(format!("{EMERGENCY_PREFIX}{timestamp}.{EXTENSION}"));
+	write_to(world, &path, "Emergency save".into())?;
+	Ok(path)
+}
+
+/// List every save in the given directory, most recent first.
+///
+/// Saves without metadata are left out.
+pub fn list(directory: impl AsRef<Path>) -> Vec<Slot> {
+	let Ok(entries) = fs::read_dir(directory) else {
+		return Vec::new();
+	};
+	let mut slots: Vec<Slot> = entries
+		.filter_map(|entry| entry.ok())
+		.map(|entry| entry.path())
+		.filter(|path| path.extension().is_some_and(|x| x == EXTENSION))
+		.filter_map(|path| {
+			let metadata = fs::read_to_string(path.with_extension(METADATA_EXTENSION)).ok()?;
+			let metadata = toml::from_str(&metadata).ok()?;
+			Some(Slot { path, metadata })
+		})
+		.collect();
+	slots.sort_by_key(|slot| std::cmp::Reverse(slot.metadata.timestamp));
+	slots
+}
+
+/// Delete a save along with its metadata.
+///
+/// # Errors
+///
+/// Fails if the save could not be removed.
+pub fn delete(slot: &Slot) -> io::Result<()> {
+	fs::remove_file(&slot.path)?;
+	// A save is no longer listed without its metadata, so this failing isn't a problem.
+	let _ = fs::remove_file(slot.path.with_extension(METADATA_EXTENSION));
+	Ok(())
+}
+
+/// Change the name a save is listed under.
+///
+/// # Errors
+///
+/// Fails if the metadata could not be written.
+pub fn rename(slot: &mut Slot, name: String) -> Result<(), WriteSaveError> {
+	slot.metadata.name = name;
+	fs::write(
+		slot.path.with_extension(METADATA_EXTENSION),
+		toml::to_string(&slot.metadata)?,
+	)?;
+	Ok(())
+}
+
+/// Find the most recent emergency save in the given directory, if there are any.
+pub fn latest_emergency(directory: impl AsRef<Path>) -> Option<PathBuf> {
+	fs::read_dir(directory)
+		.ok()?
+		.filter_map(|entry| entry.ok())
+		.map(|entry| entry.path())
+		.filter_map(|path| {
+			let timestamp: u64 = path
+				.file_stem()?
+				.to_str()?
+				.strip_prefix(EMERGENCY_PREFIX)?
+				.parse()
+				.ok()?;
+			(path.extension()? == EXTENSION).then_some((timestamp, path))
+		})
+		.max_by_key(|(timestamp, _)| *timestamp)
+		.map(|(_, path)| path)
+}
+
+/// Read a saved world.
+///
+/// The world must be passed to `world::Manager::attach` before it can be played.
+///
+/// # Errors
+///
+/// Fails if the file could not be read or parsed.
+pub fn open(path: impl AsRef<Path>) -> Result<world::Manager, OpenSaveError> {
+	Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
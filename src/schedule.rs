@@ -0,0 +1,83 @@
+//! Callbacks which run after some amount of turn-time has passed.
+//!
+//! Scripts schedule callbacks through the `Scheduler` global:
+//! ```lua
+//! Scheduler:schedule(36, "callbacks/explode", { x = piece.x, y = piece.y })
+//! ```
+//! Callbacks are referred to by the name of a module in the scripts directory,
+//! which must return a function; it's called with the given arguments once the timer runs out.
+//! Storing the module's name rather than the function itself allows timers to be saved.
+
+use crate::prelude::*;
+use mlua::LuaSerdeExt;
+use std::sync::mpsc;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Timer {
+	pub remaining: Aut,
+	pub callback: String,
+	#[serde(default)]
+	pub args: Option<toml::Value>,
+}
+
+impl Timer {
+	/// # Errors
+	///
+	/// Returns an error if the callback's module could not be loaded or if it fails.
+	pub fn run(&self, lua: &mlua::Lua) -> mlua::Result<()> {
+		script::timed(&self.callback, || {
+			let require: mlua::Function = lua.globals().get("require")?;
+			let callback: mlua::Function = require.call(self.callback.as_str())?;
+			callback.call(lua.to_value(&self.args)?)
+		})
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Handle {
+	sender: mpsc::Sender<Timer>,
+}
+
+impl mlua::UserData for Handle {
+	fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+		methods.add_method(
+			"schedule",
+			|lua, this, (delay, callback, args): (Aut, String, mlua::Value)| {
+				let args = match args {
+					mlua::Value::Nil => None,
+					args => Some(lua.from_value(args)?),
+				};
+				this.sender
+					.send(Timer {
+						remaining: delay,
+						callback,
+						args,
+					})
+					.map_err(mlua::Error::external)
+			},
+		);
+	}
+}
+
+#[derive(Debug)]
+pub struct Scheduler {
+	pub handle: Handle,
+	receiver: mpsc::Receiver<Timer>,
+}
+
+impl Default for Scheduler {
+	fn default() -> Self {
+		let (sender, receiver) = mpsc::channel();
+		Self {
+			handle: Handle { sender },
+			receiver,
+		}
+	}
+}
+
+impl Scheduler {
+	/// Collect any timers that scripts have scheduled since the last call.
+	pub fn drain(&self) -> impl Iterator<Item = Timer> + '_ {
+		self.receiver.try_iter()
+	}
+}
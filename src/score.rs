@@ -0,0 +1,241 @@
+//! Scores for finished runs, and the local leaderboard they're ranked on.
+
+use crate::prelude::*;
+use std::path::Path;
+use std::{fs, io};
+use uuid::Uuid;
+
+/// Points for each floor reached.
+const FLOOR_POINTS: u32 = 1000;
+/// Points for each defeated enemy.
+const KILL_POINTS: u32 = 50;
+/// Turns allowed per floor before the speed bonus runs out.
+const PAR_TURNS: u32 = 300;
+const VICTORY_POINTS: u32 = 5000;
+/// Extra points for playing with attrition, as a percentage.
+const ATTRITION_BONUS: u32 = 25;
+/// Extra points for playing with permadeath, as a percentage.
+const PERMADEATH_BONUS: u32 = 25;
+/// How many entries the leaderboard remembers.
+const LEADERBOARD_CAPACITY: usize = 100;
+
+/// How a run's score was reached.
+#[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Score {
+	pub depth: u32,
+	pub kills: u32,
+	/// Awarded for reaching floors in fewer turns than par.
+	pub speed: u32,
+	pub victory: u32,
+	/// Percentage applied to the sum of everything else.
+	pub modifier: u32,
+}
+
+impl Score {
+	pub fn new(world: &world::Manager, outcome: morgue::Outcome) -> Self {
+		let stats = &world.stats;
+		let floors = stats.deepest_floor as u32;
+		// Party members show up in the kill table too, but losing them isn't worth points.
+		let kills = stats
+			.kills
+			.iter()
+			.filter(|(name, _)| {
+				!world
+					.party
+					.iter()
+					.any(|member| *member.piece.borrow().sheet.nouns.name == ***name)
+			})
+			.map(|(_, count)| count)
+			.sum::<u32>();
+		let rules = &world.rules;
+		// Half of any change to enemy stats or damage taken carries over into the score.
+		let mut modifier =
+			(100 + rules.enemy_stats / 2 + rules.damage_taken / 2).saturating_sub(100);
+		if rules.hunger && world.level.attrition.enabled {
+			modifier += ATTRITION_BONUS;
+		}
+		if rules.permadeath {
+			modifier += PERMADEATH_BONUS;
+		}
+		Self {
+			depth: floors * FLOOR_POINTS,
+			kills: kills * KILL_POINTS,
+			speed: (floors * PAR_TURNS).saturating_sub(stats.turns()),
+			victory: if outcome == morgue::Outcome::Victory {
+				VICTORY_POINTS
+			} else {
+				0
+			},
+			modifier,
+		}
+	}
+
+	pub fn total(&self) -> u32 {
+		(self.depth + self.kills + self.speed + self.victory) * self.modifier / 100
+	}
+}
+
+/// A finished run, as remembered by the leaderboard.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+	/// The name of the party's leader.
+	pub name: String,
+	pub score: Score,
+	/// Which level the run took place in; runs are usually compared within the same one.
+	pub level: String,
+	pub seed: u64,
+	pub floor: usize,
+	pub turns: u32,
+	pub victory: bool,
+	/// When the run ended, in seconds since the Unix epoch.
+	pub timestamp: u64,
+	/// Which run this was; see `world::Manager::run`.
+	#[serde(default = "Uuid::new_v4")]
+	pub run: Uuid,
+}
+
+impl Entry {
+	pub fn new(world: &world::Manager, outcome: morgue::Outcome) -> Self {
+		Self {
+			name: world
+				.party
+				.first()
+				.map(|member| member.piece.borrow().sheet.nouns.name.to_string())
+				.unwrap_or_default(),
+			score: Score::new(world, outcome),
+			level: world.level.name.clone(),
+			seed: world.seed,
+			floor: world.stats.deepest_floor,
+			turns: world.stats.turns(),
+			victory: outcome == morgue::Outcome::Victory,
+			timestamp: std::time::SystemTime::now()
+				.duration_since(std::time::UNIX_EPOCH)
+				.map(|x| x.as_secs())
+				.unwrap_or_default(),
+			run: world.run,
+		}
+	}
+}
+
+/// Narrows down which entries are shown.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+	pub level: Option<String>,
+	pub seed: Option<u64>,
+}
+
+impl Filter {
+	pub fn shows(&self, entry: &Entry) -> bool {
+		self.level
+			.as_ref()
+			.is_none_or(|level| *level == entry.level)
+			&& self.seed.is_none_or(|seed| seed == entry.seed)
+	}
+}
+
+/// Which runs to compare the current one against.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Scope {
+	#[default]
+	All,
+	/// Runs in the same level.
+	Level,
+	/// Runs with the same seed.
+	Seed,
+}
+
+impl Scope {
+	pub const ALL: [Scope; 3] = [Scope::All, Scope::Level, Scope::Seed];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Scope::All => "All runs",
+			Scope::Level => "This level",
+			Scope::Seed => "This seed",
+		}
+	}
+
+	pub fn filter(self, world: &world::Manager) -> Filter {
+		match self {
+			Scope::All => Filter::default(),
+			Scope::Level => Filter {
+				level: Some(world.level.name.clone()),
+				seed: None,
+			},
+			Scope::Seed => Filter {
+				level: None,
+				seed: Some(world.seed),
+			},
+		}
+	}
+
+	/// The next scope in `ALL`, wrapping around.
+	pub fn cycle(self, step: i32) -> Self {
+		let i = Self::ALL.iter().position(|x| *x == self).unwrap_or(0) as i32;
+		Self::ALL[(i + step).rem_euclid(Self::ALL.len() as i32) as usize]
+	}
+}
+
+/// The best runs played on this machine, highest score first.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Leaderboard {
+	pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OpenLeaderboardError {
+	#[error("{0}")]
+	Io(#[from] io::Error),
+	#[error("{0}")]
+	Toml(#[from] toml::de::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SaveLeaderboardError {
+	#[error("{0}")]
+	Io(#[from] io::Error),
+	#[error("{0}")]
+	Toml(#[from] toml::ser::Error),
+}
+
+impl Leaderboard {
+	/// # Errors
+	///
+	/// Fails if the file could not be opened or parsed.
+	pub fn open(path: impl AsRef<Path>) -> Result<Self, OpenLeaderboardError> {
+		Ok(toml::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	/// # Errors
+	///
+	/// Fails if the file could not be serialized or written.
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveLeaderboardError> {
+		fs::write(path, toml::to_string(self)?)?;
+		Ok(())
+	}
+
+	/// Add an entry, returning its rank (starting from 0) if it was good enough to be kept.
+	pub fn record(&mut self, entry: Entry) -> Option<usize> {
+		// A reloaded run keeps the place it already earned.
+		if let Some(rank) = self.entries.iter().position(|x| x.run == entry.run) {
+			return Some(rank);
+		}
+		let total = entry.score.total();
+		let rank = self
+			.entries
+			.iter()
+			.position(|x| x.score.total() < total)
+			.unwrap_or(self.entries.len());
+		if rank >= LEADERBOARD_CAPACITY {
+			return None;
+		}
+		self.entries.insert(rank, entry);
+		self.entries.truncate(LEADERBOARD_CAPACITY);
+		Some(rank)
+	}
+
+	pub fn filtered<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a Entry> {
+		self.entries.iter().filter(|entry| filter.shows(entry))
+	}
+}
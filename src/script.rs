@@ -1,5 +1,121 @@
 use crate::prelude::*;
+use std::collections::BTreeMap;
 use std::fs;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::{debug_span, warn};
+
+/// The version of the Lua API provided to scripts, exposed to them as `API_VERSION`.
+///
+/// This should be incremented whenever something scripts rely on is changed or deprecated.
+pub const API_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VersionError {
+	#[error("requires API version {0}, but only {API_VERSION} is supported")]
+	Unsupported(u32),
+	#[error("api_version must be a positive integer")]
+	Invalid,
+}
+
+/// Check a resource's declared `api_version`, removing it so that the rest can be deserialized.
+///
+/// Resources without one are assumed to target the current version.
+///
+/// # Errors
+///
+/// Returns an error if the resource requires a newer version of the API.
+pub fn check_api_version(resource: &mut toml::Table, name: &str) -> Result<(), VersionError> {
+	let Some(version) = resource.remove("api_version") else {
+		return Ok(());
+	};
+	let version = version
+		.as_integer()
+		.and_then(|x| u32::try_from(x).ok())
+		.ok_or(VersionError::Invalid)?;
+	if version > API_VERSION {
+		return Err(VersionError::Unsupported(version));
+	}
+	if version < API_VERSION {
+		warn!("{name} targets API version {version} (current is {API_VERSION}); it may not work as intended");
+	}
+	Ok(())
+}
+
+/// How long a script has spent running, across every call while profiling was enabled.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timing {
+	pub calls: u32,
+	pub total: Duration,
+	pub longest: Duration,
+}
+
+impl Timing {
+	pub fn average(&self) -> Duration {
+		self.total.checked_div(self.calls).unwrap_or_default()
+	}
+}
+
+struct Profiler {
+	enabled: bool,
+	timings: BTreeMap<String, Timing>,
+}
+
+// Some scripts (like debuffs) run in their own Lua state,
+// so timings are kept globally rather than as app data.
+static PROFILER: Mutex<Profiler> = Mutex::new(Profiler {
+	enabled: false,
+	timings: BTreeMap::new(),
+});
+
+fn profiler() -> std::sync::MutexGuard<'static, Profiler> {
+	PROFILER.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Start or stop recording script timings.
+/// Timings that have already been recorded are kept; see `reset_profile`.
+pub fn set_profiling(enabled: bool) {
+	profiler().enabled = enabled;
+}
+
+pub fn profiling() -> bool {
+	profiler().enabled
+}
+
+pub fn reset_profile() {
+	profiler().timings.clear();
+}
+
+/// Returns each script's timings, most expensive first.
+pub fn profile_report() -> Vec<(String, Timing)> {
+	let mut report: Vec<_> = profiler()
+		.timings
+		.iter()
+		.map(|(name, timing)| (name.clone(), *timing))
+		.collect();
+	report.sort_by_key(|(_, timing)| std::cmp::Reverse(timing.total));
+	report
+}
+
+/// Run a Lua entry point within a tracing span,
+/// recording how long it took under the given script name if profiling is enabled.
+///
+/// Scripts which yield are only timed until their first yield.
+pub fn timed<T>(name: &str, f: impl FnOnce() -> T) -> T {
+	let _span = debug_span!("script", name).entered();
+	if !profiling() {
+		return f();
+	}
+	let start = Instant::now();
+	let result = f();
+	let elapsed = start.elapsed();
+	let mut profiler = profiler();
+	let timing = profiler.timings.entry(name.into()).or_default();
+	timing.calls += 1;
+	timing.total += elapsed;
+	timing.longest = timing.longest.max(elapsed);
+	result
+}
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", content = "source")]
@@ -15,6 +131,45 @@ impl MaybeInline {
 			MaybeInline::Path(expression) => &expression.contents,
 		}
 	}
+
+	/// The name the script's chunk runs under; inline scripts are named after `owner`.
+	pub fn name(&self, owner: &str) -> String {
+		match self {
+			MaybeInline::Inline(_) => format!("{owner} (inline)"),
+			MaybeInline::Path(Script { path, contents: _ }) => path.clone(),
+		}
+	}
+
+	/// Load the script as a named chunk, using the state's globals as its environment.
+	pub fn load<'lua>(&self, lua: &'lua mlua::Lua, owner: &str) -> mlua::Chunk<'lua, '_> {
+		lua.load(self.contents())
+			.set_name(self.name(owner))
+			.set_environment(lua.globals())
+	}
+
+	/// Evaluate the script (see `load`), timing it with `timed`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the script fails or returns something that can't be converted to `R`.
+	pub fn eval<'lua, R: mlua::FromLuaMulti<'lua>>(
+		&self,
+		lua: &'lua mlua::Lua,
+		owner: &str,
+	) -> mlua::Result<R> {
+		let chunk = self.load(lua, owner);
+		timed(&self.name(owner), || chunk.eval())
+	}
+
+	/// Execute the script (see `load`), timing it with `timed`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the script fails.
+	pub fn exec(&self, lua: &mlua::Lua, owner: &str) -> mlua::Result<()> {
+		let chunk = self.load(lua, owner);
+		timed(&self.name(owner), || chunk.exec())
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -68,3 +223,74 @@ impl<'de> serde::Deserialize<'de> for Script {
 		Ok(Script { path, contents })
 	}
 }
+
+/// A Lua error, split into a one-line summary and the traceback leading up to it.
+#[derive(Clone, Debug)]
+pub struct Report {
+	pub summary: String,
+	pub traceback: Vec<String>,
+}
+
+impl Report {
+	pub fn new(error: &mlua::Error) -> Self {
+		let (message, traceback) = match error {
+			mlua::Error::CallbackError { traceback, cause } => {
+				let mut report = Self::new(cause);
+				report
+					.traceback
+					.extend(traceback.lines().skip(1).map(chunk_names));
+				return report;
+			}
+			mlua::Error::RuntimeError(message) => message
+				.split_once("\nstack traceback:")
+				.unwrap_or((message, "")),
+			mlua::Error::SyntaxError { message, .. } => (message.as_str(), ""),
+			mlua::Error::WithContext { context, cause } => {
+				let mut report = Self::new(cause);
+				report.summary = format!("{context}: {}", report.summary);
+				return report;
+			}
+			error => {
+				return Self {
+					summary: error.to_string(),
+					traceback: Vec::new(),
+				}
+			}
+		};
+		Self {
+			summary: chunk_names(message),
+			traceback: traceback
+				.lines()
+				.map(str::trim)
+				.filter(|x| !x.is_empty())
+				.map(chunk_names)
+				.collect(),
+		}
+	}
+}
+
+impl std::fmt::Display for Report {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.summary)?;
+		for line in &self.traceback {
+			write!(f, "\n\t{line}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Replace Lua's `[string "name"]` with just the chunk's name,
+/// which is usually the path to a script in the resource directory.
+fn chunk_names(line: &str) -> String {
+	let mut result = String::new();
+	let mut rest = line.trim();
+	while let Some(start) = rest.find("[string \"") {
+		let Some(end) = rest[start..].find("\"]") else {
+			break;
+		};
+		result += &rest[..start];
+		result += &rest[start + "[string \"".len()..start + end];
+		rest = &rest[start + end + "\"]".len()..];
+	}
+	result + rest
+}
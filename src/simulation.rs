@@ -0,0 +1,149 @@
+//! Headless play, for measuring engine performance without anyone at the controls.
+//!
+//! Player-controlled pieces are driven by a stand-in that picks random valid moves,
+//! and everything else is left to the AI.
+
+use crate::prelude::*;
+use rand::seq::SliceRandom;
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// One world being simulated, along with the Lua state its scripts run in.
+pub struct Instance {
+	pub world: world::Manager,
+	pub lua: mlua::Lua,
+	/// How many turns this instance has taken.
+	pub ticks: u64,
+	/// Set once the party is defeated; the instance stops ticking afterwards.
+	pub finished: bool,
+}
+
+impl Instance {
+	pub fn new(world: world::Manager, lua: mlua::Lua) -> Self {
+		Self {
+			world,
+			lua,
+			ticks: 0,
+			finished: false,
+		}
+	}
+
+	/// Advance the world by one update, acting on behalf of the player if necessary.
+	fn tick(&mut self, rng: &mut impl Rng) {
+		let next_character = self.world.next_character().clone();
+		let needs_action = {
+			let piece = next_character.borrow();
+			piece.controlled_by_player() && piece.next_action.is_none()
+		};
+		if needs_action {
+			let action = random_action(&self.world, &next_character, rng);
+			next_character.borrow_mut().next_action = Some(action);
+		}
+
+		// Anything that would wait on player input (like a cursor) is cancelled straight away.
+		let mut input_mode = input::Mode::Normal;
+		if let Err(msg) = self.world.update(None, &self.lua, &mut input_mode) {
+			self.world.report_script_error(&msg);
+		}
+		if let Err(msg) = self.world.remove_dead(&self.lua) {
+			self.world.report_script_error(&msg);
+		}
+		self.world.events.clear();
+		self.ticks += 1;
+		self.finished = self.world.victorious || self.world.party_defeated();
+	}
+}
+
+/// Pick a direction that either moves onto an open tile or attacks an enemy.
+fn random_action(
+	world: &world::Manager,
+	character: &world::CharacterRef,
+	rng: &mut impl Rng,
+) -> character::Action {
+	let (x, y, alliance) = {
+		let piece = character.borrow();
+		(piece.x, piece.y, piece.effective_alliance())
+	};
+	let valid: Vec<_> = character::OrdDir::ALL
+		.into_iter()
+		.filter(|dir| {
+			let (xoff, yoff) = dir.as_offset();
+			let (x, y) = (x + xoff, y + yoff);
+			match world.get_character_at(x, y) {
+				Some(other) => other.borrow().effective_alliance() != alliance,
+				None => world
+					.current_floor
+					.map
+					.get(y, x)
+					.is_some_and(|tile| tile.is_passable()),
+			}
+		})
+		.collect();
+	match valid.choose(rng) {
+		Some(dir) => character::Action::Move(*dir),
+		None => character::Action::Wait(TURN),
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+	pub instances: usize,
+	pub ticks: u64,
+	pub elapsed: Duration,
+	/// Resident memory at the end of the simulation, if the platform reports it.
+	pub resident_bytes: Option<u64>,
+}
+
+impl Report {
+	pub fn ticks_per_second(&self) -> f64 {
+		self.ticks as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+	}
+}
+
+impl std::fmt::Display for Report {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{} ticks across {} instances in {:.2?} ({:.0} ticks per second)",
+			self.ticks,
+			self.instances,
+			self.elapsed,
+			self.ticks_per_second()
+		)?;
+		if let Some(bytes) = self.resident_bytes {
+			write!(f, ", {} KiB resident", bytes / 1024)?;
+		}
+		Ok(())
+	}
+}
+
+/// Tick every instance in turn until each has taken `ticks` turns or had its party defeated.
+pub fn run(instances: &mut [Instance], ticks: u64) -> Report {
+	let mut rng = rand::thread_rng();
+	let start = Instant::now();
+	while instances
+		.iter()
+		.any(|instance| !instance.finished && instance.ticks < ticks)
+	{
+		for instance in instances
+			.iter_mut()
+			.filter(|instance| !instance.finished && instance.ticks < ticks)
+		{
+			instance.tick(&mut rng);
+		}
+	}
+	Report {
+		instances: instances.len(),
+		ticks: instances.iter().map(|instance| instance.ticks).sum(),
+		elapsed: start.elapsed(),
+		resident_bytes: resident_bytes(),
+	}
+}
+
+/// Only Linux is supported, through `/proc`.
+fn resident_bytes() -> Option<u64> {
+	const PAGE_SIZE: u64 = 4096;
+	let statm = fs::read_to_string("/proc/self/statm").ok()?;
+	let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+	Some(pages * PAGE_SIZE)
+}
@@ -0,0 +1,145 @@
+//! Finding pieces by position without scanning every character.
+
+use crate::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub type Handle = Rc<RefCell<Index>>;
+
+/// Which pieces are standing on each tile.
+///
+/// Positions may be changed without the index knowing (Lua scripts can set them directly),
+/// so every lookup is checked against the piece's actual position,
+/// and `world::Manager` rebuilds the index whenever it finds a piece out of place.
+#[derive(Clone, Debug, Default)]
+pub struct Index {
+	tiles: HashMap<(i32, i32), Vec<world::CharacterRef>>,
+}
+
+impl Index {
+	pub fn clear(&mut self) {
+		self.tiles.clear();
+	}
+
+	pub fn rebuild<'a>(&mut self, characters: impl IntoIterator<Item = &'a world::CharacterRef>) {
+		self.clear();
+		for character in characters {
+			self.insert(character);
+		}
+	}
+
+	/// Whether any of `characters` has been moved, added, or removed without the index knowing.
+	pub fn is_stale(&self, characters: &[world::CharacterRef]) -> bool {
+		self.tiles.values().map(Vec::len).sum::<usize>() != characters.len()
+			|| characters.iter().any(|character| {
+				let piece = character.borrow();
+				!self
+					.tiles
+					.get(&(piece.x, piece.y))
+					.is_some_and(|pieces| pieces.iter().any(|x| Rc::ptr_eq(x, character)))
+			})
+	}
+
+	/// Add a piece at its current position, if it isn't already there.
+	pub fn insert(&mut self, character: &world::CharacterRef) {
+		let position = {
+			let piece = character.borrow();
+			(piece.x, piece.y)
+		};
+		let pieces = self.tiles.entry(position).or_default();
+		if !pieces.iter().any(|x| Rc::ptr_eq(x, character)) {
+			pieces.push(character.clone());
+		}
+	}
+
+	pub fn remove(&mut self, character: &world::CharacterRef) {
+		for pieces in self.tiles.values_mut() {
+			pieces.retain(|x| !Rc::ptr_eq(x, character));
+		}
+		self.tiles.retain(|_, pieces| !pieces.is_empty());
+	}
+
+	/// Move a piece's entry from `from` to its current position.
+	pub fn relocate(&mut self, character: &world::CharacterRef, from: (i32, i32)) {
+		if let Some(pieces) = self.tiles.get_mut(&from) {
+			pieces.retain(|x| !Rc::ptr_eq(x, character));
+			if pieces.is_empty() {
+				self.tiles.remove(&from);
+			}
+		}
+		self.insert(character);
+	}
+
+	pub fn pieces_at(&self, x: i32, y: i32) -> impl Iterator<Item = &world::CharacterRef> {
+		self.tiles
+			.get(&(x, y))
+			.into_iter()
+			.flatten()
+			.filter(move |piece| {
+				let piece = piece.borrow();
+				piece.x == x && piece.y == y
+			})
+	}
+
+	/// Every piece within `radius` tiles (diagonals count as one tile), nearest first.
+	pub fn pieces_in_radius(&self, x: i32, y: i32, radius: u32) -> Vec<world::CharacterRef> {
+		let distance = |piece: &world::CharacterRef| {
+			let piece = piece.borrow();
+			piece.x.abs_diff(x).max(piece.y.abs_diff(y))
+		};
+		let mut pieces: Vec<_> = if (radius as usize * 2 + 1).pow(2) < self.tiles.len() {
+			let radius = radius as i32;
+			(-radius..=radius)
+				.flat_map(|yoff| (-radius..=radius).map(move |xoff| (x + xoff, y + yoff)))
+				.flat_map(|(x, y)| self.pieces_at(x, y))
+				.cloned()
+				.collect()
+		} else {
+			// Scanning every occupied tile is cheaper than scanning a large area.
+			self.tiles
+				.iter()
+				.flat_map(|(&(x, y), _)| self.pieces_at(x, y))
+				.filter(|piece| distance(piece) <= radius)
+				.cloned()
+				.collect()
+		};
+		pieces.sort_by_key(distance);
+		pieces
+	}
+
+	/// The closest piece within `radius` tiles which isn't allied with `character`.
+	pub fn nearest_hostile(
+		&self,
+		character: &world::CharacterRef,
+		radius: u32,
+	) -> Option<world::CharacterRef> {
+		let (x, y, alliance) = {
+			let piece = character.borrow();
+			(piece.x, piece.y, piece.effective_alliance())
+		};
+		self.pieces_in_radius(x, y, radius)
+			.into_iter()
+			.find(|piece| {
+				!Rc::ptr_eq(piece, character) && piece.borrow().effective_alliance() != alliance
+			})
+	}
+}
+
+impl mlua::UserData for Index {
+	fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+		methods.add_method("pieces_at", |_, this, (x, y): (i32, i32)| {
+			Ok(this.pieces_at(x, y).cloned().collect::<Vec<_>>())
+		});
+		methods.add_method(
+			"pieces_in_radius",
+			|_, this, (x, y, radius): (i32, i32, u32)| Ok(this.pieces_in_radius(x, y, radius)),
+		);
+		methods.add_method(
+			"nearest_hostile",
+			|_, this, (character, radius): (mlua::UserDataRef<world::CharacterRef>, u32)| {
+				Ok(this.nearest_hostile(&character, radius))
+			},
+		);
+	}
+}
@@ -0,0 +1,72 @@
+use crate::prelude::*;
+
+/// A single kind of character which may be spawned, and when.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+	pub sheet: String,
+	/// The shallowest floor this entry may spawn on.
+	#[serde(default)]
+	pub min_depth: usize,
+	/// The deepest floor this entry may spawn on.
+	#[serde(default)]
+	pub max_depth: Option<usize>,
+	/// Relative chance of this entry being picked over others in the same table.
+	#[serde(default = "default_weight")]
+	pub weight: u32,
+	/// How many characters spawn together, as an inclusive range.
+	#[serde(default = "default_pack")]
+	pub pack: (u32, u32),
+}
+
+fn default_weight() -> u32 {
+	1
+}
+
+fn default_pack() -> (u32, u32) {
+	(1, 1)
+}
+
+impl Entry {
+	pub fn spawns_at(&self, depth: usize) -> bool {
+		self.min_depth <= depth && !self.max_depth.is_some_and(|max| depth > max)
+	}
+}
+
+/// Populates the parts of a floor that vaults don't cover.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Table {
+	/// How many packs should be spawned on each floor.
+	pub packs: u32,
+	pub entries: Vec<Entry>,
+}
+
+impl Table {
+	/// Pick which packs should spawn on a floor at the given depth.
+	///
+	/// Each pack is a sheet name and how many of it to spawn.
+	pub fn choose(&self, depth: usize, rng: &mut dyn rand::RngCore) -> Vec<(&str, u32)> {
+		let candidates: Vec<&Entry> = self
+			.entries
+			.iter()
+			.filter(|entry| entry.spawns_at(depth) && entry.weight > 0)
+			.collect();
+		let total: u32 = candidates.iter().map(|entry| entry.weight).sum();
+		if total == 0 {
+			return Vec::new();
+		}
+
+		let mut packs = Vec::new();
+		for _ in 0..self.packs {
+			let mut roll = rng.gen_range(0..total);
+			for entry in &candidates {
+				if roll < entry.weight {
+					let (min, max) = entry.pack;
+					packs.push((entry.sheet.as_str(), rng.gen_range(min..=max.max(min))));
+					break;
+				}
+				roll -= entry.weight;
+			}
+		}
+		packs
+	}
+}
@@ -33,6 +33,54 @@ pub enum Skillset {
 	},
 }
 
+/// A skill from either axis.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Skill {
+	Energy(Energy),
+	Harmony(Harmony),
+}
+
+impl std::fmt::Display for Skill {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Skill::Energy(energy) => write!(f, "{energy:?}"),
+			Skill::Harmony(harmony) => write!(f, "{harmony:?}"),
+		}
+	}
+}
+
+impl Skillset {
+	/// Grant a minor skill, if there isn't one yet and it's on the opposite axis of the major skill.
+	///
+	/// Returns false if nothing changed.
+	pub fn unlock_minor(&mut self, skill: Skill) -> bool {
+		match (self, skill) {
+			(
+				Skillset::EnergyMajor {
+					minor: minor @ None,
+					..
+				},
+				Skill::Harmony(harmony),
+			) => {
+				*minor = Some(harmony);
+				true
+			}
+			(
+				Skillset::HarmonyMajor {
+					minor: minor @ None,
+					..
+				},
+				Skill::Energy(energy),
+			) => {
+				*minor = Some(energy);
+				true
+			}
+			_ => false,
+		}
+	}
+}
+
 #[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Affinity {
 	/// No skillset matches; the spell is not castable.
@@ -66,6 +114,27 @@ impl mlua::UserData for Affinity {
 	}
 }
 
+/// How well a piece can cast a spell, and what it's likely to do.
+#[derive(Clone, Debug, alua::UserData)]
+pub struct Outlook {
+	/// Whether the caster has the skill and SP (or HP, if overcasting is allowed) to cast the spell right now.
+	#[alua(get)]
+	pub castable: bool,
+	#[alua(get)]
+	pub affinity: Affinity,
+	/// Likelihood, from 0 to 1, that the spell will have no effect on the target.
+	///
+	/// Without a target, this only accounts for whether the spell is castable.
+	#[alua(get)]
+	pub failure_chance: f64,
+	/// The lowest and highest magnitudes the spell might have, after affinity.
+	/// Against a target, this is after the target's resistance and the spell's pierce threshold.
+	#[alua(get)]
+	pub min_magnitude: Option<u32>,
+	#[alua(get)]
+	pub max_magnitude: Option<u32>,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Spell {
 	pub name: String,
@@ -82,6 +151,14 @@ pub struct Spell {
 	pub parameters: Parameters,
 	/// Script to execute upon casting the spell.
 	pub on_cast: script::MaybeInline,
+	/// Played when the spell is cast.
+	#[serde(default)]
+	pub sound: Option<String>,
+	/// Whether the spell is meant for allies (healing, buffs) rather than hostile pieces.
+	///
+	/// AI pieces cast these at their most wounded ally.
+	#[serde(default)]
+	pub beneficial: bool,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -101,13 +178,64 @@ pub enum Parameters {
 		/// A pierce threshold of -2 reduces the enemy's resistance by 2.
 		#[serde(default)]
 		pierce_threshold: i32,
+		/// How many tiles away a target may be, if there's a limit.
+		#[serde(default)]
+		range: Option<u32>,
 	},
 }
 
 impl Spell {
-	pub fn castable_by(&self, character: &character::Piece) -> bool {
+	/// Whether `character` can pay for the spell, overcasting at `overcast_cost` HP per missing SP.
+	pub fn castable_by(&self, character: &character::Piece, overcast_cost: u32) -> bool {
 		// if this ever changes, a result should be returned instead to print more detailed messages.
-		character.sp >= self.level as i32
+		character.can_afford(self.level.into(), overcast_cost)
+	}
+
+	/// Predict the outcome of `caster` casting this spell, optionally at `target`.
+	pub fn outlook(
+		&self,
+		caster: &character::Piece,
+		target: Option<&character::Piece>,
+		overcast_cost: u32,
+	) -> Outlook {
+		let affinity = self.affinity(caster);
+		let castable =
+			self.castable_by(caster, overcast_cost) && !matches!(affinity, Affinity::Uncastable);
+		let Parameters::Target {
+			magnitude,
+			pierce_threshold,
+			..
+		} = &self.parameters;
+		// Spell magnitudes don't (yet) have any randomness, so the range is a single value.
+		let magnitude = magnitude
+			.as_ref()
+			.map(|x| affinity.magnitude(u32::evalv(x, caster)));
+		let magnitude = match (magnitude, target) {
+			(Some(magnitude), Some(target)) => {
+				// Mirrors `apply_damage_with_pierce` in `combat.lua`.
+				let damage = (magnitude as i32 - target.stats().resistance as i32
+					+ pierce_threshold.min(&0))
+				.max(0);
+				Some(if damage <= *pierce_threshold {
+					0
+				} else {
+					damage as u32
+				})
+			}
+			(magnitude, _) => magnitude,
+		};
+		let failure_chance = if !castable || (target.is_some() && magnitude == Some(0)) {
+			1.0
+		} else {
+			0.0
+		};
+		Outlook {
+			castable,
+			affinity,
+			failure_chance,
+			min_magnitude: magnitude,
+			max_magnitude: magnitude,
+		}
 	}
 
 	pub fn affinity(&self, character: &character::Piece) -> Affinity {
@@ -8,6 +8,8 @@ use tracing::{error, warn};
 pub enum Duration {
 	Rest,
 	Turn,
+	/// Lasts for the given number of the afflicted piece's turns.
+	Turns(u32),
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -25,7 +27,7 @@ impl Debuff {
 		// TODO: OnceCell
 		let lua = mlua::Lua::new();
 		lua.globals().set("magnitude", self.magnitude)?;
-		let stats = lua.from_value(lua.load(self.on_debuff.contents()).eval()?)?;
+		let stats = lua.from_value(self.on_debuff.eval(&lua, "debuff")?)?;
 		Ok(stats)
 	}
 
@@ -44,17 +46,87 @@ impl Debuff {
 	}
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 enum Effect {
 	StaticDebuff(character::Stats),
 	Debuff(Debuff),
+	/// Turns the afflicted piece against its allies.
+	Charm,
+	/// No built-in effect; everything the status does comes from its hooks.
+	#[default]
+	Scripted,
+}
+
+/// Points in a status's life at which a script may run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Hook {
+	/// The status was inflicted on a piece that didn't already have it.
+	Inflict,
+	/// The afflicted piece began a turn.
+	Turn,
+	/// The status ran out.
+	Expire,
+	/// The status was removed for any reason, including expiring.
+	Remove,
+}
+
+impl std::fmt::Display for Hook {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Hook::Inflict => write!(f, "on_inflict"),
+			Hook::Turn => write!(f, "on_turn"),
+			Hook::Expire => write!(f, "on_expire"),
+			Hook::Remove => write!(f, "on_remove"),
+		}
+	}
+}
+
+/// Scripts run with `piece` set to the afflicted piece,
+/// and `state` set to a table which is saved along with the status.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+	pub on_inflict: Option<script::MaybeInline>,
+	pub on_turn: Option<script::MaybeInline>,
+	pub on_expire: Option<script::MaybeInline>,
+	pub on_remove: Option<script::MaybeInline>,
+}
+
+impl Hooks {
+	fn get(&self, hook: Hook) -> Option<&script::MaybeInline> {
+		match hook {
+			Hook::Inflict => self.on_inflict.as_ref(),
+			Hook::Turn => self.on_turn.as_ref(),
+			Hook::Expire => self.on_expire.as_ref(),
+			Hook::Remove => self.on_remove.as_ref(),
+		}
+	}
+}
+
+/// A hook waiting to be run by `world::Manager`,
+/// since pieces don't have access to Lua when their statuses change.
+#[derive(Clone, Debug)]
+pub enum Pending {
+	/// A hook for a status which is still afflicting the piece, by key.
+	Active(Box<str>, Hook),
+	/// A hook for a status which has already been removed.
+	Removed(Box<Status>, Hook),
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Status {
 	pub name: String,
+	/// Drawn above afflicted pieces.
+	#[serde(default)]
+	pub icon: Option<String>,
 	pub duration: Duration,
+	#[serde(default)]
 	effect: Effect,
+	#[serde(default)]
+	pub hooks: Hooks,
+	/// Persistent state for the status's hooks.
+	#[serde(default)]
+	pub state: toml::Table,
 }
 
 impl Status {
@@ -63,7 +135,7 @@ impl Status {
 			Effect::Debuff(Debuff { magnitude, .. }) => {
 				*magnitude = magnitude.saturating_add(amount)
 			}
-			Effect::StaticDebuff(_) => {
+			Effect::StaticDebuff(_) | Effect::Charm | Effect::Scripted => {
 				warn!(
 					"attempted to increase the magnitude of \"{}\" but it had none",
 					self.name
@@ -76,9 +148,14 @@ impl Status {
 		match &self.effect {
 			Effect::Debuff(debuff) => debuff.get(),
 			Effect::StaticDebuff(debuff) => Some(*debuff),
+			Effect::Charm | Effect::Scripted => None,
 		}
 	}
 
+	pub fn charms(&self) -> bool {
+		matches!(self.effect, Effect::Charm)
+	}
+
 	pub fn tip(&self) -> String {
 		use std::fmt::Write;
 
@@ -106,6 +183,11 @@ impl Status {
 				}
 			}
 			Effect::StaticDebuff(stats) => print_stats(&mut tip, stats),
+			Effect::Charm | Effect::Scripted => {}
+		}
+
+		if let Duration::Turns(turns) = self.duration {
+			let _ = write!(tip, " ({turns})");
 		}
 
 		tip
@@ -114,8 +196,66 @@ impl Status {
 	pub fn color(&self) -> (u8, u8, u8, u8) {
 		match &self.effect {
 			Effect::Debuff(_) | Effect::StaticDebuff(_) => (255, 0, 0, 255),
+			Effect::Charm => (255, 128, 255, 255),
+			Effect::Scripted => (255, 192, 0, 255),
+		}
+	}
+
+	pub fn has_hook(&self, hook: Hook) -> bool {
+		self.hooks.get(hook).is_some()
+	}
+
+	fn magnitude(&self) -> Option<u32> {
+		match &self.effect {
+			Effect::Debuff(debuff) => Some(debuff.magnitude),
+			Effect::StaticDebuff(_) | Effect::Charm | Effect::Scripted => None,
 		}
 	}
+
+	/// Run one of this status's hooks, keeping any changes it makes to `state`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the script fails, or if it leaves `state` as something other than a table.
+	pub fn run_hook(
+		&mut self,
+		lua: &mlua::Lua,
+		hook: Hook,
+		piece: &world::CharacterRef,
+	) -> mlua::Result<()> {
+		let Some(script) = self.hooks.get(hook) else {
+			return Ok(());
+		};
+		let globals = lua.globals();
+		globals.set("piece", piece.clone())?;
+		globals.set("magnitude", self.magnitude())?;
+		globals.set("state", lua.to_value(&self.state)?)?;
+		script.exec(lua, &format!("{} {hook}", self.name))?;
+		self.state = match globals.get::<_, mlua::Value>("state")? {
+			mlua::Value::Nil => toml::Table::new(),
+			state => lua.from_value(state)?,
+		};
+		Ok(())
+	}
 }
 
 impl mlua::UserData for Status {}
+
+/// Available to expressions as `<status>.<variable>`; see `character::Piece`'s implementation.
+impl expression::Variables for Status {
+	fn get(&self, s: &str) -> Result<expression::Integer, expression::Error> {
+		match s {
+			"magnitude" => Ok(self.magnitude().unwrap_or(0).into()),
+			"turns" => match self.duration {
+				Duration::Turns(turns) => Ok(turns.into()),
+				Duration::Rest | Duration::Turn => Ok(0),
+			},
+			// Integers in a scripted status's state can be used too.
+			_ => self
+				.state
+				.get(s)
+				.and_then(toml::Value::as_integer)
+				.ok_or_else(|| expression::Error::MissingVariable(s.into())),
+		}
+	}
+}
@@ -18,7 +18,9 @@ impl<'ttf_module, 'rwops> Typography<'ttf_module, 'rwops> {
 	/// Returns an error if the font file could not be read.
 	pub fn new(options: &Options, ttf_context: &'ttf_module sdl2::ttf::Sdl2TtfContext) -> Self {
 		let point_size = options.font_size;
-		let annotation_size = options.font_size.saturating_sub(2);
+		let annotation_size = options
+			.annotation_size
+			.unwrap_or(options.font_size.saturating_sub(2));
 		let title_size = options.font_size.saturating_add(2);
 
 		let default_font_bytes = include_bytes!("res/FantasqueSansMNerdFontPropo-Regular.ttf");
@@ -46,11 +48,16 @@ impl<'ttf_module, 'rwops> Typography<'ttf_module, 'rwops> {
 	}
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Options {
-	font: Option<PathBuf>,
-	font_size: u16,
-	font_color: Color,
+	pub font: Option<PathBuf>,
+	/// Point size of most text; titles are drawn slightly larger.
+	pub font_size: u16,
+	/// Point size of annotations, such as stat labels.
+	/// Defaults to slightly smaller than `font_size`.
+	#[serde(default)]
+	pub annotation_size: Option<u16>,
+	pub font_color: Color,
 }
 
 impl Default for Options {
@@ -58,6 +65,7 @@ impl Default for Options {
 		Self {
 			font: None,
 			font_size: 18,
+			annotation_size: None,
 			font_color: (255, 255, 255, 255),
 		}
 	}
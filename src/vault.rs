@@ -1,5 +1,6 @@
 use crate::floor::Tile;
 use crate::prelude::*;
+use rand::seq::IteratorRandom;
 use std::{collections::HashMap, fs, path::Path};
 
 #[derive(Clone, Debug)]
@@ -8,6 +9,149 @@ pub struct Vault {
 	pub width: usize,
 
 	pub characters: Vec<(i32, i32, String)>,
+	pub placement: Placement,
+}
+
+/// Rules for how often (and where) a vault may appear.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Placement {
+	/// Relative chance of this vault being picked over others in the same set.
+	pub weight: u32,
+	/// This many copies are always placed, regardless of weight.
+	pub min: u32,
+	/// Never place more than this many copies on one floor.
+	pub max: Option<u32>,
+	/// The shallowest floor this vault may appear on.
+	pub min_depth: usize,
+	/// Vaults sharing any of these tags will never be placed on the same floor.
+	pub exclusive_tags: Vec<String>,
+}
+
+impl Default for Placement {
+	fn default() -> Self {
+		Self {
+			weight: 1,
+			min: 0,
+			max: None,
+			min_depth: 0,
+			exclusive_tags: Vec::new(),
+		}
+	}
+}
+
+/// A collection of vaults to be placed on a floor, and how to lay out the rest of it.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Set {
+	pub vaults: Vec<String>,
+	/// How many vaults should be placed on each floor.
+	pub density: u32,
+	#[serde(default)]
+	pub generator: mapgen::Generator,
+	#[serde(default)]
+	pub connectivity: mapgen::Connectivity,
+	/// Which spawn table to populate the rest of the floor from, if any.
+	#[serde(default)]
+	pub spawns: Option<String>,
+	#[serde(default)]
+	pub boss: Option<BossPlacement>,
+}
+
+/// Guarantees a boss vault at regular intervals.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BossPlacement {
+	/// One of these is placed on each boss floor.
+	pub vaults: Vec<String>,
+	/// Every floor divisible by this is a boss floor.
+	pub every: usize,
+}
+
+impl Set {
+	/// Pick which vaults should be placed on a floor at the given depth.
+	///
+	/// On boss floors, a boss vault is always placed first and doesn't count towards density.
+	/// Vaults with a minimum count are placed next,
+	/// and the rest are chosen by weight until the set's density is reached.
+	///
+	/// # Errors
+	///
+	/// Returns an error if any of the set's vaults could not be found.
+	pub fn choose<'resources>(
+		&self,
+		resources: &'resources resource::Manager,
+		depth: usize,
+		rng: &mut dyn rand::RngCore,
+	) -> Result<Vec<&'resources Vault>> {
+		let mut candidates = Vec::new();
+		for name in &self.vaults {
+			let vault = resources.get_vault(name)?;
+			if vault.placement.min_depth <= depth {
+				candidates.push((vault, 0));
+			}
+		}
+
+		let mut tags: Vec<&str> = Vec::new();
+		let mut chosen = Vec::new();
+		// Vaults are only excluded by the tags of *other* vaults;
+		// repeating a vault is limited by `max` instead.
+		let allowed = |vault: &Vault, count: u32, tags: &[&str]| {
+			!vault.placement.max.is_some_and(|max| count >= max)
+				&& (count > 0
+					|| !vault
+						.placement
+						.exclusive_tags
+						.iter()
+						.any(|tag| tags.contains(&tag.as_str())))
+		};
+
+		for (vault, count) in &mut candidates {
+			for _ in 0..vault.placement.min {
+				if allowed(vault, *count, &tags) {
+					*count += 1;
+					tags.extend(vault.placement.exclusive_tags.iter().map(String::as_str));
+					chosen.push(*vault);
+				}
+			}
+		}
+
+		while (chosen.len() as u32) < self.density {
+			let total: u32 = candidates
+				.iter()
+				.filter(|(vault, count)| allowed(vault, *count, &tags))
+				.map(|(vault, _)| vault.placement.weight)
+				.sum();
+			if total == 0 {
+				break;
+			}
+			let mut roll = rng.gen_range(0..total);
+			let Some((vault, count)) = candidates.iter_mut().find(|(vault, count)| {
+				if !allowed(vault, *count, &tags) {
+					return false;
+				}
+				if roll < vault.placement.weight {
+					true
+				} else {
+					roll -= vault.placement.weight;
+					false
+				}
+			}) else {
+				break;
+			};
+			*count += 1;
+			tags.extend(vault.placement.exclusive_tags.iter().map(String::as_str));
+			chosen.push(*vault);
+		}
+
+		if let Some(boss) = &self.boss {
+			if depth.is_multiple_of(boss.every) {
+				if let Some(name) = boss.vaults.iter().choose(rng) {
+					chosen.insert(0, resources.get_vault(name)?);
+				}
+			}
+		}
+
+		Ok(chosen)
+	}
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -18,6 +162,9 @@ pub enum SymbolMeaning {
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Metadata {
+	#[serde(flatten)]
+	placement: Placement,
+	#[serde(default)]
 	symbols: HashMap<char, SymbolMeaning>,
 }
 
@@ -69,6 +216,7 @@ impl Vault {
 						'.' => Some(Tile::Floor),
 						'x' => Some(Tile::Wall),
 						'>' => Some(Tile::Exit),
+						'*' => Some(Tile::Rest),
 						_ => Err(Error::UnexpectedSymbol(c))?,
 					});
 				}
@@ -82,6 +230,7 @@ impl Vault {
 			tiles,
 			width,
 			characters,
+			placement: metadata.placement,
 		})
 	}
 }
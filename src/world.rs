@@ -1,28 +1,310 @@
 use crate::character::OrdDir;
+use crate::mapgen::Generate;
 use crate::nouns::StrExt;
 use crate::prelude::*;
+use grid::Grid;
 use mlua::LuaSerdeExt;
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
+use rand::SeedableRng;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
-use tracing::error;
+use tracing::{debug_span, error, info_span, warn};
+use uuid::Uuid;
 
 pub type CharacterRef = Rc<RefCell<character::Piece>>;
 
+/// How many times a floor may be thrown away for being disconnected.
+const MAX_GENERATION_ATTEMPTS: u32 = 10;
+/// Spawn tables won't place anything this close to where the party arrives.
+const SPAWN_SAFE_DISTANCE: u32 = 5;
+/// How many supplies a new party starts with.
+const STARTING_SUPPLIES: u32 = 10;
+/// How far from a resting party ambushers may appear.
+const AMBUSH_RANGE: i32 = 4;
+/// How close a hostile piece must be to interrupt walking to a chase point.
+const CHASE_INTERRUPT_RANGE: u32 = 2;
+/// How far away the party can make out tiles, for the sake of exploring.
+const VISION_RANGE: i32 = 8;
+
+fn unexplored() -> Grid<bool> {
+	Grid::new(0, 0)
+}
+
+/// Seeds are kept within `i64`'s range, since that's the largest integer TOML can store.
+fn random_seed() -> u64 {
+	rand::thread_rng().gen::<u64>() >> 1
+}
+
+/// How a party member's defeat is described in `Statistics::cause_of_death`.
+fn defeat_message(piece: &character::Piece) -> String {
+	let name = &piece.sheet.nouns.name;
+	match &piece.defeated_by {
+		Some(attacker) => format!("{name} was defeated by {attacker}"),
+		None => format!("{name} was defeated"),
+	}
+}
+
+/// Whether `count` has grown past what was `seen`.
+///
+/// Things which were already in view (or have since left it) don't count,
+/// but a fresh one does, even if another has left since.
+fn appeared(seen: &mut Option<usize>, count: usize) -> bool {
+	let seen = seen.get_or_insert(count);
+	*seen = (*seen).min(count);
+	count > *seen
+}
+
+/// An action whose turn doesn't end until the player picks (or gives up on picking) a target.
+#[derive(Debug)]
+struct Aiming {
+	actor: CharacterRef,
+	/// Remembered so that whatever target the player picks can be recorded.
+	spell: Option<Rc<Spell>>,
+	delay: Aut,
+}
+
 /// This struct contains all information that is relevant during gameplay.
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Manager {
 	/// Where in the world the characters are.
 	pub location: Location,
+	pub level: Level,
+	/// The biome of the current floor.
+	#[serde(default)]
+	pub biome: String,
 	pub current_floor: Floor,
 	// It might be useful to sort this by remaining action delay to make selecting the next character easier.
 	pub characters: Vec<CharacterRef>,
+	/// Where each of `characters` is standing.
+	#[serde(skip)]
+	pub pieces: spatial::Handle,
+	/// Shared with scripts as the `World` global.
+	#[serde(skip)]
+	pub query: query::Query,
 	pub items: Vec<item::Piece>,
+	#[serde(default)]
+	pub corpses: Vec<Corpse>,
 	/// Always point to the party's pieces, even across floors.
 	/// When exiting a dungeon, these sheets will be saved to a party struct.
 	pub party: Vec<PartyReference>,
 	pub inventory: Vec<String>,
 	#[serde(skip)]
 	pub console: Console,
+	#[serde(skip)]
+	pub audio: audio::Handle,
+	/// Describes how the current floor was generated.
+	#[serde(skip)]
+	pub generation_log: mapgen::Log,
+	/// What may spawn on the current floor, such as when ambushing a resting party.
+	#[serde(default)]
+	pub spawns: Option<spawn::Table>,
+	/// Only consumed when `Attrition` is enabled.
+	#[serde(default)]
+	pub supplies: u32,
+	/// Time passed since a supply was last consumed.
+	#[serde(default)]
+	pub hunger: Aut,
+	#[serde(default)]
+	pub stats: morgue::Statistics,
+	#[serde(default)]
+	pub quests: Vec<quest::Active>,
+	/// While present, turns are paused until the cutscene ends.
+	#[serde(skip)]
+	pub cutscene: Option<cutscene::Cutscene>,
+	/// Set by cutscenes; rendering should be offset while this is active.
+	#[serde(skip)]
+	pub shake: Option<cutscene::Shake>,
+	/// Which tiles of the current floor the party has seen.
+	#[serde(default = "unexplored")]
+	pub explored: Grid<bool>,
+	/// An action waiting on the player to pick its target.
+	#[serde(skip)]
+	aiming: Option<Aiming>,
+	/// Index of the party member whose camera is in use.
+	#[serde(skip)]
+	pub focus: usize,
+	/// While present, turns are paused until the dialogue ends.
+	#[serde(skip)]
+	pub dialogue: Option<dialogue::State>,
+	/// Callbacks waiting for time to pass, soonest first.
+	#[serde(default)]
+	pub timers: Vec<schedule::Timer>,
+	#[serde(skip)]
+	pub scheduler: schedule::Scheduler,
+	/// Notable things that have happened since the last time this was drained.
+	#[serde(skip)]
+	pub events: Vec<Event>,
+	/// Every floor of the run is generated from this,
+	/// so runs with the same seed have the same layouts.
+	#[serde(default = "random_seed")]
+	pub seed: u64,
+	/// Identifies this run across saves,
+	/// so that reloading a finished run can't bank or score it a second time.
+	#[serde(default = "Uuid::new_v4")]
+	pub run: Uuid,
+	/// Set once the party leaves the level's final floor.
+	#[serde(default)]
+	pub victorious: bool,
+	#[serde(default)]
+	pub rules: Rules,
+}
+
+/// Why an action can't be performed.
+///
+/// Messages contain noun tokens for the acting piece; see `nouns::StrExt`.
+#[derive(Debug, thiserror::Error)]
+pub enum ActionError {
+	#[error("It isn't {{Address's}} turn.")]
+	WrongTurn,
+	#[error("{{Address}} doesn't have enough SP to cast {0}.")]
+	NotEnoughSp(String),
+	#[error("{{Address}} can't move there.")]
+	Blocked,
+	#[error("You cannot attack your allies.")]
+	AttackingAlly,
+	#[error("You can only delay your turn for an ally.")]
+	NotAnAlly,
+	#[error("There is no item in slot {0}.")]
+	NoSuchItem(usize),
+	#[error("There's nothing on the ground here.")]
+	NothingHere,
+	#[error("You can only rest at a rest site.")]
+	NotRestSite,
+}
+
+/// A copy of a world's state, which can be returned to with `Manager::restore`.
+///
+/// Taking a snapshot copies everything, including the floor and every piece.
+/// Snapshots are immutable once taken though, so cloning one afterwards is cheap.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+	location: Location,
+	level: Rc<Level>,
+	biome: String,
+	current_floor: Rc<Floor>,
+	explored: Rc<Grid<bool>>,
+	characters: Rc<[character::Piece]>,
+	/// Each piece's threat table, as indices into `characters`,
+	/// since the tables refer to the pieces directly.
+	threat: Rc<[Vec<(usize, u32)>]>,
+	items: Rc<[item::Piece]>,
+	corpses: Rc<[Corpse]>,
+	/// Indices into `characters`, and each member's accent color and starting experience.
+	party: Rc<[(usize, Color, u32)]>,
+	inventory: Rc<[String]>,
+	spawns: Option<Rc<spawn::Table>>,
+	supplies: u32,
+	hunger: Aut,
+	stats: Rc<morgue::Statistics>,
+	quests: Rc<[quest::Active]>,
+	timers: Rc<[schedule::Timer]>,
+}
+
+/// Something the frontend may want to react to, like by changing the music.
+#[derive(Clone, Debug)]
+pub enum Event {
+	BossStarted(Uuid),
+	BossDefeated(Uuid),
+	/// A character with the given name was defeated.
+	Killed(String),
+	/// A piece's HP changed; negative for damage and positive for healing.
+	HpChanged {
+		piece: Uuid,
+		change: i32,
+		/// Where the piece was standing and its maximum HP,
+		/// in case it has been removed (like by dying) by the time this is seen.
+		x: i32,
+		y: i32,
+		heart: u32,
+	},
+	FloorEntered(usize),
+	/// A piece stepped onto a new tile.
+	Moved(Uuid),
+	/// A piece began casting a spell.
+	SpellCast(Uuid),
+	/// The party started a conversation with a character of the given name.
+	Talked(String),
+}
+
+/// Difficulty settings, chosen when a run begins.
+///
+/// Multipliers are percentages, so 100 leaves things as they are.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, alua::UserData)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+	/// Scales the stats of every piece that isn't friendly when it's spawned.
+	#[alua(get)]
+	pub enemy_stats: u32,
+	/// Scales damage taken by friendly pieces.
+	#[alua(get)]
+	pub damage_taken: u32,
+	/// Fallen party members stay down for the rest of the run,
+	/// rather than recovering on the next floor.
+	#[alua(get)]
+	pub permadeath: bool,
+	/// How many turns a party member may spend downed before dying.
+	///
+	/// When 0, party members die as soon as they fall.
+	#[alua(get)]
+	pub bleed_out: u32,
+	/// Allow levels to require supplies; see `Attrition`.
+	#[alua(get)]
+	pub hunger: bool,
+	/// Scales how eager hostile pieces are to approach and attack.
+	#[alua(get)]
+	pub aggression: u32,
+	/// Allow spells (especially those covering an area) to hit the caster's allies.
+	///
+	/// Melee attacks never hit allies.
+	#[alua(get)]
+	pub friendly_fire: bool,
+	/// Allies stop each other's movement; otherwise they may share a tile.
+	#[alua(get)]
+	pub ally_collision: bool,
+	/// Moving into an ally trades places with it, regardless of `ally_collision`.
+	#[alua(get)]
+	pub swap_allies: bool,
+}
+
+impl Default for Rules {
+	fn default() -> Self {
+		Self {
+			enemy_stats: 100,
+			damage_taken: 100,
+			permadeath: false,
+			bleed_out: 5,
+			hunger: true,
+			aggression: 100,
+			friendly_fire: false,
+			ally_collision: true,
+			swap_allies: false,
+		}
+	}
+}
+
+impl Rules {
+	/// Apply `enemy_stats` to a sheet which is about to be spawned as an enemy.
+	pub fn enemy_sheet(&self, mut sheet: character::Sheet) -> character::Sheet {
+		sheet.bases = sheet.bases * self.enemy_stats / 100;
+		sheet.growths = sheet.growths * self.enemy_stats / 100;
+		sheet
+	}
+
+	/// Whether a piece may move into a tile occupied by one of its allies.
+	pub fn allies_passable(&self) -> bool {
+		self.swap_allies || !self.ally_collision
+	}
+}
+
+/// What's left behind by a fallen party member, until the floor ends.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Corpse {
+	pub x: i32,
+	pub y: i32,
+	pub icon: String,
+	pub name: String,
 }
 
 /// Contains information about what should generate on each floor.
@@ -30,13 +312,110 @@ pub struct Manager {
 pub struct Level {
 	#[alua(get, set)]
 	pub name: String,
+	/// Percent chance for the party to be ambushed while resting.
+	#[alua(get, set)]
+	#[serde(default = "default_ambush_chance")]
+	pub ambush_chance: u32,
+	/// Optional rule: the party must carry supplies to avoid starving.
+	#[serde(default)]
+	pub attrition: Attrition,
+	#[serde(default)]
+	pub economy: Economy,
+	/// Quests started along with the level.
+	#[serde(default)]
+	pub quests: Vec<String>,
+	/// Taking the exit from this floor wins the run.
+	#[serde(default)]
+	pub final_floor: Option<usize>,
+	/// Biomes which this level passes through.
+	/// Each floor uses the first biome whose depth range contains it,
+	/// or the last biome if none do.
+	pub biomes: Vec<String>,
+}
+
+/// Rules for the party's supplies, if enabled.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Attrition {
+	pub enabled: bool,
+	/// How many turns one supply lasts the whole party.
+	pub interval: u32,
+	/// Inflicted on each party member when a supply is due but there are none left.
+	pub starvation: String,
+	/// Items which are turned into supplies when picked up, and how many supplies they're worth.
+	pub foods: HashMap<String, u32>,
+}
+
+impl Default for Attrition {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			interval: 50,
+			starvation: String::from("starving"),
+			foods: HashMap::from([
+				("apple".into(), 2),
+				("grapes".into(), 1),
+				("pear".into(), 2),
+				("water_chestnut".into(), 1),
+				("watermelon".into(), 4),
+			]),
+		}
+	}
+}
+
+/// Rules for how pieces regain and spend SP, beyond resting.
+#[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Economy {
+	/// SP restored to each piece at the start of its turn.
+	pub sp_per_turn: u32,
+	/// SP restored to a piece whenever it damages another.
+	pub sp_on_hit: u32,
+	/// SP restored to a piece whenever it defeats another.
+	pub sp_on_kill: u32,
+	/// HP spent for each SP a caster is short by.
+	/// 0 disables overcasting, so spells require the full cost in SP.
+	pub overcast_cost: u32,
+}
+
+fn default_ambush_chance() -> u32 {
+	25
 }
 
 impl Default for Level {
 	fn default() -> Self {
 		Self {
 			name: String::from("New Level"),
+			ambush_chance: default_ambush_chance(),
+			attrition: Attrition::default(),
+			economy: Economy {
+				sp_on_kill: 1,
+				..Economy::default()
+			},
+			quests: vec!["descend".into()],
+			final_floor: Some(6),
+			biomes: vec!["caves".into(), "ruins".into(), "sanctum".into()],
+		}
+	}
+}
+
+impl Level {
+	/// Returns the name of the biome used for the given floor.
+	///
+	/// # Errors
+	///
+	/// Returns an error if any of the level's biomes could not be found.
+	pub fn biome<'level>(
+		&'level self,
+		floor: usize,
+		resources: &resource::Manager,
+	) -> Result<Option<&'level str>> {
+		for name in &self.biomes {
+			if resources.get_biome(name)?.contains(floor) {
+				return Ok(Some(name));
+			}
 		}
+		Ok(self.biomes.last().map(String::as_str))
 	}
 }
 
@@ -46,6 +425,7 @@ impl Default for Level {
 pub struct PartyReferenceDrawState {
 	pub cloud: draw::CloudState,
 	pub cloud_trail: draw::CloudTrail,
+	pub camera: draw::Camera,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -54,23 +434,30 @@ pub struct PartyReference {
 	pub piece: CharacterRef,
 	/// Displayed on the pamphlet.
 	pub accent_color: Color,
+	/// The piece's total experience when it joined the party,
+	/// so that only what was earned during the run gets banked.
+	#[serde(default)]
+	pub starting_experience: u32,
 	#[serde(skip)]
 	pub draw_state: PartyReferenceDrawState,
 }
 
 impl PartyReference {
 	pub fn new(piece: CharacterRef, accent_color: Color) -> Self {
+		let starting_experience = piece.borrow().total_experience();
 		Self {
 			piece,
 			accent_color,
+			starting_experience,
 			draw_state: PartyReferenceDrawState::default(),
 		}
 	}
 }
 
 // this is probably uneccessary and just makes main.rs look nicer
+#[derive(Clone)]
 pub struct PartyReferenceBase {
-	pub sheet: &'static str,
+	pub sheet: character::Sheet,
 	pub accent_color: Color,
 }
 
@@ -87,6 +474,7 @@ impl Manager {
 		resource_manager: &resource::Manager,
 		lua: &mlua::Lua,
 		options: &Options,
+		profile: &mut Profile,
 	) -> Result<Self> {
 		let mut party = Vec::new();
 		let mut characters = Vec::new();
@@ -98,69 +486,289 @@ impl Manager {
 			accent_color,
 		} in party_blueprint
 		{
-			let sheet = resource_manager.get_sheet(sheet)?;
+			let mut sheet = sheet;
+			profile.prepare(&mut sheet);
 			let character = Rc::new(RefCell::new(character::Piece {
 				player_controlled,
 				alliance: character::Alliance::Friendly,
-				..character::Piece::new(sheet.clone(), resource_manager)?
+				..character::Piece::new(sheet, resource_manager)?
 			}));
 			party.push(world::PartyReference::new(character.clone(), accent_color));
 			characters.push(character);
 			player_controlled = false;
 		}
 
-		let console = Console::new(options.ui.colors.console.clone());
+		let console = Console::new(
+			options.ui.colors.console.clone(),
+			options.ui.console_history,
+		);
 
 		lua.globals().set("Console", console.handle.clone())?;
+		// Replaced by the frontend once an audio device is opened.
+		let audio = audio::Handle::default();
+		lua.globals().set("Audio", audio.clone())?;
 		lua.globals()
 			.set("Status", resource_manager.statuses_handle())?;
+		lua.globals().set("Resources", resource_manager.catalog())?;
+		let pieces = spatial::Handle::default();
+		pieces.borrow_mut().rebuild(&characters);
+		lua.globals().set("Pieces", pieces.clone())?;
+		let query = query::Query::new(pieces.clone());
+		lua.globals().set("World", query.clone())?;
+		lua.globals().set("API_VERSION", script::API_VERSION)?;
+		let scheduler = schedule::Scheduler::default();
+		lua.globals().set("Scheduler", scheduler.handle.clone())?;
+		lua.globals().set("Rules", options.rules.clone())?;
 
-		Ok(Manager {
+		let mut manager = Manager {
 			location: world::Location {
 				level: String::from("New Level"),
 				floor: 0,
 			},
+			level: Level::default(),
+			biome: String::new(),
 			current_floor: Floor::default(),
 			characters,
+			pieces,
+			query,
 			items: Vec::new(),
 
 			party,
 			inventory: vec![
-				"items/aloe".into(),
-				"items/apple".into(),
-				"items/blinkfruit".into(),
-				"items/fabric_shred".into(),
-				"items/grapes".into(),
-				"items/ice_cream".into(),
-				"items/lily".into(),
-				"items/pear_on_a_stick".into(),
-				"items/pear".into(),
-				"items/pepper".into(),
-				"items/purefruit".into(),
-				"items/raspberry".into(),
-				"items/reviver_seed".into(),
-				"items/ring_alt".into(),
-				"items/ring".into(),
-				"items/scarf".into(),
-				"items/slimy_apple".into(),
-				"items/super_pepper".into(),
-				"items/twig".into(),
-				"items/water_chestnut".into(),
-				"items/watermelon".into(),
+				"aloe".into(),
+				"apple".into(),
+				"blinkfruit".into(),
+				"fabric_shred".into(),
+				"grapes".into(),
+				"ice_cream".into(),
+				"lily".into(),
+				"pear_on_a_stick".into(),
+				"pear".into(),
+				"pepper".into(),
+				"purefruit".into(),
+				"raspberry".into(),
+				"reviver_seed".into(),
+				"ring_alt".into(),
+				"ring".into(),
+				"scarf".into(),
+				"slimy_apple".into(),
+				"super_pepper".into(),
+				"twig".into(),
+				"water_chestnut".into(),
+				"watermelon".into(),
 			],
 
 			console,
-		})
+			audio,
+			generation_log: mapgen::Log::default(),
+			corpses: Vec::new(),
+			spawns: None,
+			supplies: STARTING_SUPPLIES,
+			hunger: 0,
+			stats: morgue::Statistics::default(),
+			quests: Vec::new(),
+			cutscene: None,
+			shake: None,
+			explored: unexplored(),
+			aiming: None,
+			focus: 0,
+			dialogue: None,
+			timers: Vec::new(),
+			scheduler,
+			events: Vec::new(),
+			seed: random_seed(),
+			run: Uuid::new_v4(),
+			victorious: false,
+			rules: options.rules.clone(),
+		};
+		for quest in manager.level.quests.clone() {
+			manager.start_quest(&quest, resource_manager)?;
+		}
+		Ok(manager)
+	}
+
+	/// Rebuild everything that isn't saved along with a world, so that a loaded world can be played.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the world's globals could not be registered with Lua.
+	pub fn attach(&mut self, lua: &mlua::Lua, options: &Options) -> mlua::Result<()> {
+		// Deserialization gives each reference its own copy of a piece,
+		// so party members need to be pointed back at the pieces on the floor.
+		for member in &mut self.party {
+			let id = member.piece.borrow().id;
+			let found = self
+				.characters
+				.iter()
+				.find(|character| character.borrow().id == id);
+			if let Some(character) = found {
+				member.piece = character.clone();
+			}
+		}
+		self.pieces.borrow_mut().rebuild(&self.characters);
+		self.query = query::Query::new(self.pieces.clone());
+		self.query.set_floor(&self.current_floor);
+		self.query.set_economy(self.level.economy);
+		self.console = Console::new(
+			options.ui.colors.console.clone(),
+			options.ui.console_history,
+		);
+
+		lua.globals().set("Console", self.console.handle.clone())?;
+		lua.globals().set("Audio", self.audio.clone())?;
+		lua.globals().set("Pieces", self.pieces.clone())?;
+		lua.globals().set("World", self.query.clone())?;
+		lua.globals()
+			.set("Scheduler", self.scheduler.handle.clone())?;
+		lua.globals().set("Rules", self.rules.clone())?;
+		Ok(())
+	}
+
+	/// Copy the state of the world, for undoing actions or trying them out without consequences.
+	///
+	/// Presentation (the console, cutscenes, and dialogue) isn't included.
+	pub fn snapshot(&self) -> Snapshot {
+		let party = self
+			.party
+			.iter()
+			.filter_map(|member| {
+				let index = self
+					.characters
+					.iter()
+					.position(|character| Rc::ptr_eq(character, &member.piece))?;
+				Some((index, member.accent_color, member.starting_experience))
+			})
+			.collect();
+		let index_of = |source: &CharacterRef| {
+			self.characters
+				.iter()
+				.position(|character| Rc::ptr_eq(character, source))
+		};
+		let threat = self
+			.characters
+			.iter()
+			.map(|character| {
+				character
+					.borrow()
+					.threat
+					.iter()
+					.filter_map(|(source, threat)| Some((index_of(&source)?, threat)))
+					.collect()
+			})
+			.collect();
+		Snapshot {
+			location: self.location.clone(),
+			level: Rc::new(self.level.clone()),
+			biome: self.biome.clone(),
+			current_floor: Rc::new(self.current_floor.clone()),
+			explored: Rc::new(self.explored.clone()),
+			characters: self
+				.characters
+				.iter()
+				.map(|character| character.borrow().clone())
+				.collect(),
+			threat,
+			items: self.items.iter().cloned().collect(),
+			corpses: self.corpses.iter().cloned().collect(),
+			party,
+			inventory: self.inventory.iter().cloned().collect(),
+			spawns: self.spawns.clone().map(Rc::new),
+			supplies: self.supplies,
+			hunger: self.hunger,
+			stats: Rc::new(self.stats.clone()),
+			quests: self.quests.iter().cloned().collect(),
+			timers: self.timers.iter().cloned().collect(),
+		}
+	}
+
+	/// Return the world to the state it was in when the snapshot was taken.
+	///
+	/// Pieces are recreated, so any references to the old ones (such as from scripts) are left behind.
+	/// Running cutscenes, dialogue, and actions waiting on a target are ended for the same reason.
+	pub fn restore(&mut self, snapshot: &Snapshot) {
+		self.location = snapshot.location.clone();
+		self.level = (*snapshot.level).clone();
+		self.biome = snapshot.biome.clone();
+		self.current_floor = (*snapshot.current_floor).clone();
+		self.explored = (*snapshot.explored).clone();
+		self.characters = snapshot
+			.characters
+			.iter()
+			.map(|piece| {
+				let mut piece = piece.clone();
+				// Delays refer to their ally directly, which wouldn't be restored along with it.
+				if matches!(piece.next_action, Some(character::Action::Delay(_))) {
+					piece.next_action = None;
+				}
+				piece.threat = ai::ThreatTable::default();
+				Rc::new(RefCell::new(piece))
+			})
+			.collect();
+		for (character, threat) in self.characters.iter().zip(snapshot.threat.iter()) {
+			for &(index, amount) in threat {
+				if let Some(source) = self.characters.get(index) {
+					character.borrow_mut().threat.add(source, amount);
+				}
+			}
+		}
+		self.items = snapshot.items.to_vec();
+		self.corpses = snapshot.corpses.to_vec();
+		self.party = snapshot
+			.party
+			.iter()
+			.filter_map(|&(index, accent_color, starting_experience)| {
+				let piece = self.characters.get(index)?.clone();
+				Some(PartyReference {
+					starting_experience,
+					..PartyReference::new(piece, accent_color)
+				})
+			})
+			.collect();
+		self.inventory = snapshot.inventory.to_vec();
+		self.spawns = snapshot.spawns.as_deref().cloned();
+		self.supplies = snapshot.supplies;
+		self.hunger = snapshot.hunger;
+		self.stats = (*snapshot.stats).clone();
+		self.quests = snapshot.quests.to_vec();
+		self.timers = snapshot.timers.to_vec();
+
+		self.cutscene = None;
+		self.shake = None;
+		self.dialogue = None;
+		self.aiming = None;
+		self.events.clear();
+		self.pieces.borrow_mut().rebuild(&self.characters);
+		self.query.set_floor(&self.current_floor);
+		self.query.set_economy(self.level.economy);
 	}
 
 	pub fn new_floor(&mut self, resources: &resource::Manager) -> Result<()> {
+		if self
+			.level
+			.final_floor
+			.is_some_and(|floor| self.location.floor >= floor)
+		{
+			self.victorious = true;
+			self.console
+				.print_important(format!("{} has been conquered!", self.level.name));
+			return Ok(());
+		}
 		self.location.floor += 1;
+		self.stats.deepest_floor = self.stats.deepest_floor.max(self.location.floor);
 		self.console
 			.print_important(format!("Entering floor {}", self.location.floor));
 		self.current_floor = Floor::default();
+		self.explored = unexplored();
+		self.corpses.clear();
 
-		let party_pieces: Vec<_> = self.party.iter().map(|x| x.piece.clone()).collect();
+		let party_pieces: Vec<_> = self
+			.party
+			.iter()
+			.map(|x| x.piece.clone())
+			.filter(|piece| !self.rules.permadeath || piece.borrow().hp > 0)
+			.collect();
 		self.characters.clear();
+		self.pieces.borrow_mut().clear();
 
 		self.console
 			.print_unimportant("You take some time to rest...".into());
@@ -169,27 +777,299 @@ impl Manager {
 			// Reset positions
 			i.x = 0;
 			i.y = 0;
+			i.action_delay = 0;
 			// Rest
+			let was_charmed = i.is_charmed();
 			i.rest();
+			if was_charmed && !i.is_charmed() {
+				self.console.print_special(
+					"{Address} comes to {their} senses.".replace_nouns(&i.sheet.nouns),
+				);
+			}
 			// Award experience
 			i.sheet.experience += 40;
 			while i.sheet.experience >= 100 {
 				i.sheet.experience -= 100;
-				i.sheet.level = i.sheet.level.saturating_add(1);
+				i.level_up(&resources.catalog());
 				self.console.print_special(
-					format!("{{Address}}'s level increased to {}!", i.sheet.level)
+					format!("{{Address's}} level increased to {}!", i.sheet.level)
 						.replace_nouns(&i.sheet.nouns),
 				);
 			}
 		}
-		self.characters = party_pieces;
+		let biome_name = self
+			.level
+			.biome(self.location.floor, resources)?
+			.ok_or_else(|| resource::Error::NotFound(format!("floor {}", self.location.floor)))?;
+		let biome = resources.get_biome(biome_name)?;
+		self.biome = biome_name.into();
+		let set_name = &biome.vault_set;
+		let set = resources.get_vault_set(set_name)?;
+
+		// Each floor gets its own seed, so that floors don't depend on how the ones before them went.
+		let seed = self
+			.seed
+			.wrapping_add(self.location.floor as u64)
+			.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+		let mut rng = StdRng::seed_from_u64(seed);
+		let mut log = mapgen::Log::new(seed);
+		log.push(format!(
+			"generating floor {} in \"{biome_name}\" from \"{set_name}\"",
+			self.location.floor
+		));
+
+		// Spawn tables leave vaults alone, since they already decide their own inhabitants.
+		let mut vault_areas = Vec::new();
+		let mut entrance = None;
+		self.items.clear();
+		for attempt in 1..=MAX_GENERATION_ATTEMPTS {
+			self.current_floor = Floor::default();
+			self.explored = unexplored();
+			self.characters = party_pieces.clone();
+			self.pieces.borrow_mut().rebuild(&self.characters);
+			vault_areas.clear();
+			set.generator.generate(&mut self.current_floor, &mut rng);
+
+			for vault in set.choose(resources, self.location.floor, &mut rng)? {
+				let height = vault.tiles.len() / vault.width.max(1);
+				let x =
+					rng.gen_range(0..=self.current_floor.map.cols().saturating_sub(vault.width));
+				let y = rng.gen_range(0..=self.current_floor.map.rows().saturating_sub(height));
+				log.push(format!("placed a vault at ({x}, {y})"));
+				self.apply_vault(x as i32, y as i32, vault, resources)?;
+				vault_areas.push((x as i32, y as i32, vault.width as i32, height as i32));
+			}
+
+			entrance = self.place_party(&mut rng);
+			let Some(entrance) = entrance else {
+				log.push(String::from("there was nowhere to place the party"));
+				continue;
+			};
+			let stranded = mapgen::unreachable_tiles(&self.current_floor, entrance).len();
+			if stranded == 0 {
+				break;
+			}
+			match set.connectivity {
+				mapgen::Connectivity::Regenerate if attempt < MAX_GENERATION_ATTEMPTS => {
+					log.push(format!(
+						"rejected attempt {attempt}: {stranded} tiles are unreachable"
+					));
+				}
+				mapgen::Connectivity::Carve | mapgen::Connectivity::Regenerate => {
+					mapgen::connect(&mut self.current_floor, entrance, &mut log);
+					break;
+				}
+			}
+		}
+
+		self.query.set_floor(&self.current_floor);
+		self.query.set_economy(self.level.economy);
+		self.spawns = None;
+		if let Some(table) = biome.spawns.as_ref().or(set.spawns.as_ref()) {
+			let table = resources.get_spawn_table(table)?;
+			self.spawns = Some(table.clone());
+			for (sheet, count) in table.choose(self.location.floor, &mut rng) {
+				let sheet = resources.get_sheet(sheet)?;
+				let spawned =
+					self.spawn_pack(sheet, count, &vault_areas, entrance, resources, &mut rng)?;
+				log.push(format!("spawned {spawned} of {count} {}", sheet.nouns.name));
+			}
+		}
+
+		let mut boss_events = Vec::new();
+		for character in &self.characters {
+			let piece = character.borrow();
+			if piece.sheet.boss {
+				self.console.print_important(
+					"{Address} awaits on this floor.".replace_nouns(&piece.sheet.nouns),
+				);
+				boss_events.push(Event::BossStarted(piece.id));
+				if let Some(entrance) = &piece.sheet.entrance {
+					self.cutscene = Some(cutscene::Cutscene::new(
+						entrance.clone(),
+						Some(character.clone()),
+					));
+				}
+			}
+		}
+		self.emit(Event::FloorEntered(self.location.floor));
+		for event in boss_events {
+			self.emit(event);
+		}
+
+		if let Some(ambience) = biome.ambience.iter().choose(&mut rng) {
+			self.console.print_unimportant(ambience.clone());
+		}
+
+		self.generation_log = log;
+		Ok(())
+	}
+
+	/// Place a group of characters near each other,
+	/// outside of any vault and away from the entrance.
+	///
+	/// Returns how many characters could actually be placed.
+	fn spawn_pack(
+		&mut self,
+		sheet: &character::Sheet,
+		count: u32,
+		vault_areas: &[(i32, i32, i32, i32)],
+		entrance: Option<(i32, i32)>,
+		resources: &resource::Manager,
+		rng: &mut dyn rand::RngCore,
+	) -> Result<u32> {
+		let in_vault = |x: i32, y: i32| {
+			vault_areas
+				.iter()
+				.any(|&(vx, vy, w, h)| x >= vx && y >= vy && x < vx + w && y < vy + h)
+		};
+		let near_entrance = |x: i32, y: i32| {
+			entrance.is_some_and(|(ex, ey)| {
+				(x - ex).unsigned_abs().max((y - ey).unsigned_abs()) < SPAWN_SAFE_DISTANCE
+			})
+		};
+		let mut leader: Option<(i32, i32)> = None;
+		let mut spawned = 0;
+		for _ in 0..count {
+			let neighbor = leader.and_then(|(x, y)| {
+				(-1..=1)
+					.flat_map(|yoff| (-1..=1).map(move |xoff| (x + xoff, y + yoff)))
+					.find(|&(x, y)| {
+						self.current_floor.map.get(y, x) == Some(&floor::Tile::Floor)
+							&& !in_vault(x, y) && self.get_character_at(x, y).is_none()
+					})
+			});
+			let position = neighbor.or_else(|| {
+				(0..100)
+					.filter_map(|_| self.current_floor.random_floor_tile(rng))
+					.find(|&(x, y)| {
+						!in_vault(x, y)
+							&& !near_entrance(x, y)
+							&& self.get_character_at(x, y).is_none()
+					})
+			});
+			let Some((x, y)) = position else {
+				break;
+			};
+			let piece = character::Piece {
+				x,
+				y,
+				..character::Piece::new(self.rules.enemy_sheet(sheet.clone()), resources)?
+			};
+			self.add_character(Rc::new(RefCell::new(piece)));
+			leader.get_or_insert((x, y));
+			spawned += 1;
+		}
+		Ok(spawned)
+	}
+
+	/// Rest the party at the rest site under `camper`, which is used up in the process.
+	fn camp(&mut self, lua: &mlua::Lua, camper: &CharacterRef, delay: Aut) -> mlua::Result<()> {
+		let (x, y) = {
+			let camper = camper.borrow();
+			(camper.x, camper.y)
+		};
+		let Some(tile @ floor::Tile::Rest) = self.current_floor.map.get_mut(y, x) else {
+			self.console
+				.print_unimportant("You can only rest at a rest site.".into());
+			return Ok(());
+		};
+		*tile = floor::Tile::Floor;
+		self.query.set_floor(&self.current_floor);
+
 		let mut rng = rand::thread_rng();
-		self.apply_vault(
-			rng.gen_range(1..8),
-			rng.gen_range(1..8),
-			resources.get_vault("example")?,
-			resources,
-		)
+		if rng.gen_range(0..100) < self.level.ambush_chance {
+			let resources: resource::Catalog = lua.globals().get("Resources")?;
+			let ambushers = self
+				.spawns
+				.as_ref()
+				.and_then(|table| table.choose(self.location.floor, &mut rng).pop())
+				.map(|(sheet, count)| (sheet.to_string(), count));
+			if let Some((sheet, count)) = ambushers {
+				let sheet = resources
+					.get_sheet(&sheet)
+					.map_err(mlua::Error::external)?
+					.clone();
+				for _ in 0..count {
+					let position = (0..100)
+						.map(|_| {
+							(
+								x + rng.gen_range(-AMBUSH_RANGE..=AMBUSH_RANGE),
+								y + rng.gen_range(-AMBUSH_RANGE..=AMBUSH_RANGE),
+							)
+						})
+						.find(|&(x, y)| {
+							self.current_floor.map.get(y, x) == Some(&floor::Tile::Floor)
+								&& self.get_character_at(x, y).is_none()
+						});
+					let Some((x, y)) = position else {
+						break;
+					};
+					let piece = character::Piece {
+						x,
+						y,
+						..character::Piece::from_catalog(sheet.clone(), &resources)
+							.map_err(mlua::Error::external)?
+					};
+					self.add_character(Rc::new(RefCell::new(piece)));
+				}
+				self.console
+					.print_important("The party is ambushed while resting!".into());
+				// Being ambushed cuts the rest short.
+				self.end_turn(TURN);
+				return Ok(());
+			}
+		}
+
+		for member in &self.party {
+			let mut member = member.piece.borrow_mut();
+			let was_charmed = member.is_charmed();
+			member.rest();
+			if was_charmed && !member.is_charmed() {
+				self.console.print_special(
+					"{Address} comes to {their} senses.".replace_nouns(&member.sheet.nouns),
+				);
+			}
+		}
+		self.console
+			.print_special("The party rests and recovers.".into());
+		self.end_turn(delay);
+		Ok(())
+	}
+
+	/// Scatter the party across the floor, keeping members next to each other when possible.
+	///
+	/// Returns the position of the first member placed.
+	fn place_party(&mut self, rng: &mut dyn rand::RngCore) -> Option<(i32, i32)> {
+		let mut leader: Option<(i32, i32)> = None;
+		for member in self.party.iter().map(|x| &x.piece) {
+			let neighbor = leader.and_then(|(x, y)| {
+				(-1..=1)
+					.flat_map(|yoff| (-1..=1).map(move |xoff| (x + xoff, y + yoff)))
+					.find(|&(x, y)| {
+						self.current_floor.map.get(y, x) == Some(&floor::Tile::Floor)
+							&& self.get_character_at(x, y).is_none()
+					})
+			});
+			let position = neighbor.or_else(|| {
+				// Give up after a few attempts; an occupied tile is better than nothing.
+				(0..100)
+					.filter_map(|_| self.current_floor.random_floor_tile(rng))
+					.find(|&(x, y)| self.get_character_at(x, y).is_none())
+			});
+			if let Some((x, y)) = position {
+				let from = {
+					let mut member = member.borrow_mut();
+					let from = (member.x, member.y);
+					member.x = x;
+					member.y = y;
+					from
+				};
+				self.pieces.borrow_mut().relocate(member, from);
+				leader.get_or_insert((x, y));
+			}
+		}
+		leader
 	}
 
 	pub fn update<'lua>(
@@ -198,8 +1078,21 @@ impl Manager {
 		lua: &'lua mlua::Lua,
 		input_mode: &mut input::Mode,
 	) -> mlua::Result<Option<world::ActionRequest<'lua>>> {
-		let (renew_action, action_request) = match action_request {
-			Some(world::ActionRequest::BeginCursor { x, y, callback }) => {
+		let _span = info_span!(
+			"tick",
+			next = %self.next_character().borrow().sheet.nouns.name
+		)
+		.entered();
+		let hp_before = self.hp_snapshot();
+
+		// Any HP lost while a piece is acting is attributed to it.
+		let (renew_action, action_request, actor) = match action_request {
+			Some(world::ActionRequest::BeginCursor {
+				x,
+				y,
+				range,
+				callback,
+			}) => {
 				match *input_mode {
 					input::Mode::Cursor {
 						x,
@@ -208,10 +1101,30 @@ impl Manager {
 						..
 					} => {
 						*input_mode = input::Mode::Normal;
+						let actor = self.aiming.as_ref().map(|x| x.actor.clone());
 						if let Some(character) = self.get_character_at(x, y) {
-							(true, ActionRequest::poll(lua, callback, character.clone())?)
+							if let Some(Aiming {
+								actor,
+								spell: Some(spell),
+								..
+							}) = &self.aiming
+							{
+								actor.borrow_mut().last_cast =
+									Some((spell.clone(), Some(character.borrow().id)));
+							}
+							let request = ActionRequest::poll(lua, callback, character.clone())
+								.unwrap_or_else(|error| {
+									self.report_script_error(&error);
+									None
+								});
+							// The callback may ask for another target before the turn is over.
+							if request.is_none() {
+								self.stop_aiming();
+							}
+							(true, request, actor)
 						} else {
-							(false, None)
+							self.stop_aiming();
+							(false, None, None)
 						}
 					}
 					input::Mode::Cursor {
@@ -223,24 +1136,64 @@ impl Manager {
 						// even if this is a no-op.
 						(
 							false,
-							Some(world::ActionRequest::BeginCursor { x, y, callback }),
+							Some(world::ActionRequest::BeginCursor {
+								x,
+								y,
+								range,
+								callback,
+							}),
+							None,
 						)
 					}
 					_ => {
 						// If cursor mode is cancelled in any way, the callback will be destroyed.
-						(false, None)
+						self.stop_aiming();
+						(false, None, None)
 					}
 				}
 			}
-			None => (true, self.pop_action(lua)?),
+			None if self.cutscene.is_some() => (false, None, None),
+			None if self.dialogue.is_some() => {
+				self.advance_dialogue(lua)?;
+				if self.dialogue.is_none() {
+					*input_mode = input::Mode::Normal;
+				}
+				(false, None, None)
+			}
+			// A broken script shouldn't stop the game;
+			// whatever turn it was part of has already been spent, so it's as if the piece waited.
+			None => {
+				let actor = self.next_character().clone();
+				let request = self.pop_action(lua).unwrap_or_else(|error| {
+					self.report_script_error(&error);
+					None
+				});
+				(true, request, Some(actor))
+			}
 		};
+		self.record_damage(actor.as_ref(), &hp_before);
+		// Statuses and timers hurt pieces on nobody's behalf,
+		// even when they happen to fire during somebody's turn.
+		let hp_before = self.hp_snapshot();
+		self.run_status_hooks(lua);
+		self.run_timers(lua);
+		self.record_damage(None, &hp_before);
+		self.look_around();
+		self.enter_phases(lua)?;
+		self.consume_supplies(lua)?;
+		// Scripts may have moved pieces behind the index's back.
+		if self.pieces.borrow().is_stale(&self.characters) {
+			self.pieces.borrow_mut().rebuild(&self.characters);
+		}
 
 		if renew_action {
 			// Set up any new action requests.
-			if let Some(world::ActionRequest::BeginCursor { x, y, callback: _ }) = action_request {
+			if let Some(world::ActionRequest::BeginCursor { x, y, range, .. }) = action_request {
 				*input_mode = input::Mode::Cursor {
 					x,
 					y,
+					origin: (x, y),
+					range,
 					submitted: false,
 					state: input::CursorState::default(),
 				};
@@ -250,143 +1203,1759 @@ impl Manager {
 		Ok(action_request)
 	}
 
-	pub fn next_character(&self) -> &CharacterRef {
-		&self.characters[0]
-	}
-
-	pub fn get_character_at(&self, x: i32, y: i32) -> Option<&CharacterRef> {
-		self.characters.iter().find(|p| {
-			let p = p.borrow();
-			p.x == x && p.y == y
-		})
-	}
-
-	pub fn apply_vault(
-		&mut self,
-		x: i32,
-		y: i32,
-		vault: &Vault,
-		resources: &resource::Manager,
-	) -> Result<()> {
-		self.current_floor.blit_vault(x as usize, y as usize, vault);
-		for (xoff, yoff, sheet_name) in &vault.characters {
-			let piece = character::Piece {
-				x: x + xoff,
-				y: y + yoff,
-				..character::Piece::new(resources.get_sheet(sheet_name)?.clone(), resources)?
-			};
-			self.characters.push(Rc::new(RefCell::new(piece)));
+	/// Run any scheduled callbacks whose time has come.
+	fn run_timers(&mut self, lua: &mlua::Lua) {
+		for timer in self.scheduler.drain() {
+			let index = self
+				.timers
+				.partition_point(|x| x.remaining <= timer.remaining);
+			self.timers.insert(index, timer);
 		}
-		Ok(())
-	}
-}
-
-/// Used to "escape" the world and request extra information, such as inputs.
-pub enum ActionRequest<'lua> {
-	/// This callback will be called in place of `pop_action` once a position is selected.
-	BeginCursor {
-		x: i32,
-		y: i32,
-		callback: mlua::Thread<'lua>,
-	},
-}
-
-impl<'lua> ActionRequest<'lua> {
-	fn poll(
-		lua: &'lua mlua::Lua,
-		thread: mlua::Thread<'lua>,
-		args: impl mlua::IntoLuaMulti<'lua>,
-	) -> mlua::Result<Option<ActionRequest<'lua>>> {
-		#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-		#[serde(tag = "type")]
-		pub enum LuaActionRequest {
-			Cursor { x: i32, y: i32 },
+		let due = self.timers.partition_point(|x| x.remaining == 0);
+		for timer in self.timers.drain(..due).collect::<Vec<_>>() {
+			if let Err(error) = timer.run(lua) {
+				self.report_script_error(&error);
+			}
 		}
-
-		let action: Option<LuaActionRequest> = lua.from_value(thread.resume(args)?)?;
-		Ok(action.map(
-			|LuaActionRequest::Cursor { x, y }| ActionRequest::BeginCursor {
-				x,
-				y,
-				callback: thread,
-			},
-		))
 	}
-}
-
-impl Manager {
-	pub fn pop_action<'lua>(
-		&mut self,
-		lua: &'lua mlua::Lua,
-	) -> mlua::Result<Option<ActionRequest<'lua>>> {
-		let next_character = self.next_character();
 
-		// TODO: Character ordering/timing
-		let Some(action) = next_character.borrow_mut().next_action.take() else {
-			return Ok(None);
-		};
-		match action {
-			character::Action::Move(dir) => self.move_piece(lua, next_character, dir),
-			character::Action::Cast(spell) => {
-				if spell.castable_by(&next_character.borrow()) {
-					let spell = spell.clone();
-					// TODO: this is awful. just move targeting into scripts.
-					match spell.parameters.clone() {
-						spell::Parameters::Target {
-							magnitude,
-							pierce_threshold,
-						} => {
-							// Create a reference for the callback to use.
-							let caster = next_character.clone();
-							let affinity = spell.affinity(&caster.borrow());
-							let magnitude = magnitude
-								.as_ref()
-								.map(|x| affinity.magnitude(u32::evalv(x, &*caster.borrow())));
-
-							let chunk = lua.load(spell.on_cast.contents());
-							let name = match &spell.on_cast {
-								script::MaybeInline::Inline(_) => {
-									format!("{} (inline)", spell.name)
-								}
-								script::MaybeInline::Path(script::Script { path, contents: _ }) => {
-									path.clone()
-								}
-							};
-							let globals = lua.globals().clone();
-
-							globals.set("caster", caster)?;
-							// Maybe these should be members of the spell?
-							globals.set("magnitude", magnitude)?;
-							globals.set("pierce_threshold", pierce_threshold)?;
-							globals.set("level", spell.level)?;
-							globals.set("affinity", affinity)?;
-
-							let value: mlua::Value =
-								chunk.set_name(name).set_environment(globals).eval()?;
-
-							match value {
-								mlua::Value::Thread(thread) => ActionRequest::poll(lua, thread, ()),
-
-								mlua::Value::Nil => Ok(None),
-								_ => {
-									error!("unexpected return value");
-									Ok(None)
-								}
-							}
+	/// Announce anything learned and run any status hooks queued up by the pieces on this floor.
+	fn run_status_hooks(&mut self, lua: &mlua::Lua) {
+		for character in self.characters.clone() {
+			let lessons = std::mem::take(&mut character.borrow_mut().pending_lessons);
+			for lesson in lessons {
+				self.console.print_special(
+					lesson
+						.ability
+						.announcement()
+						.replace_nouns(&character.borrow().sheet.nouns),
+				);
+				if let Err(error) = lesson.run_hook(lua, &character) {
+					self.report_script_error(&error);
+				}
+			}
+			let pending = std::mem::take(&mut character.borrow_mut().pending_hooks);
+			for pending in pending {
+				let result = match pending {
+					status::Pending::Active(key, hook) => {
+						let Some(mut status) = character.borrow().statuses.get(&key).cloned()
+						else {
+							continue;
+						};
+						let result = status.run_hook(lua, hook, &character);
+						// The hook may have removed the status; otherwise, save its state.
+						if let Some(active) = character.borrow_mut().statuses.get_mut(&key) {
+							active.state = status.state;
 						}
+						result
 					}
-				} else {
-					let message =
-						format!("{{Address}} doesn't have enough SP to cast {}.", spell.name)
-							.replace_nouns(&next_character.borrow().sheet.nouns);
-					self.console.print_system(message);
-					Ok(None)
+					status::Pending::Removed(mut status, hook) => {
+						status.run_hook(lua, hook, &character)
+					}
+				};
+				if let Err(error) = result {
+					self.report_script_error(&error);
 				}
 			}
 		}
 	}
 
-	/// # Errors
+	/// Describe a failed script in the console and logs, so that play can continue without it.
+	pub fn report_script_error(&self, error: &mlua::Error) {
+		let report = script::Report::new(error);
+		error!("script error: {report}");
+		self.console
+			.print_danger(format!("Script error: {}", report.summary));
+		for line in &report.traceback {
+			self.console.print_debug(line.clone());
+		}
+	}
+
+	fn hp_snapshot(&self) -> Vec<(CharacterRef, i32)> {
+		self.characters
+			.iter()
+			.map(|x| (x.clone(), x.borrow().hp))
+			.collect()
+	}
+
+	/// Attribute any HP changes since `hp_before` to `actor`, or to nobody if no piece was acting.
+	///
+	/// Only hurting hostile pieces earns the actor SP.
+	fn record_damage(&mut self, actor: Option<&CharacterRef>, hp_before: &[(CharacterRef, i32)]) {
+		let mut changes = Vec::new();
+		// SP earned by the actor for hits and kills.
+		let mut restored = 0;
+		for (character, before) in hp_before {
+			// Scripts deal damage by setting HP directly, so difficulty is applied after the fact.
+			if self.rules.damage_taken != 100 {
+				let mut piece = character.borrow_mut();
+				let damage = before - piece.hp;
+				if damage > 0 && piece.effective_alliance() == character::Alliance::Friendly {
+					piece.hp = before - (damage * self.rules.damage_taken as i32 / 100).max(1);
+				}
+			}
+			let damage = before - character.borrow().hp.max(0);
+			if damage != 0 {
+				let piece = character.borrow();
+				changes.push(Event::HpChanged {
+					piece: piece.id,
+					change: -damage,
+					x: piece.x,
+					y: piece.y,
+					heart: piece.stats().heart,
+				});
+			}
+			if let Some(actor) = actor.filter(|_| damage < 0) {
+				// Healing draws the attention of everything that wanted the patient dead.
+				let alliance = character.borrow().effective_alliance();
+				for hostile in &self.characters {
+					if !Rc::ptr_eq(hostile, actor)
+						&& hostile.borrow().effective_alliance() != alliance
+					{
+						hostile.borrow_mut().threat.add(actor, -damage as u32);
+					}
+				}
+			}
+			if damage <= 0 {
+				continue;
+			}
+			let attacker = actor.filter(|actor| !Rc::ptr_eq(character, actor));
+			let hostile = attacker.is_some_and(|attacker| {
+				attacker.borrow().effective_alliance() != character.borrow().effective_alliance()
+			});
+			if let Some(attacker) = attacker {
+				character.borrow_mut().threat.add(attacker, damage as u32);
+			}
+			if hostile {
+				restored += self.level.economy.sp_on_hit;
+			}
+			let attacker = attacker.map(|x| x.borrow().sheet.nouns.name.clone());
+			let mut piece = character.borrow_mut();
+			self.stats
+				.record_damage(attacker.as_deref(), &piece.sheet.nouns.name, damage as u32);
+			if *before > 0 && piece.hp <= 0 {
+				// Party members may only be downed, so the kill is left for `remove_dead`.
+				piece.defeated_by = attacker.map(|attacker| attacker.to_string());
+				if hostile {
+					restored += self.level.economy.sp_on_kill;
+				}
+			}
+		}
+		for event in changes {
+			self.emit(event);
+		}
+		if let Some(actor) = actor.filter(|_| restored > 0) {
+			actor.borrow_mut().restore_sp(restored);
+		}
+	}
+
+	/// Bring a friendly piece into the party, so that it follows the party between floors.
+	///
+	/// Charmed pieces may be recruited too, in which case the charm becomes permanent.
+	/// Returns false if the piece is already in the party or isn't friendly.
+	pub fn recruit(&mut self, character: &CharacterRef, accent_color: Color) -> bool {
+		if self.party.iter().any(|x| Rc::ptr_eq(&x.piece, character))
+			|| character.borrow().effective_alliance() != character::Alliance::Friendly
+		{
+			return false;
+		}
+		{
+			let mut piece = character.borrow_mut();
+			piece.alliance = character::Alliance::Friendly;
+			piece.statuses.retain(|_, status| !status.charms());
+			self.console
+				.print_special("{Address} joins the party!".replace_nouns(&piece.sheet.nouns));
+		}
+		self.party
+			.push(PartyReference::new(character.clone(), accent_color));
+		true
+	}
+
+	/// Remove a piece from the party.
+	/// It stays on the current floor, but won't follow the party any further.
+	///
+	/// Returns false if the piece isn't in the party, or is the last member.
+	pub fn dismiss(&mut self, character: &CharacterRef) -> bool {
+		let Some(index) = self
+			.party
+			.iter()
+			.position(|x| Rc::ptr_eq(&x.piece, character))
+		else {
+			return false;
+		};
+		if self.party.len() <= 1 {
+			return false;
+		}
+		self.party.remove(index);
+		self.console.print_unimportant(
+			"{Address} leaves the party.".replace_nouns(&character.borrow().sheet.nouns),
+		);
+		true
+	}
+
+	/// Exchange the positions of two party members.
+	/// The first member leads the party onto new floors.
+	///
+	/// Returns false if either piece isn't in the party.
+	pub fn swap(&mut self, a: &CharacterRef, b: &CharacterRef) -> bool {
+		let position = |character| {
+			self.party
+				.iter()
+				.position(|x| Rc::ptr_eq(&x.piece, character))
+		};
+		let (Some(a), Some(b)) = (position(a), position(b)) else {
+			return false;
+		};
+		self.party.swap(a, b);
+		true
+	}
+
+	/// Returns true once every member of the party has fallen.
+	pub fn party_defeated(&self) -> bool {
+		self.party
+			.iter()
+			.all(|member| member.piece.borrow().hp <= 0)
+	}
+
+	/// Advance any running cutscene by `delta` seconds.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the cutscene's script fails, in which case it is stopped.
+	pub fn tick_cutscene(&mut self, lua: &mlua::Lua, delta: f64) -> mlua::Result<()> {
+		if let Some(shake) = &mut self.shake {
+			shake.remaining -= delta;
+			if shake.remaining <= 0.0 {
+				self.shake = None;
+			}
+		}
+
+		let thread = match self.cutscene.take() {
+			None => return Ok(()),
+			Some(cutscene::Cutscene::Pending { script, subject }) => {
+				lua.globals().set("subject", subject)?;
+				let function = script.load(lua, "cutscene").into_function()?;
+				lua.create_thread(function)?
+			}
+			Some(cutscene::Cutscene::Running { thread, wait }) if wait > delta => {
+				self.cutscene = Some(cutscene::Cutscene::Running {
+					thread,
+					wait: wait - delta,
+				});
+				return Ok(());
+			}
+			Some(cutscene::Cutscene::Running {
+				thread: key,
+				wait: _,
+			}) => {
+				let thread: mlua::Thread = lua.registry_value(&key)?;
+				// Every wait registers the thread anew, so this key is done with.
+				lua.remove_registry_value(key)?;
+				thread
+			}
+		};
+
+		// Run commands until one of them needs to wait, or the cutscene ends.
+		loop {
+			if thread.status() != mlua::ThreadStatus::Resumable {
+				return Ok(());
+			}
+			let command: Option<mlua::Table> = thread.resume(())?;
+			let Some(command) = command else {
+				return Ok(());
+			};
+			let kind: String = command.get("type")?;
+			match kind.as_str() {
+				"Say" => {
+					let speaker: String = command.get("speaker")?;
+					self.console.say(speaker.into(), command.get("text")?);
+				}
+				"Move" => {
+					let piece: mlua::UserDataRef<CharacterRef> = command.get("piece")?;
+					let from = {
+						let mut piece = piece.borrow_mut();
+						let from = (piece.x, piece.y);
+						piece.x = command.get("x")?;
+						piece.y = command.get("y")?;
+						from
+					};
+					self.pieces.borrow_mut().relocate(&piece, from);
+				}
+				"Wait" => {
+					let ms: f64 = command.get("ms")?;
+					self.cutscene = Some(cutscene::Cutscene::Running {
+						thread: lua.create_registry_value(thread)?,
+						wait: ms / 1000.0,
+					});
+					return Ok(());
+				}
+				"Shake" => {
+					let ms: f64 = command.get("ms")?;
+					self.shake = Some(cutscene::Shake {
+						intensity: command.get("intensity")?,
+						remaining: ms / 1000.0,
+					});
+				}
+				_ => {
+					error!("unknown cutscene command: {kind}");
+				}
+			}
+		}
+	}
+
+	/// Record an event, advancing any quests waiting on it.
+	pub fn emit(&mut self, event: Event) {
+		for quest in &mut self.quests {
+			if quest.update(&event) {
+				if quest.is_complete() {
+					self.console
+						.print_special(format!("Quest complete: {}", quest.quest.name));
+				} else if let Some(stage) = quest.current() {
+					self.console
+						.print_special(format!("{}: {}", quest.quest.name, stage.description));
+				}
+			}
+		}
+		self.events.push(event);
+	}
+
+	/// Begin a quest, unless it's already active.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the quest could not be found.
+	pub fn start_quest(&mut self, key: &str, resources: &resource::Manager) -> Result<()> {
+		let quest = resources.get_quest(key)?;
+		if self.quests.iter().any(|x| x.quest.name == quest.name) {
+			return Ok(());
+		}
+		self.console
+			.print_special(format!("New quest: {}", quest.name));
+		self.quests.push(quest::Active::new(quest.clone()));
+		Ok(())
+	}
+
+	/// Begin a conversation between `listener` and `speaker`, pausing turns until it ends.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the dialogue could not be found.
+	pub fn start_dialogue(
+		&mut self,
+		key: &str,
+		speaker: CharacterRef,
+		listener: CharacterRef,
+		resources: &resource::Manager,
+	) -> Result<()> {
+		let dialogue = resources.get_dialogue(key)?.clone();
+		let name = speaker.borrow().sheet.nouns.name.to_string();
+		self.emit(Event::Talked(name));
+		self.dialogue = Some(dialogue::State::new(dialogue, speaker, listener));
+		Ok(())
+	}
+
+	/// Returns true if the script evaluated to `true`.
+	fn run_dialogue_script(
+		lua: &mlua::Lua,
+		state: &dialogue::State,
+		script: &script::MaybeInline,
+	) -> mlua::Result<bool> {
+		let globals = lua.globals();
+		globals.set("speaker", state.speaker.clone())?;
+		globals.set("listener", state.listener.clone())?;
+		let value: mlua::Value = script.eval(lua, &format!("dialogue node {}", state.node))?;
+		Ok(matches!(value, mlua::Value::Boolean(true)))
+	}
+
+	/// Enter the current node if necessary, and act on the player's selection.
+	fn advance_dialogue(&mut self, lua: &mlua::Lua) -> mlua::Result<()> {
+		let Some(state) = &mut self.dialogue else {
+			return Ok(());
+		};
+		let Some(node) = state.current().cloned() else {
+			error!("dialogue node {} does not exist", state.node);
+			self.dialogue = None;
+			return Ok(());
+		};
+
+		if !state.entered {
+			state.entered = true;
+			if let Some(on_enter) = &node.on_enter {
+				Self::run_dialogue_script(lua, state, on_enter)?;
+			}
+			state.available.clear();
+			for (i, choice) in node.choices.iter().enumerate() {
+				let offered = match &choice.condition {
+					Some(condition) => Self::run_dialogue_script(lua, state, condition)?,
+					None => true,
+				};
+				if offered {
+					state.available.push(i);
+				}
+			}
+			let speaker = node
+				.speaker
+				.clone()
+				.unwrap_or_else(|| state.speaker.borrow().sheet.nouns.name.to_string());
+			self.console.say(speaker.into(), node.text.clone());
+			return Ok(());
+		}
+
+		let next = match state.selection.take() {
+			None => return Ok(()),
+			// If every choice was filtered out, the node acts as though it had none.
+			Some(None) if state.available.is_empty() => node.next.clone(),
+			Some(None) => return Ok(()),
+			Some(Some(i)) => {
+				let Some(choice) = state
+					.available
+					.contains(&i)
+					.then(|| node.choices.get(i))
+					.flatten()
+				else {
+					return Ok(());
+				};
+				if let Some(effect) = &choice.effect {
+					Self::run_dialogue_script(lua, state, effect)?;
+				}
+				choice.next.clone()
+			}
+		};
+		match next {
+			Some(next) => {
+				state.node = next;
+				state.entered = false;
+			}
+			None => self.dialogue = None,
+		}
+		Ok(())
+	}
+
+	/// Whether the party is eating through supplies, which requires both the rule and the level to allow it.
+	pub fn hungers(&self) -> bool {
+		self.rules.hunger && self.level.attrition.enabled && self.level.attrition.interval > 0
+	}
+
+	/// Eat a supply for every interval that has passed, starving the party if there are none.
+	fn consume_supplies(&mut self, lua: &mlua::Lua) -> mlua::Result<()> {
+		let attrition = &self.level.attrition;
+		if !self.hungers() {
+			self.hunger = 0;
+			return Ok(());
+		}
+		let interval = attrition.interval * TURN;
+		while self.hunger >= interval {
+			self.hunger -= interval;
+			if self.supplies > 0 {
+				self.supplies -= 1;
+				if self.supplies == 0 {
+					self.console
+						.print_important("The party has run out of supplies.".into());
+				}
+			} else {
+				let statuses: resource::Handle<Status> = lua.globals().get("Status")?;
+				for member in &self.party {
+					let mut member = member.piece.borrow_mut();
+					if !member.statuses.contains_key(attrition.starvation.as_str()) {
+						self.console.print_important(
+							"{Address} is starving!".replace_nouns(&member.sheet.nouns),
+						);
+					}
+					member
+						.inflict(&statuses, attrition.starvation.clone(), None)
+						.map_err(mlua::Error::external)?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Run the phase scripts of any characters whose HP has fallen far enough.
+	fn enter_phases(&self, lua: &mlua::Lua) -> mlua::Result<()> {
+		for character in &self.characters {
+			loop {
+				let phase = {
+					let piece = character.borrow();
+					let Some(phase) = piece.sheet.phases.get(piece.phase) else {
+						break;
+					};
+					let max_hp = piece.sheet.stats().heart as i64;
+					if piece.hp as i64 * 100 > phase.threshold as i64 * max_hp {
+						break;
+					}
+					phase.clone()
+				};
+				let index = {
+					let mut piece = character.borrow_mut();
+					piece.phase += 1;
+					piece.phase
+				};
+				let owner = format!("{} phase {index}", character.borrow().sheet.nouns.name);
+				let globals = lua.globals();
+				globals.set("boss", character.clone())?;
+				globals.set("phase", index)?;
+				phase.on_enter.exec(lua, &owner)?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Remove any characters which have run out of HP, leaving their drops behind.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a sheet's `on_drop` script fails.
+	pub fn remove_dead(&mut self, lua: &mlua::Lua) -> mlua::Result<()> {
+		let mut downed = Vec::new();
+		let mut recovered = Vec::new();
+		let mut dead = Vec::new();
+		let bleed_out = self.rules.bleed_out;
+		let party = &self.party;
+		self.characters.retain(|character| {
+			let mut piece = character.borrow_mut();
+			if piece.hp > 0 {
+				// Healing a downed piece is enough to get it back up.
+				if piece.downed.take().is_some() {
+					recovered.push(character.clone());
+				}
+				return true;
+			}
+			let in_party = party.iter().any(|x| Rc::ptr_eq(&x.piece, character));
+			match piece.downed {
+				None if in_party && bleed_out > 0 => {
+					piece.downed = Some(bleed_out);
+					downed.push(character.clone());
+					true
+				}
+				Some(turns) if turns > 0 => true,
+				_ => {
+					piece.downed = None;
+					dead.push(character.clone());
+					false
+				}
+			}
+		});
+		for character in &downed {
+			let piece = character.borrow();
+			// The run may end here if nobody is left standing, so this can't wait for bleeding out.
+			self.stats.cause_of_death = Some(defeat_message(&piece));
+			self.console.print_danger(
+				"{Address} {verb:fall}! Revive {them} before {they} {verb:bleed} out."
+					.replace_nouns(&piece.sheet.nouns),
+			);
+		}
+		for character in &recovered {
+			self.console.print_special(
+				"{Address} gets back up.".replace_nouns(&character.borrow().sheet.nouns),
+			);
+		}
+		for character in &dead {
+			self.pieces.borrow_mut().remove(character);
+			let piece = character.borrow();
+			let name = &piece.sheet.nouns.name;
+			self.stats.record_kill(name);
+			if self.party.iter().any(|x| Rc::ptr_eq(&x.piece, character)) {
+				self.stats.cause_of_death = Some(defeat_message(&piece));
+				self.console
+					.print_danger("{Address} has died.".replace_nouns(&piece.sheet.nouns));
+				self.corpses.push(Corpse {
+					x: piece.x,
+					y: piece.y,
+					icon: piece.sheet.icon.clone(),
+					name: piece.sheet.nouns.name.to_string(),
+				});
+			}
+			let name = name.to_string();
+			drop(piece);
+			self.emit(Event::Killed(name));
+		}
+		let mut rng = rand::thread_rng();
+		for character in dead {
+			let piece = character.borrow();
+			let mut drops = piece.sheet.drops.roll(&mut rng);
+			if let Some(on_drop) = &piece.sheet.on_drop {
+				let replacement = || -> mlua::Result<Option<Vec<String>>> {
+					let globals = lua.globals();
+					globals.set("dropper", character.clone())?;
+					globals.set("drops", drops.clone())?;
+					on_drop.eval(lua, &format!("{} drops", piece.sheet.nouns.name))
+				};
+				// A broken drop script shouldn't cost the rest of the dead their drops,
+				// so the rolled drops are used instead.
+				match replacement() {
+					Ok(Some(replacement)) => drops = replacement,
+					Ok(None) => {}
+					Err(error) => self.report_script_error(&error),
+				}
+			}
+			for name in drops {
+				self.items.push(item::Piece {
+					item: Item { name },
+					x: piece.x,
+					y: piece.y,
+				});
+			}
+			if piece.sheet.boss {
+				self.console.print_special(
+					"{Address} has been defeated!".replace_nouns(&piece.sheet.nouns),
+				);
+				let id = piece.id;
+				drop(piece);
+				self.emit(Event::BossDefeated(id));
+			}
+		}
+		Ok(())
+	}
+
+	/// Use or equip an item from the inventory.
+	///
+	/// Returns false if the item couldn't be used, in which case no time should pass.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the item's script fails.
+	fn use_item(
+		&mut self,
+		lua: &mlua::Lua,
+		character: &CharacterRef,
+		index: usize,
+	) -> mlua::Result<bool> {
+		let Some(key) = self.inventory.get(index).cloned() else {
+			return Ok(false);
+		};
+		let resources: resource::Catalog = lua.globals().get("Resources")?;
+		let item = resources.get_item(&key).map_err(mlua::Error::external)?;
+		match &item.usage {
+			item::Usage::None => {
+				self.console
+					.print_unimportant(format!("The {} can't be used.", item.name));
+				Ok(false)
+			}
+			item::Usage::Consume { on_use } => {
+				self.inventory.remove(index);
+				self.console.print(
+					format!("{{Address}} uses the {}.", item.name)
+						.replace_nouns(&character.borrow().sheet.nouns),
+				);
+				lua.globals().set("user", character.clone())?;
+				on_use.exec(lua, &key)?;
+				Ok(true)
+			}
+			item::Usage::Equip { slot, bonuses } => {
+				self.inventory.remove(index);
+				let mut piece = character.borrow_mut();
+				self.console.print(
+					format!("{{Address}} equips the {}.", item.name)
+						.replace_nouns(&piece.sheet.nouns),
+				);
+				let previous = piece.equip(item::Equipped {
+					item: key,
+					slot: *slot,
+					bonuses: *bonuses,
+				});
+				if let Some(previous) = previous {
+					self.inventory.push(previous.item);
+				}
+				Ok(true)
+			}
+		}
+	}
+
+	/// Returns every item lying on the given tile.
+	pub fn get_items_at(&self, x: i32, y: i32) -> impl Iterator<Item = &item::Piece> {
+		self.items
+			.iter()
+			.filter(move |item| item.x == x && item.y == y)
+	}
+
+	pub fn get_piece(&self, id: Uuid) -> Option<&CharacterRef> {
+		self.characters
+			.iter()
+			.find(|character| character.borrow().id == id)
+	}
+
+	pub fn next_character(&self) -> &CharacterRef {
+		&self.characters[0]
+	}
+
+	/// Add a character to the turn order, respecting its current action delay.
+	pub fn add_character(&mut self, character: CharacterRef) {
+		let delay = character.borrow().action_delay;
+		let index = self
+			.characters
+			.partition_point(|x| x.borrow().action_delay <= delay);
+		self.pieces.borrow_mut().insert(&character);
+		self.characters.insert(index, character);
+	}
+
+	/// Check whether `character` could perform `action` right now, without performing it.
+	///
+	/// # Errors
+	///
+	/// Returns the first reason the action would be refused.
+	pub fn validate_action(
+		&self,
+		character: &CharacterRef,
+		action: &character::Action,
+	) -> Result<(), ActionError> {
+		if !Rc::ptr_eq(character, self.next_character()) {
+			return Err(ActionError::WrongTurn);
+		}
+		let piece = character.borrow();
+		match action {
+			character::Action::Move(dir) => {
+				let (xoff, yoff) = dir.as_offset();
+				let (x, y) = (piece.x + xoff, piece.y + yoff);
+				match self.get_character_at(x, y) {
+					Some(target)
+						if target.borrow().effective_alliance() == piece.effective_alliance()
+							&& !self.rules.allies_passable() =>
+					{
+						Err(ActionError::AttackingAlly)
+					}
+					Some(_) => Ok(()),
+					None if self
+						.current_floor
+						.map
+						.get(y, x)
+						.is_some_and(|tile| tile.is_passable()) =>
+					{
+						Ok(())
+					}
+					None => Err(ActionError::Blocked),
+				}
+			}
+			character::Action::Cast(spell)
+				if !spell.castable_by(&piece, self.level.economy.overcast_cost) =>
+			{
+				Err(ActionError::NotEnoughSp(spell.name.clone()))
+			}
+			character::Action::Delay(ally)
+				if Rc::ptr_eq(character, ally)
+					|| ally.borrow().effective_alliance() != piece.effective_alliance() =>
+			{
+				Err(ActionError::NotAnAlly)
+			}
+			character::Action::PickUp
+				if !self
+					.items
+					.iter()
+					.any(|item| item.x == piece.x && item.y == piece.y) =>
+			{
+				Err(ActionError::NothingHere)
+			}
+			character::Action::Rest
+				if self.current_floor.map.get(piece.y, piece.x) != Some(&floor::Tile::Rest) =>
+			{
+				Err(ActionError::NotRestSite)
+			}
+			character::Action::UseItem(index) | character::Action::DropItem(index)
+				if *index >= self.inventory.len() =>
+			{
+				Err(ActionError::NoSuchItem(*index))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Predict the next `length` turns, soonest first.
+	///
+	/// This assumes that every piece spends its turns on actions that take its usual speed,
+	/// so anything further out than each piece's next turn is a guess.
+	pub fn turn_forecast(&self, length: usize) -> Vec<CharacterRef> {
+		// Pieces are ordered by their delay, and then by when they were (re)inserted into the turn order.
+		let mut order: Vec<(Aut, usize, &CharacterRef)> = self
+			.characters
+			.iter()
+			.enumerate()
+			.map(|(i, character)| (character.borrow().action_delay, i, character))
+			.collect();
+		let mut inserted = order.len();
+		let mut forecast = Vec::with_capacity(length);
+		while forecast.len() < length {
+			let Some(next) = order.iter_mut().min_by_key(|(delay, i, _)| (*delay, *i)) else {
+				break;
+			};
+			forecast.push(next.2.clone());
+			next.0 += next.2.borrow().sheet.speed;
+			next.1 = inserted;
+			inserted += 1;
+		}
+		forecast
+	}
+
+	/// How many turns `character` will get before any hostile piece acts, up to `limit`.
+	pub fn turns_before_hostile(&self, character: &CharacterRef, limit: usize) -> usize {
+		let alliance = character.borrow().effective_alliance();
+		// Allies may act in between, so the forecast needs to look further ahead than `limit`.
+		self.turn_forecast(limit * self.characters.len())
+			.into_iter()
+			.take_while(|piece| piece.borrow().effective_alliance() == alliance)
+			.filter(|piece| Rc::ptr_eq(piece, character))
+			.take(limit)
+			.count()
+	}
+
+	/// Every unoccupied tile `character` could walk to before a hostile piece acts,
+	/// and every hostile piece it could attack along the way.
+	///
+	/// At most `limit` turns are considered.
+	pub fn move_preview(
+		&self,
+		character: &CharacterRef,
+		limit: usize,
+	) -> (Vec<(i32, i32)>, Vec<CharacterRef>) {
+		let turns = self.turns_before_hostile(character, limit) as u32;
+		let (x, y, alliance) = {
+			let piece = character.borrow();
+			(piece.x, piece.y, piece.effective_alliance())
+		};
+		let distances = ai::distance_map(&self.current_floor, [(x, y)].into_iter());
+		let reachable: Vec<(i32, i32)> = distances
+			.indexed_iter()
+			.filter(|(_, distance)| distance.is_some_and(|distance| distance <= turns))
+			.map(|((y, x), _)| (x as i32, y as i32))
+			.filter(|&(x, y)| self.get_character_at(x, y).is_none())
+			.collect();
+		// Attacking takes a turn of its own, so it has to happen from a tile reached with one to spare.
+		let attackable = self
+			.characters
+			.iter()
+			.filter(|target| target.borrow().effective_alliance() != alliance)
+			.filter(|target| {
+				let target = target.borrow();
+				character::OrdDir::ALL.into_iter().any(|dir| {
+					let (xoff, yoff) = dir.as_offset();
+					distances
+						.get(target.y + yoff, target.x + xoff)
+						.copied()
+						.flatten()
+						.is_some_and(|distance| distance < turns)
+				})
+			})
+			.cloned()
+			.collect();
+		(reachable, attackable)
+	}
+
+	/// End the next character's turn, delaying its next action by `delay`.
+	pub fn end_turn(&mut self, delay: Aut) {
+		let character = self.characters.remove(0);
+		character.borrow_mut().action_delay = delay;
+		self.add_character(character);
+		self.next_turn();
+	}
+
+	/// End the turn of whichever piece was waiting on a target, if any.
+	fn stop_aiming(&mut self) {
+		if let Some(aiming) = self.aiming.take() {
+			if Rc::ptr_eq(self.next_character(), &aiming.actor) {
+				self.end_turn(aiming.delay);
+			}
+		}
+	}
+
+	/// Move the next character in the turn order to directly after `ally`.
+	///
+	/// Returns false (and does nothing) if `ally` is not an ally of the next character.
+	pub fn delay_until_after(&mut self, ally: &CharacterRef) -> bool {
+		let character = self.next_character();
+		if Rc::ptr_eq(character, ally)
+			|| character.borrow().effective_alliance() != ally.borrow().effective_alliance()
+		{
+			return false;
+		}
+		let Some(index) = self.characters.iter().position(|x| Rc::ptr_eq(x, ally)) else {
+			return false;
+		};
+		let character = self.characters.remove(0);
+		character.borrow_mut().action_delay = ally.borrow().action_delay;
+		// `index` now points past the ally, since the next character was in front of it.
+		self.characters.insert(index, character);
+		self.next_turn();
+		true
+	}
+
+	/// Advance time until the next character is ready to act, and start its turn.
+	fn next_turn(&mut self) {
+		let elapsed = self.next_character().borrow().action_delay;
+		for character in &self.characters {
+			character.borrow_mut().action_delay -= elapsed;
+		}
+		self.hunger += elapsed;
+		self.stats.time += elapsed;
+		for timer in &mut self.timers {
+			timer.remaining = timer.remaining.saturating_sub(elapsed);
+		}
+		let mut next_character = self.next_character().borrow_mut();
+		let was_charmed = next_character.is_charmed();
+		next_character.new_turn();
+		next_character.restore_sp(self.level.economy.sp_per_turn);
+		if was_charmed && !next_character.is_charmed() {
+			self.console.print_special(
+				"{Address} comes to {their} senses.".replace_nouns(&next_character.sheet.nouns),
+			);
+		}
+	}
+
+	/// Iterate over every character sharing an alliance with `character`, excluding itself.
+	pub fn allies_of<'a>(
+		&'a self,
+		character: &'a CharacterRef,
+	) -> impl Iterator<Item = &'a CharacterRef> {
+		let alliance = character.borrow().effective_alliance();
+		self.characters.iter().filter(move |x| {
+			!Rc::ptr_eq(x, character) && x.borrow().effective_alliance() == alliance
+		})
+	}
+
+	/// Returns true if a piece of another alliance is within `range` tiles of `character`.
+	pub fn hostile_within(&self, character: &CharacterRef, range: u32) -> bool {
+		self.pieces
+			.borrow()
+			.nearest_hostile(character, range)
+			.is_some()
+	}
+
+	/// The camera of the focused party member.
+	pub fn camera(&self) -> draw::Camera {
+		self.party
+			.get(self.focus)
+			.map(|member| member.draw_state.camera)
+			.unwrap_or_default()
+	}
+
+	/// Focus the next character if it's a party member the player is controlling.
+	pub fn focus_next_character(&mut self) {
+		let next_character = self.next_character();
+		if !next_character.borrow().controlled_by_player() {
+			return;
+		}
+		if let Some(i) = self
+			.party
+			.iter()
+			.position(|member| Rc::ptr_eq(&member.piece, next_character))
+		{
+			self.focus = i;
+		}
+	}
+
+	/// Move the focus to the next party member that's still standing.
+	pub fn cycle_focus(&mut self) {
+		let len = self.party.len();
+		for step in 1..=len {
+			let i = (self.focus + step) % len;
+			if self.party[i].piece.borrow().hp > 0 {
+				self.focus = i;
+				return;
+			}
+		}
+	}
+
+	/// Give or take away the player's control of the focused party member.
+	///
+	/// The player always keeps control of at least one living party member.
+	pub fn toggle_control(&mut self) {
+		let Some(member) = self.party.get(self.focus) else {
+			return;
+		};
+		let mut piece = member.piece.borrow_mut();
+		if !piece.player_controlled {
+			piece.player_controlled = true;
+			let message =
+				"{Address} {are} now under your control.".replace_nouns(&piece.sheet.nouns);
+			drop(piece);
+			self.console.print_unimportant(message);
+			return;
+		}
+		let others = self
+			.party
+			.iter()
+			.filter(|other| !Rc::ptr_eq(&other.piece, &member.piece))
+			.any(|other| {
+				let other = other.piece.borrow();
+				other.player_controlled && other.hp > 0
+			});
+		if others {
+			piece.player_controlled = false;
+			let message = "{Address} will now act independently.".replace_nouns(&piece.sheet.nouns);
+			drop(piece);
+			self.console.print_unimportant(message);
+		} else {
+			drop(piece);
+			self.console
+				.print_unimportant("You need to control at least one party member.".into());
+		}
+	}
+
+	/// Party members which aren't controlled by the player, and so can be given orders.
+	pub fn followers(&self) -> Vec<CharacterRef> {
+		self.party
+			.iter()
+			.filter(|member| !member.piece.borrow().player_controlled)
+			.map(|member| member.piece.clone())
+			.collect()
+	}
+
+	/// Every piece the next character can see, including itself, nearest first.
+	pub fn visible_pieces(&self) -> Vec<CharacterRef> {
+		let (x, y) = {
+			let piece = self.next_character().borrow();
+			(piece.x, piece.y)
+		};
+		let mut pieces: Vec<_> = self
+			.characters
+			.iter()
+			.filter(|piece| {
+				let piece = piece.borrow();
+				self.current_floor.line_of_sight((x, y), (piece.x, piece.y))
+			})
+			.cloned()
+			.collect();
+		pieces.sort_by_key(|piece| {
+			let piece = piece.borrow();
+			(piece.x - x)
+				.unsigned_abs()
+				.max((piece.y - y).unsigned_abs())
+		});
+		pieces
+	}
+
+	/// Every piece hostile to `caster` within `range` of and in sight of `origin`, nearest to the caster first.
+	///
+	/// Ties go to whichever piece is most threatened by the caster.
+	pub fn targets_within(
+		&self,
+		caster: &CharacterRef,
+		origin: (i32, i32),
+		range: Option<u32>,
+	) -> Vec<CharacterRef> {
+		let (x, y, alliance) = {
+			let piece = caster.borrow();
+			(piece.x, piece.y, piece.effective_alliance())
+		};
+		let mut targets: Vec<_> = self
+			.characters
+			.iter()
+			.filter(|piece| {
+				let piece = piece.borrow();
+				piece.effective_alliance() != alliance
+					&& self
+						.current_floor
+						.within_range(origin, (piece.x, piece.y), range)
+			})
+			.cloned()
+			.collect();
+		targets.sort_by_key(|target| {
+			let target = target.borrow();
+			let distance = (target.x - x)
+				.unsigned_abs()
+				.max((target.y - y).unsigned_abs());
+			(distance, std::cmp::Reverse(target.threat.get(caster)))
+		});
+		targets
+	}
+
+	/// Targets within `range` of `origin` for whichever piece is waiting on the player to pick one.
+	pub fn aiming_targets(&self, origin: (i32, i32), range: Option<u32>) -> Vec<CharacterRef> {
+		self.aiming
+			.as_ref()
+			.map(|aiming| self.targets_within(&aiming.actor, origin, range))
+			.unwrap_or_default()
+	}
+
+	pub fn get_character_at(&self, x: i32, y: i32) -> Option<CharacterRef> {
+		self.pieces.borrow().pieces_at(x, y).next().cloned()
+	}
+
+	pub fn apply_vault(
+		&mut self,
+		x: i32,
+		y: i32,
+		vault: &Vault,
+		resources: &resource::Manager,
+	) -> Result<()> {
+		self.current_floor.blit_vault(x as usize, y as usize, vault);
+		self.query.set_floor(&self.current_floor);
+		for (xoff, yoff, sheet_name) in &vault.characters {
+			let piece = character::Piece {
+				x: x + xoff,
+				y: y + yoff,
+				..character::Piece::new(
+					self.rules
+						.enemy_sheet(resources.get_sheet(sheet_name)?.clone()),
+					resources,
+				)?
+			};
+			self.add_character(Rc::new(RefCell::new(piece)));
+		}
+		Ok(())
+	}
+}
+
+/// Used to "escape" the world and request extra information, such as inputs.
+pub enum ActionRequest<'lua> {
+	/// This callback will be called in place of `pop_action` once a position is selected.
+	///
+	/// Positions more than `range` tiles from the cursor's origin (or out of its sight) can't be selected.
+	BeginCursor {
+		x: i32,
+		y: i32,
+		range: Option<u32>,
+		callback: mlua::Thread<'lua>,
+	},
+}
+
+impl<'lua> ActionRequest<'lua> {
+	fn poll(
+		lua: &'lua mlua::Lua,
+		thread: mlua::Thread<'lua>,
+		args: impl mlua::IntoLuaMulti<'lua>,
+	) -> mlua::Result<Option<ActionRequest<'lua>>> {
+		#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+		#[serde(tag = "type")]
+		pub enum LuaActionRequest {
+			Cursor {
+				x: i32,
+				y: i32,
+				#[serde(default)]
+				range: Option<u32>,
+			},
+		}
+
+		let action: Option<LuaActionRequest> = lua.from_value(thread.resume(args)?)?;
+		Ok(action.map(
+			|LuaActionRequest::Cursor { x, y, range }| ActionRequest::BeginCursor {
+				x,
+				y,
+				range,
+				callback: thread,
+			},
+		))
+	}
+}
+
+impl Manager {
+	fn pop_action<'lua>(
+		&mut self,
+		lua: &'lua mlua::Lua,
+	) -> mlua::Result<Option<ActionRequest<'lua>>> {
+		let next_character = self.next_character().clone();
+
+		// Downed pieces can't act; their turns are spent bleeding out.
+		let bleeding = {
+			let mut piece = next_character.borrow_mut();
+			if let Some(turns) = &mut piece.downed {
+				*turns = turns.saturating_sub(1);
+				let turns = *turns;
+				piece.next_action = None;
+				piece.chase_point = None;
+				piece.autopilot = None;
+				Some(turns)
+			} else {
+				None
+			}
+		};
+		if let Some(turns) = bleeding {
+			if turns > 0 {
+				self.console.print_danger(
+					format!("{{Address}} {{are}} bleeding out ({turns} turns left).")
+						.replace_nouns(&next_character.borrow().sheet.nouns),
+				);
+			}
+			self.end_turn(TURN);
+			return Ok(None);
+		}
+
+		let considered = {
+			let piece = next_character.borrow();
+			if !piece.controlled_by_player() && piece.next_action.is_none() {
+				// Charmed pieces ignore their orders.
+				let order = piece.order.filter(|_| !piece.is_charmed());
+				let action = match (order, &piece.sheet.ai) {
+					(Some(order), _) => {
+						let _span = debug_span!("consider", order = ?order).entered();
+						drop(piece);
+						order
+							.profile()
+							.consider(self, &next_character, &mut rand::thread_rng())
+					}
+					(None, Some(profile)) => {
+						let _span = debug_span!("consider", profile = %profile).entered();
+						let resources: resource::Catalog = lua.globals().get("Resources")?;
+						let profile = resources
+							.get_ai(profile)
+							.map_err(mlua::Error::external)?
+							.clone();
+						drop(piece);
+						profile.consider(self, &next_character, &mut rand::thread_rng())
+					}
+					(None, None) => character::Action::Wait(TURN),
+				};
+				Some(action)
+			} else if piece.next_action.is_none() && piece.chase_point.is_some() {
+				drop(piece);
+				self.chase(&next_character)
+			} else if piece.next_action.is_none() && piece.autopilot.is_some() {
+				drop(piece);
+				self.autopilot(&next_character)
+			} else {
+				None
+			}
+		};
+		let action = {
+			let mut next_character = next_character.borrow_mut();
+			if considered.is_some() {
+				next_character.next_action = considered;
+			}
+			next_character.next_action.take()
+		};
+		let Some(action) = action else {
+			return Ok(None);
+		};
+		let delay = action.delay(&next_character.borrow());
+		let _span = info_span!(
+			"action",
+			actor = %next_character.borrow().sheet.nouns.name,
+			kind = action.kind(),
+		)
+		.entered();
+
+		// Moves are left to `move_piece`, so that bumping into walls still spends a turn.
+		if !matches!(action, character::Action::Move(_)) {
+			if let Err(error) = self.validate_action(&next_character, &action) {
+				let mut piece = next_character.borrow_mut();
+				// Whatever chose this action would only choose it again.
+				piece.chase_point = None;
+				piece.autopilot = None;
+				if piece.controlled_by_player() {
+					self.console
+						.print_unimportant(error.to_string().replace_nouns(&piece.sheet.nouns));
+				} else {
+					// Nobody is around to pick something else, so the turn is spent waiting.
+					warn!(
+						"{} chose an invalid action: {error}",
+						piece.sheet.nouns.name
+					);
+					drop(piece);
+					self.end_turn(TURN);
+				}
+				return Ok(None);
+			}
+		}
+
+		match action {
+			character::Action::Move(dir) => {
+				let request = self.move_piece(lua, &next_character, dir);
+				self.aim(lua, &next_character, None, delay, request)
+			}
+			character::Action::Cast(spell) => {
+				self.emit(Event::SpellCast(next_character.borrow().id));
+				let request = self.cast_spell(lua, &next_character, &spell);
+				self.aim(lua, &next_character, Some(spell), delay, request)
+			}
+			character::Action::Wait(_) => {
+				self.end_turn(delay);
+				Ok(None)
+			}
+			character::Action::Delay(ally) => {
+				if !self.delay_until_after(&ally) {
+					self.console
+						.print_unimportant("You can only delay your turn for an ally.".into());
+				}
+				Ok(None)
+			}
+			character::Action::PickUp => {
+				let (x, y) = {
+					let piece = next_character.borrow();
+					(piece.x, piece.y)
+				};
+				let mut picked_up = 0;
+				let mut eaten = 0;
+				let attrition = &self.level.attrition;
+				self.items.retain(|item| {
+					if item.x != x || item.y != y {
+						return true;
+					}
+					match attrition.foods.get(&item.item.name) {
+						Some(supplies) if attrition.enabled => eaten += supplies,
+						_ => self.inventory.push(item.item.name.clone()),
+					}
+					picked_up += 1;
+					false
+				});
+				if eaten > 0 {
+					self.supplies += eaten;
+					self.console
+						.print(format!("The party gains {eaten} supplies."));
+				}
+				if picked_up == 0 {
+					self.console
+						.print_unimportant("There's nothing on the ground here.".into());
+				} else {
+					self.console.print(
+						format!("{{Address}} picks up {picked_up} item(s).")
+							.replace_nouns(&next_character.borrow().sheet.nouns),
+					);
+					self.end_turn(delay);
+				}
+				Ok(None)
+			}
+			character::Action::Rest => {
+				self.camp(lua, &next_character, delay)?;
+				Ok(None)
+			}
+			character::Action::UseItem(index) => {
+				if self.use_item(lua, &next_character, index)? {
+					self.end_turn(delay);
+				}
+				Ok(None)
+			}
+			character::Action::DropItem(index) => {
+				if index < self.inventory.len() {
+					let name = self.inventory.remove(index);
+					let piece = next_character.borrow();
+					self.items.push(item::Piece {
+						item: Item { name },
+						x: piece.x,
+						y: piece.y,
+					});
+					self.console
+						.print("{Address} drops an item.".replace_nouns(&piece.sheet.nouns));
+					drop(piece);
+					self.end_turn(delay);
+				}
+				Ok(None)
+			}
+			character::Action::Hold(range) => {
+				if self.hostile_within(&next_character, range) {
+					// Holding is free if it's interrupted right away,
+					// so the piece can act normally this turn.
+					self.console.print_unimportant(
+						"{Address} stops holding as an enemy approaches."
+							.replace_nouns(&next_character.borrow().sheet.nouns),
+					);
+				} else {
+					next_character.borrow_mut().next_action = Some(character::Action::Hold(range));
+					self.end_turn(delay);
+				}
+				Ok(None)
+			}
+		}
+	}
+
+	/// Returns the next step towards `character`'s chase point,
+	/// or clears the chase point if there's no reason to keep walking.
+	fn chase(&self, character: &CharacterRef) -> Option<character::Action> {
+		let (x, y, alliance, point) = {
+			let piece = character.borrow();
+			(
+				piece.x,
+				piece.y,
+				piece.effective_alliance(),
+				piece.chase_point?,
+			)
+		};
+		// Chasing a hostile piece means walking up to it and attacking, so it shouldn't be interrupted.
+		let chasing_hostile = self
+			.get_character_at(point.0, point.1)
+			.is_some_and(|x| x.borrow().effective_alliance() != alliance);
+		let interrupted = (x, y) == point
+			|| (!chasing_hostile && self.hostile_within(character, CHASE_INTERRUPT_RANGE));
+		let step = if interrupted {
+			None
+		} else {
+			let distances = ai::distance_map(&self.current_floor, [point].into_iter());
+			self.downhill((x, y), &distances, Some(point))
+		};
+		match step {
+			Some(dir) => Some(character::Action::Move(dir)),
+			None => {
+				character.borrow_mut().chase_point = None;
+				None
+			}
+		}
+	}
+
+	/// The open step from `from` that gets closest to the bottom of `distances`.
+	///
+	/// Steps onto other pieces are only allowed if they're standing on `goal`.
+	fn downhill(
+		&self,
+		(x, y): (i32, i32),
+		distances: &Grid<Option<u32>>,
+		goal: Option<(i32, i32)>,
+	) -> Option<OrdDir> {
+		let here = distances.get(y, x).copied().flatten();
+		OrdDir::ALL
+			.into_iter()
+			.filter_map(|dir| {
+				let (xoff, yoff) = dir.as_offset();
+				let (x, y) = (x + xoff, y + yoff);
+				let distance = distances.get(y, x).copied().flatten()?;
+				let open = self
+					.current_floor
+					.map
+					.get(y, x)
+					.is_some_and(|tile| tile.is_passable())
+					&& (goal == Some((x, y)) || self.get_character_at(x, y).is_none());
+				(open && here.is_some_and(|here| distance < here)).then_some((distance, dir))
+			})
+			.min_by_key(|(distance, _)| *distance)
+			.map(|(_, dir)| dir)
+	}
+
+	/// Mark every tile near a player-controlled party member, and in their line of sight, as explored.
+	fn look_around(&mut self) {
+		if self.explored.size() != self.current_floor.map.size() {
+			let (rows, cols) = self.current_floor.map.size();
+			self.explored = Grid::init(rows, cols, false);
+		}
+		for member in &self.party {
+			let piece = member.piece.borrow();
+			if !piece.controlled_by_player() || piece.hp <= 0 {
+				continue;
+			}
+			for y in piece.y - VISION_RANGE..=piece.y + VISION_RANGE {
+				for x in piece.x - VISION_RANGE..=piece.x + VISION_RANGE {
+					if self.explored.get(y, x) == Some(&false)
+						&& self.current_floor.line_of_sight((piece.x, piece.y), (x, y))
+					{
+						self.explored[(y as usize, x as usize)] = true;
+					}
+				}
+			}
+		}
+	}
+
+	/// The closest passable tile to `from` which hasn't been explored, if any can be reached.
+	pub fn nearest_unexplored(&self, from: (i32, i32)) -> Option<(i32, i32)> {
+		let distances = ai::distance_map(&self.current_floor, [from].into_iter());
+		distances
+			.indexed_iter()
+			.filter(|((y, x), _)| self.explored.get(*y, *x) == Some(&false))
+			.filter_map(|((y, x), distance)| {
+				Some((distance.filter(|x| *x > 0)?, (x as i32, y as i32)))
+			})
+			.min_by_key(|(distance, _)| *distance)
+			.map(|(_, tile)| tile)
+	}
+
+	/// How many items `character` has a line of sight to.
+	fn visible_items(&self, character: &CharacterRef) -> usize {
+		let piece = character.borrow();
+		self.items
+			.iter()
+			.filter(|item| {
+				self.current_floor
+					.line_of_sight((piece.x, piece.y), (item.x, item.y))
+			})
+			.count()
+	}
+
+	/// How many hostile pieces `character` has a line of sight to.
+	fn visible_hostiles(&self, character: &CharacterRef) -> usize {
+		let piece = character.borrow();
+		let alliance = piece.effective_alliance();
+		self.characters
+			.iter()
+			.filter(|other| {
+				let other = other.borrow();
+				other.effective_alliance() != alliance
+					&& self
+						.current_floor
+						.line_of_sight((piece.x, piece.y), (other.x, other.y))
+			})
+			.count()
+	}
+
+	/// Returns the next action of `character`'s autopilot,
+	/// or clears the autopilot if it's finished or interrupted.
+	fn autopilot(&mut self, character: &CharacterRef) -> Option<character::Action> {
+		use character::{Action, Autopilot};
+
+		let autopilot = character.borrow().autopilot.clone()?;
+		let hostiles = self.visible_hostiles(character);
+		let (action, autopilot, stop) = match autopilot {
+			Autopilot::Explore {
+				interruptions,
+				mut seen,
+				mut items,
+				mut hp,
+			} => {
+				let (x, y, current_hp) = {
+					let piece = character.borrow();
+					(piece.x, piece.y, piece.hp)
+				};
+				let hurt = hp.is_some_and(|hp| current_hp < hp);
+				hp = Some(current_hp);
+				let step = self.nearest_unexplored((x, y)).and_then(|target| {
+					let distances = ai::distance_map(&self.current_floor, [target].into_iter());
+					self.downhill((x, y), &distances, None)
+				});
+				if appeared(&mut seen, hostiles) && interruptions.enemies {
+					(
+						None,
+						None,
+						Some("{Address} stops exploring as an enemy comes into view."),
+					)
+				} else if appeared(&mut items, self.visible_items(character)) && interruptions.items
+				{
+					(
+						None,
+						None,
+						Some("{Address} stops exploring to look at an item."),
+					)
+				} else if hurt && interruptions.damage {
+					(
+						None,
+						None,
+						Some("{Address} stops exploring after getting hurt."),
+					)
+				} else if let Some(dir) = step {
+					(
+						Some(Action::Move(dir)),
+						Some(Autopilot::Explore {
+							interruptions,
+							seen,
+							items,
+							hp,
+						}),
+						None,
+					)
+				} else {
+					(None, None, Some("There's nothing left to explore nearby."))
+				}
+			}
+			Autopilot::Fight { mut seen } => {
+				let piece = character.borrow();
+				let alliance = piece.effective_alliance();
+				let adjacent = OrdDir::ALL.into_iter().find(|dir| {
+					let (xoff, yoff) = dir.as_offset();
+					self.get_character_at(piece.x + xoff, piece.y + yoff)
+						.is_some_and(|other| other.borrow().effective_alliance() != alliance)
+				});
+				if appeared(&mut seen, hostiles) {
+					(
+						None,
+						None,
+						Some("{Address} stops fighting as another enemy appears."),
+					)
+				} else if piece.hp * 2 < piece.stats().heart as i32 {
+					(
+						None,
+						None,
+						Some("{Address} stops fighting, too hurt to go on."),
+					)
+				} else if let Some(dir) = adjacent {
+					(
+						Some(Action::Move(dir)),
+						Some(Autopilot::Fight { seen }),
+						None,
+					)
+				} else {
+					(None, None, Some("{Address} has nobody left to fight."))
+				}
+			}
+			Autopilot::Recast {
+				spell,
+				target,
+				mut seen,
+			} => {
+				let piece = character.borrow();
+				if appeared(&mut seen, hostiles) {
+					(
+						None,
+						None,
+						Some("{Address} stops casting as another enemy appears."),
+					)
+				} else if target.is_some_and(|target| self.get_piece(target).is_none()) {
+					(None, None, Some("{Address}'s target is gone."))
+				} else if !spell.castable_by(&piece, self.level.economy.overcast_cost) {
+					(None, None, Some("{Address} is too tired to keep casting."))
+				} else {
+					(
+						Some(Action::Cast(spell.clone())),
+						Some(Autopilot::Recast {
+							spell,
+							target,
+							seen,
+						}),
+						None,
+					)
+				}
+			}
+		};
+		let mut piece = character.borrow_mut();
+		piece.autopilot = autopilot;
+		if let Some(stop) = stop {
+			self.console
+				.print_unimportant(stop.replace_nouns(&piece.sheet.nouns));
+		}
+		action
+	}
+
+	/// Answers an action's targeting requests on the actor's behalf where it can,
+	/// ending its turn once nothing is left to aim.
+	///
+	/// Pieces recasting at a known target aim at it, and pieces the player isn't controlling aim at the nearest hostile
+	/// (or, for beneficial spells, the most wounded ally).
+	/// Otherwise, the turn is held until the player picks a target.
+	fn aim<'lua>(
+		&mut self,
+		lua: &'lua mlua::Lua,
+		actor: &CharacterRef,
+		spell: Option<Rc<Spell>>,
+		delay: Aut,
+		mut request: mlua::Result<Option<ActionRequest<'lua>>>,
+	) -> mlua::Result<Option<ActionRequest<'lua>>> {
+		let mut target = None;
+		while let Ok(Some(ActionRequest::BeginCursor {
+			x,
+			y,
+			range,
+			callback,
+		})) = request
+		{
+			let Some(aimed) = self.auto_target(actor, spell.as_deref(), x, y, range) else {
+				if actor.borrow().controlled_by_player() {
+					self.aiming = Some(Aiming {
+						actor: actor.clone(),
+						spell,
+						delay,
+					});
+					return Ok(Some(ActionRequest::BeginCursor {
+						x,
+						y,
+						range,
+						callback,
+					}));
+				}
+				// Nobody in reach; the action fizzles.
+				request = Ok(None);
+				break;
+			};
+			target = Some(aimed.borrow().id);
+			request = ActionRequest::poll(lua, callback, aimed);
+		}
+		if let Some(spell) = spell {
+			if actor.borrow().autopilot.is_none() {
+				actor.borrow_mut().last_cast = Some((spell, target));
+			}
+		}
+		// Whatever turn a broken script was part of has still been spent.
+		self.end_turn(delay);
+		request
+	}
+
+	/// Picks a target within `range` of `(x, y)` for `actor`, if it shouldn't be left to the player.
+	fn auto_target(
+		&self,
+		actor: &CharacterRef,
+		spell: Option<&Spell>,
+		x: i32,
+		y: i32,
+		range: Option<u32>,
+	) -> Option<CharacterRef> {
+		let recast_target = match &actor.borrow().autopilot {
+			Some(character::Autopilot::Recast {
+				target: Some(target),
+				..
+			}) => Some(self.get_piece(*target).cloned()),
+			_ => None,
+		};
+		match recast_target {
+			Some(Some(target))
+				if self.current_floor.within_range(
+					(x, y),
+					(target.borrow().x, target.borrow().y),
+					range,
+				) =>
+			{
+				Some(target)
+			}
+			Some(_) => {
+				// The player has to pick a new target for themselves.
+				let mut actor = actor.borrow_mut();
+				actor.autopilot = None;
+				self.console.print_unimportant(
+					"{Address}'s target is out of range.".replace_nouns(&actor.sheet.nouns),
+				);
+				None
+			}
+			None if actor.borrow().controlled_by_player() => None,
+			None if spell.is_some_and(|spell| spell.beneficial) => {
+				let alliance = actor.borrow().effective_alliance();
+				self.characters
+					.iter()
+					.filter(|ally| !Rc::ptr_eq(ally, actor))
+					.filter(|ally| {
+						let ally = ally.borrow();
+						ally.effective_alliance() == alliance
+							&& self
+								.current_floor
+								.within_range((x, y), (ally.x, ally.y), range)
+					})
+					.min_by_key(|ally| {
+						let ally = ally.borrow();
+						let max_hp = ally.sheet.stats().heart.max(1) as i64;
+						ally.hp as i64 * 100 / max_hp
+					})
+					.cloned()
+			}
+			None => self.targets_within(actor, (x, y), range).into_iter().next(),
+		}
+	}
+
+	fn cast_spell<'lua>(
+		&self,
+		lua: &'lua mlua::Lua,
+		caster: &CharacterRef,
+		spell: &Spell,
+	) -> mlua::Result<Option<ActionRequest<'lua>>> {
+		// TODO: this is awful. just move targeting into scripts.
+		match spell.parameters.clone() {
+			spell::Parameters::Target {
+				magnitude: _,
+				pierce_threshold,
+				range,
+			} => {
+				let spell::Outlook {
+					affinity,
+					max_magnitude: magnitude,
+					..
+				} = spell.outlook(&caster.borrow(), None, self.level.economy.overcast_cost);
+
+				let globals = lua.globals();
+
+				// Create a reference for the callback to use.
+				globals.set("caster", caster.clone())?;
+				// Maybe these should be members of the spell?
+				globals.set("magnitude", magnitude)?;
+				globals.set("pierce_threshold", pierce_threshold)?;
+				globals.set("range", range)?;
+				globals.set("level", spell.level)?;
+				globals.set("affinity", affinity)?;
+
+				if let Some(sound) = &spell.sound {
+					self.audio.play(sound);
+				}
+				let value: mlua::Value = spell.on_cast.eval(lua, &spell.name)?;
+
+				match value {
+					mlua::Value::Thread(thread) => ActionRequest::poll(lua, thread, ()),
+
+					mlua::Value::Nil => Ok(None),
+					_ => {
+						error!("unexpected return value");
+						Ok(None)
+					}
+				}
+			}
+		}
+	}
+
+	/// # Errors
 	///
 	/// Returns an error if the target is an ally, or if the user has no attacks.
 	pub fn attack_piece<'lua>(
@@ -402,7 +2971,7 @@ impl Manager {
 			return Ok(None);
 		};
 
-		if target.borrow().alliance == user.borrow().alliance {
+		if target.borrow().effective_alliance() == user.borrow().effective_alliance() {
 			self.console
 				.print_unimportant("You cannot attack your allies.".into());
 			return Ok(None);
@@ -411,20 +2980,15 @@ impl Manager {
 		// Calculate damage
 		let magnitude = u32::evalv(&attack.magnitude, &*user.borrow());
 
-		let chunk = lua.load(attack.on_use.contents());
-		let name = match &attack.on_use {
-			script::MaybeInline::Inline(_) => {
-				format!("{} (inline)", attack.name)
-			}
-			script::MaybeInline::Path(script::Script { path, contents: _ }) => path.clone(),
-		};
-		let globals = lua.globals().clone();
-
+		let globals = lua.globals();
 		globals.set("user", user.clone())?;
 		globals.set("target", target.clone())?;
 		globals.set("magnitude", magnitude)?;
 
-		let value: mlua::Value = chunk.set_name(name).set_environment(globals).eval()?;
+		if let Some(sound) = &attack.sound {
+			self.audio.play(sound);
+		}
+		let value: mlua::Value = attack.on_use.eval(lua, &attack.name)?;
 
 		match value {
 			mlua::Value::Thread(thread) => {
@@ -442,7 +3006,7 @@ impl Manager {
 	///
 	/// Fails if a wall or void is in the way, or if an implicit attack failed.
 	pub fn move_piece<'lua>(
-		&self,
+		&mut self,
 		lua: &'lua mlua::Lua,
 		character: &CharacterRef,
 		dir: OrdDir,
@@ -458,15 +3022,45 @@ impl Manager {
 		// There's a really annoying phenomenon in Pokémon Mystery Dungeon where you can't hit ghosts that are inside of walls.
 		// I think that this is super lame, so the attack check comes before any movement.
 		if let Some(target_ref) = self.get_character_at(x, y) {
-			return self.attack_piece(lua, character, target_ref);
+			let allied =
+				target_ref.borrow().effective_alliance() == character.borrow().effective_alliance();
+			if !allied || !self.rules.allies_passable() {
+				return self.attack_piece(lua, character, &target_ref);
+			}
+			if self.rules.swap_allies {
+				let from = {
+					let mut character = character.borrow_mut();
+					let from = (character.x, character.y);
+					character.x = x;
+					character.y = y;
+					from
+				};
+				{
+					let mut target = target_ref.borrow_mut();
+					target.x = from.0;
+					target.y = from.1;
+				}
+				self.pieces.borrow_mut().relocate(character, from);
+				self.pieces.borrow_mut().relocate(&target_ref, (x, y));
+				self.emit(Event::Moved(character.borrow().id));
+				self.emit(Event::Moved(target_ref.borrow().id));
+				return Ok(None);
+			}
+			// Without collision, allies are simply walked through.
 		}
 
 		let tile = self.current_floor.map.get(y, x);
 		match tile {
-			Some(Tile::Floor) | Some(Tile::Exit) => {
-				let mut character = character.borrow_mut();
-				character.x = x;
-				character.y = y;
+			Some(Tile::Floor) | Some(Tile::Exit) | Some(Tile::Rest) => {
+				let from = {
+					let mut character = character.borrow_mut();
+					let from = (character.x, character.y);
+					character.x = x;
+					character.y = y;
+					from
+				};
+				self.pieces.borrow_mut().relocate(character, from);
+				self.emit(Event::Moved(character.borrow().id));
 				Ok(None)
 			}
 			Some(Tile::Wall) => {